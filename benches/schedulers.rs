@@ -0,0 +1,73 @@
+//! Compares the global schedulers on a gas of elastic particles in a periodic box.
+//!
+//! Run `cargo bench --bench schedulers`, optionally followed by `-- <N>...`
+//!   to give the numbers of particles (10^4, 10^5, and 10^6 by default).
+//! All schedulers process the same events, and thus only the elapsed times differ.
+//!
+//! N.B.: 10^6 particles take several gigabytes of memory, mostly occupied by the queued events.
+
+use std::time::Instant;
+
+use collision::simulator::particle::get_volume;
+use collision::simulator::{Boundary, Extrema, SchedulerKind, SimulationConfig, Simulator, NDIMS};
+
+const SCHEDULERS: [(&str, SchedulerKind); 4] = [
+    ("binary heap", SchedulerKind::BinaryHeap),
+    ("calendar queue", SchedulerKind::CalendarQueue),
+    ("tournament tree", SchedulerKind::TournamentTree),
+    ("pairing heap", SchedulerKind::PairingHeap),
+];
+/// Volume fraction of the particles.
+const VOLUME_FRACTION: f64 = 0.3;
+/// Numbers of the events per particle, to relax the initial condition and to be measured.
+const NEVENTS: (usize, usize) = (2, 8);
+
+fn configure(nparticles: usize, scheduler: SchedulerKind) -> SimulationConfig {
+    let radius: f64 = 0.5;
+    let volume: f64 = nparticles as f64 * get_volume(radius) / VOLUME_FRACTION;
+    SimulationConfig::builder()
+        .lengths([volume.powf(1. / NDIMS as f64); NDIMS])
+        .nparticles(nparticles)
+        .radius(radius)
+        .boundaries(std::array::from_fn(|_| Extrema::both(Boundary::Periodic)))
+        .restitution_coefficient(1.)
+        .scalar_transports(Vec::new())
+        .buoyancy(None)
+        .scheduler(scheduler)
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    // the flags given by cargo are ignored
+    let mut nparticles: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter(|arg: &String| !arg.starts_with('-'))
+        .map(|arg: String| arg.parse().expect("number of particles"))
+        .collect();
+    if nparticles.is_empty() {
+        nparticles = vec![10_000, 100_000, 1_000_000];
+    }
+    println!(
+        "{:>10} {:>16} {:>12} {:>10} {:>12}",
+        "particles", "scheduler", "events", "time [s]", "events / s"
+    );
+    for &nparticles in nparticles.iter() {
+        for (name, scheduler) in SCHEDULERS {
+            let mut simulator = Simulator::new(configure(nparticles, scheduler)).unwrap();
+            simulator.step_events(NEVENTS.0 * nparticles);
+            let nevents: usize = NEVENTS.1 * nparticles;
+            let start = Instant::now();
+            simulator.step_events(nevents);
+            let elapsed: f64 = start.elapsed().as_secs_f64();
+            println!(
+                "{:>10} {:>16} {:>12} {:>10.3} {:>12.3e}",
+                nparticles,
+                name,
+                nevents,
+                elapsed,
+                nevents as f64 / elapsed
+            );
+        }
+    }
+}
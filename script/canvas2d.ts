@@ -1,4 +1,4 @@
-import { Collision, InitOutput, radius as getParticleRadius } from "../pkg";
+import { Collision, InitOutput } from "../pkg";
 import { Timer } from "./timer";
 import { syncCanvasSize } from "./dom";
 
@@ -26,9 +26,9 @@ export function canvas2dDrawer({
     domainHeight,
     nitems,
     rate,
-    Math.random(),
+    Math.floor(Math.random() * 2 ** 32),
   );
-  const radius = getParticleRadius();
+  const radius = collision.radius();
   const ctx: CanvasRenderingContext2D = (function () {
     const ctx: CanvasRenderingContext2D | null = canvas.getContext("2d");
     if (null === ctx) {
@@ -39,17 +39,17 @@ export function canvas2dDrawer({
   const timer = new Timer(1000);
   function updateAndDraw() {
     collision.update();
-    const positions = new Float32Array(
+    const positions = new Float64Array(
       wasm.memory.buffer,
       collision.positions(),
-      nitems * "xy".length,
+      collision.nitems() * "xy".length,
     );
     const amplificationFactor = canvas.width / domainWidth;
     ctx.clearRect(0, 0, canvas.width, canvas.height);
     ctx.imageSmoothingEnabled = false;
     ctx.fillStyle = "#ffffff";
     ctx.beginPath();
-    for (let index = 0; index < nitems; index++) {
+    for (let index = 0; index < collision.nitems(); index++) {
       const x: number = amplificationFactor * positions[2 * index + 0];
       const y: number = amplificationFactor * positions[2 * index + 1];
       const r: number = amplificationFactor * radius;
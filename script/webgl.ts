@@ -1,8 +1,4 @@
-import {
-  InitOutput,
-  Collision,
-  radius as getParticleRadius,
-} from "../pkg/collision";
+import { InitOutput, Collision } from "../pkg/collision";
 import { Timer } from "./timer";
 import { syncCanvasSize } from "./dom";
 import { getContext, WebGLContext } from "./webgl/context";
@@ -125,9 +121,9 @@ export function webGLDrawer({
     domainHeight,
     nitems,
     rate,
-    Math.random(),
+    Math.floor(Math.random() * 2 ** 32),
   );
-  const radius = getParticleRadius();
+  const radius = collision.radius();
   const webGLObjects = new WebGLObjects(
     canvas,
     domainWidth,
@@ -138,17 +134,18 @@ export function webGLDrawer({
   const timer = new Timer(1000);
   function updateAndDraw() {
     collision.update();
+    // WebGL has no double-precision attributes
     const positions = new Float32Array(
       wasm.memory.buffer,
-      collision.positions(),
-      nitems * "xy".length,
+      collision.positions_f32(),
+      collision.nitems() * "xy".length,
     );
     const temperatures = new Float32Array(
       wasm.memory.buffer,
       collision.temperatures(),
-      nitems,
+      collision.nitems(),
     );
-    webGLObjects.draw(nitems, positions, temperatures);
+    webGLObjects.draw(collision.nitems(), positions, temperatures);
     timer.update();
     requestAnimationFrame(updateAndDraw);
   }
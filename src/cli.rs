@@ -0,0 +1,658 @@
+mod json;
+mod toml;
+mod value;
+
+use std::path::{Path, PathBuf};
+
+use collision::ensemble::{Ensemble, Parameter};
+use collision::simulator::{
+    Boundary, ConfigError, Extrema, ScalarCondition, SchedulerKind, SimulationConfig,
+    SimulationConfigBuilder, SnapshotFormat, NDIMS,
+};
+pub use value::SyntaxError;
+use value::Value;
+
+pub const USAGE: &str = "\
+Usage: collision [OPTIONS]
+       collision ensemble [OPTIONS]
+
+The latter runs the members given by the seeds and the swept parameters in parallel,
+  writing their observables to runs.csv and the means and the standard errors over the seeds
+  to ensemble.csv in the output directory.
+
+Options:
+  --config <FILE>           read the options from a TOML (.toml) or JSON (.json) file,
+                              which are overridden by the ones given on the command line
+  --lengths <L,...>         domain lengths, one for each direction
+  --nparticles <N>          number of particles
+  --volume-fraction <PHI>   number of particles given by the volume fraction
+  --seed <SEED>             seed of the random-number generator, a non-negative integer
+  --time-max <TIME>         time to finish the run
+  --sync-rate <RATE>        time between two synchronisations
+  --boundaries <B,...>      boundaries, one for each direction,
+                              either <FACE> for both faces or <FACE>/<FACE>, where <FACE> is
+                              periodic, specular[:VAL...], diffuse:TEMP[:VAL...], or outflow,
+                              VAL being the wall values of the scalars, one for each
+                              (insulated if omitted)
+  --shear-rate <RATE>       shear rate imposed by the Lees-Edwards boundaries
+  --restitution-coefficient <E>
+                            restitution coefficient between the particles
+  --scheduler <KIND>        data structure to find the coming event, which does not change
+                              the results: binary-heap (default), calendar-queue,
+                              tournament-tree, or pairing-heap
+  --output <DIR>            directory to which the snapshots and the checkpoint are written
+  --format <FORMAT>         format of the snapshots: binary, csv, or none
+  --restart <FILE>          resume from the checkpoint, where the physical parameters are ignored
+
+Ensemble options:
+  --seeds <SEED,...>        seeds of the members at each grid point
+  --restitution-coefficients <E,...>
+                            restitution coefficients to be swept
+  --volume-fractions <PHI,...>
+                            volume fractions to be swept
+  --nthreads <N>            number of the threads, one for each core by default
+  -h, --help                print this message
+
+The keys in the configuration files are the option names with the underscores,
+  e.g. `time_max = 100` in TOML or `\"time_max\": 100` in JSON.
+";
+
+/// Reasons why the options are rejected.
+#[derive(Debug)]
+pub enum CliError {
+    /// Option on the command line is unknown or lacks its value.
+    InvalidArgument(String),
+    /// Configuration file cannot be read.
+    Io(PathBuf, std::io::Error),
+    /// Configuration file is not a valid document.
+    Syntax(PathBuf, SyntaxError),
+    /// Key in the configuration file is unknown.
+    UnknownKey(String),
+    /// Value given to the key is of a wrong type or out of range.
+    InvalidValue(String, String),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::InvalidArgument(argument) => write!(f, "invalid argument: {}", argument),
+            CliError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            CliError::Syntax(path, error) => write!(f, "{}: {}", path.display(), error),
+            CliError::UnknownKey(key) => write!(f, "unknown key: {}", key),
+            CliError::InvalidValue(key, reason) => {
+                write!(f, "invalid value of {}: {}", key, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// How the number of particles is given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Population {
+    Count(usize),
+    /// Ratio of the total particle volume to the domain volume,
+    ///   which is still capped by the maximum volume fraction of the random placement.
+    VolumeFraction(f64),
+}
+
+/// Parameters of a run given by the command line and the configuration file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub lengths: [f64; NDIMS],
+    pub population: Population,
+    pub seed: u64,
+    pub time_max: f64,
+    pub sync_rate: f64,
+    /// The default ones of [`SimulationConfigBuilder`] are used if not given.
+    pub boundaries: Option<[Extrema<Boundary>; NDIMS]>,
+    pub shear_rate: Option<f64>,
+    pub restitution_coefficient: Option<f64>,
+    pub scheduler: Option<SchedulerKind>,
+    pub output: PathBuf,
+    /// No snapshot is written if `None`.
+    pub format: Option<SnapshotFormat>,
+    pub restart: Option<PathBuf>,
+    /// Seeds of the ensemble members, where the single `seed` is used if empty.
+    pub seeds: Vec<u64>,
+    pub restitution_coefficients: Vec<f64>,
+    pub volume_fractions: Vec<f64>,
+    pub nthreads: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            // a smaller domain in three dimensions, where the number of cells grows rapidly
+            lengths: if 2 == NDIMS {
+                [800.; NDIMS]
+            } else {
+                [80.; NDIMS]
+            },
+            population: Population::Count(65536),
+            seed: 0,
+            time_max: 50.,
+            sync_rate: 1.,
+            boundaries: None,
+            shear_rate: None,
+            restitution_coefficient: None,
+            scheduler: None,
+            output: PathBuf::from("output"),
+            format: Some(SnapshotFormat::Binary),
+            restart: None,
+            seeds: Vec::new(),
+            restitution_coefficients: Vec::new(),
+            volume_fractions: Vec::new(),
+            nthreads: None,
+        }
+    }
+}
+
+/// What the binary is asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Box<Options>),
+    Ensemble(Box<Options>),
+    Help,
+}
+
+fn invalid<T>(key: &str, reason: &str) -> Result<T, CliError> {
+    Err(CliError::InvalidValue(
+        String::from(key),
+        String::from(reason),
+    ))
+}
+
+/// Interprets the value as a number.
+///
+/// N.B.: The values given on the command line are strings, which are parsed here.
+fn get_number(key: &str, value: &Value) -> Result<f64, CliError> {
+    let number: f64 = match value {
+        Value::Number(number) => *number,
+        Value::String(string) => match string.trim().parse::<f64>() {
+            Ok(number) => number,
+            Err(_) => return invalid(key, "expected a number"),
+        },
+        _ => return invalid(key, "expected a number"),
+    };
+    if !number.is_finite() {
+        return invalid(key, "expected a finite number");
+    }
+    Ok(number)
+}
+
+fn get_positive(key: &str, value: &Value) -> Result<f64, CliError> {
+    let number: f64 = get_number(key, value)?;
+    if number <= 0. {
+        return invalid(key, "expected a positive number");
+    }
+    Ok(number)
+}
+
+fn get_string(key: &str, value: &Value) -> Result<String, CliError> {
+    match value {
+        Value::String(string) => Ok(string.clone()),
+        _ => invalid(key, "expected a string"),
+    }
+}
+
+/// Interprets the value as an array, where a string is split by commas.
+fn get_array(key: &str, value: &Value) -> Result<Vec<Value>, CliError> {
+    match value {
+        Value::Array(items) => Ok(items.clone()),
+        Value::String(string) => Ok(string
+            .split(',')
+            .map(|item: &str| Value::String(String::from(item.trim())))
+            .collect()),
+        _ => invalid(key, "expected an array"),
+    }
+}
+
+fn get_numbers(key: &str, value: &Value) -> Result<Vec<f64>, CliError> {
+    get_array(key, value)?
+        .iter()
+        .map(|item: &Value| get_number(key, item))
+        .collect()
+}
+
+fn get_count(key: &str, value: &Value) -> Result<usize, CliError> {
+    let number: f64 = get_number(key, value)?;
+    if number < 0. || number.fract() != 0. {
+        return invalid(key, "expected a non-negative integer");
+    }
+    Ok(number as usize)
+}
+
+/// Interprets the value as a seed, where a string is parsed as an integer to keep all 64 bits.
+fn get_seed(key: &str, value: &Value) -> Result<u64, CliError> {
+    if let Value::String(string) = value {
+        return match string.trim().parse::<u64>() {
+            Ok(seed) => Ok(seed),
+            Err(_) => invalid(key, "expected a non-negative integer"),
+        };
+    }
+    let number: f64 = get_number(key, value)?;
+    if number < 0. || number.fract() != 0. || u64::MAX as f64 <= number {
+        return invalid(key, "expected a non-negative integer");
+    }
+    Ok(number as u64)
+}
+
+/// Interprets the value as an array with one item for each direction.
+fn get_directions<T, F>(key: &str, value: &Value, convert: F) -> Result<[T; NDIMS], CliError>
+where
+    F: Fn(&Value) -> Result<T, CliError>,
+{
+    let items: Vec<Value> = get_array(key, value)?;
+    if NDIMS != items.len() {
+        return invalid(key, &format!("expected {} items", NDIMS));
+    }
+    let items: Vec<T> = items.iter().map(convert).collect::<Result<_, _>>()?;
+    Ok(items
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("the number of items is checked")))
+}
+
+/// Parses a face, e.g. `periodic`, `specular`, `specular:1`, `diffuse:0.5:1`, or `outflow`,
+///   where the wall values of the scalars follow the parameters of the wall.
+fn parse_face(key: &str, text: &str) -> Result<Boundary, CliError> {
+    let words: Vec<&str> = text.trim().split(':').collect();
+    let number = |word: &str| get_number(key, &Value::String(String::from(word)));
+    let scalar = |words: &[&str]| -> Result<ScalarCondition, CliError> {
+        match words {
+            [] => Ok(ScalarCondition::Neumann),
+            vals => Ok(ScalarCondition::Dirichlet(
+                vals.iter()
+                    .map(|val: &&str| number(val))
+                    .collect::<Result<_, _>>()?,
+            )),
+        }
+    };
+    match words[..] {
+        ["periodic"] => Ok(Boundary::Periodic),
+        ["outflow"] => Ok(Boundary::Outflow),
+        ["specular", ref rest @ ..] => Ok(Boundary::Specular(scalar(rest)?)),
+        ["diffuse", temperature, ref rest @ ..] => Ok(Boundary::Diffuse {
+            temperature: number(temperature)?,
+            scalar: scalar(rest)?,
+        }),
+        _ => invalid(key, &format!("unknown boundary {}", text)),
+    }
+}
+
+/// Parses the faces in one direction, e.g. `periodic` or `specular:0/specular:1`.
+fn parse_boundary(key: &str, value: &Value) -> Result<Extrema<Boundary>, CliError> {
+    let text: String = get_string(key, value)?;
+    match text.split('/').collect::<Vec<&str>>()[..] {
+        [face] => Ok(Extrema::both(parse_face(key, face)?)),
+        [min, max] => Ok(Extrema::<Boundary> {
+            min: parse_face(key, min)?,
+            max: parse_face(key, max)?,
+        }),
+        _ => invalid(key, &format!("too many faces in {}", text)),
+    }
+}
+
+impl Options {
+    /// Sets the option given by the key, which is the one in the configuration files.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<(), CliError> {
+        match key {
+            "lengths" => self.lengths = get_directions(key, value, |item| get_positive(key, item))?,
+            "nparticles" => self.population = Population::Count(get_count(key, value)?),
+            "volume_fraction" => {
+                self.population = Population::VolumeFraction(get_number(key, value)?)
+            }
+            "seed" => self.seed = get_seed(key, value)?,
+            "time_max" => self.time_max = get_number(key, value)?,
+            "sync_rate" => self.sync_rate = get_positive(key, value)?,
+            "boundaries" => {
+                self.boundaries = Some(get_directions(key, value, |item| {
+                    parse_boundary(key, item)
+                })?)
+            }
+            "shear_rate" => self.shear_rate = Some(get_number(key, value)?),
+            "restitution_coefficient" => {
+                self.restitution_coefficient = Some(get_number(key, value)?)
+            }
+            "scheduler" => {
+                self.scheduler = Some(match get_string(key, value)?.as_str() {
+                    "binary-heap" => SchedulerKind::BinaryHeap,
+                    "calendar-queue" => SchedulerKind::CalendarQueue,
+                    "tournament-tree" => SchedulerKind::TournamentTree,
+                    "pairing-heap" => SchedulerKind::PairingHeap,
+                    scheduler => return invalid(key, &format!("unknown scheduler {}", scheduler)),
+                })
+            }
+            "output" => self.output = PathBuf::from(get_string(key, value)?),
+            "format" => {
+                self.format = match get_string(key, value)?.as_str() {
+                    "binary" => Some(SnapshotFormat::Binary),
+                    "csv" => Some(SnapshotFormat::Csv),
+                    "none" => None,
+                    format => return invalid(key, &format!("unknown format {}", format)),
+                }
+            }
+            "restart" => self.restart = Some(PathBuf::from(get_string(key, value)?)),
+            "seeds" => {
+                self.seeds = get_array(key, value)?
+                    .iter()
+                    .map(|item: &Value| get_seed(key, item))
+                    .collect::<Result<_, _>>()?
+            }
+            "restitution_coefficients" => self.restitution_coefficients = get_numbers(key, value)?,
+            "volume_fractions" => self.volume_fractions = get_numbers(key, value)?,
+            "nthreads" => self.nthreads = Some(get_count(key, value)?),
+            _ => return Err(CliError::UnknownKey(String::from(key))),
+        }
+        Ok(())
+    }
+
+    /// Reads the options from a TOML or a JSON file, which is told by the extension.
+    pub fn load(&mut self, path: &Path) -> Result<(), CliError> {
+        let text: String = std::fs::read_to_string(path)
+            .map_err(|error: std::io::Error| CliError::Io(path.to_path_buf(), error))?;
+        let document: Result<Value, SyntaxError> =
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("toml") => toml::parse(&text),
+                Some("json") => json::parse(&text),
+                _ => {
+                    return Err(CliError::InvalidArgument(format!(
+                        "{} is neither .toml nor .json",
+                        path.display()
+                    )))
+                }
+            };
+        let pairs: Vec<(String, Value)> = match document {
+            Ok(Value::Table(pairs)) => pairs,
+            Ok(_) => unreachable!("the top level of a document is a table"),
+            Err(error) => return Err(CliError::Syntax(path.to_path_buf(), error)),
+        };
+        for (key, value) in pairs.iter() {
+            self.set(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Prepares the configuration of a new run.
+    pub fn get_builder(&self) -> SimulationConfigBuilder {
+        let mut builder: SimulationConfigBuilder = SimulationConfig::builder()
+            .lengths(self.lengths)
+            .seed(self.seed)
+            .sync_rate(self.sync_rate);
+        builder = match self.population {
+            Population::Count(nparticles) => builder.nparticles(nparticles),
+            Population::VolumeFraction(fraction) => builder.volume_fraction(fraction),
+        };
+        if let Some(boundaries) = &self.boundaries {
+            builder = builder.boundaries(boundaries.clone());
+        }
+        if let Some(shear_rate) = self.shear_rate {
+            builder = builder.lees_edwards(shear_rate);
+        }
+        if let Some(restitution_coefficient) = self.restitution_coefficient {
+            builder = builder.restitution_coefficient(restitution_coefficient);
+        }
+        if let Some(scheduler) = self.scheduler {
+            builder = builder.scheduler(scheduler);
+        }
+        builder
+    }
+
+    /// Creates the configuration of a new run.
+    pub fn configure(&self) -> Result<SimulationConfig, ConfigError> {
+        self.get_builder().build()
+    }
+
+    /// Creates the ensemble swept over the given values.
+    pub fn get_ensemble(&self) -> Ensemble {
+        let mut ensemble = Ensemble::new(self.get_builder(), self.time_max);
+        if !self.seeds.is_empty() {
+            ensemble = ensemble.seeds(self.seeds.clone());
+        }
+        for (parameter, values) in [
+            (
+                Parameter::RestitutionCoefficient,
+                &self.restitution_coefficients,
+            ),
+            (Parameter::VolumeFraction, &self.volume_fractions),
+        ] {
+            if !values.is_empty() {
+                ensemble = ensemble.sweep(parameter, values.clone());
+            }
+        }
+        if let Some(nthreads) = self.nthreads {
+            ensemble = ensemble.nthreads(nthreads);
+        }
+        ensemble
+    }
+
+    /// Checks if any of the options only meaningful to an ensemble is given.
+    fn is_ensemble(&self) -> bool {
+        !self.seeds.is_empty()
+            || !self.restitution_coefficients.is_empty()
+            || !self.volume_fractions.is_empty()
+            || self.nthreads.is_some()
+    }
+}
+
+/// Parses the command-line arguments (excluding the program name).
+///
+/// Each option is given as `--name value` or `--name=value`,
+///   and the configuration file is read first wherever `--config` appears.
+pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut config: Option<PathBuf> = None;
+    let mut pairs = Vec::<(String, String)>::new();
+    let mut args = args.peekable();
+    let is_ensemble: bool = args.next_if(|arg: &String| "ensemble" == arg).is_some();
+    while let Some(arg) = args.next() {
+        if "-h" == arg || "--help" == arg {
+            return Ok(Command::Help);
+        }
+        let Some(name) = arg.strip_prefix("--") else {
+            return Err(CliError::InvalidArgument(arg));
+        };
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => match args.next() {
+                Some(value) => (name.to_string(), value),
+                None => {
+                    return Err(CliError::InvalidArgument(format!(
+                        "{} lacks its value",
+                        arg
+                    )))
+                }
+            },
+        };
+        if "config" == name {
+            config = Some(PathBuf::from(value));
+        } else {
+            pairs.push((name.replace('-', "_"), value));
+        }
+    }
+    let mut options = Options::default();
+    if let Some(config) = config {
+        options.load(&config)?;
+    }
+    for (key, value) in pairs.into_iter() {
+        options
+            .set(&key, &Value::String(value))
+            .map_err(|error: CliError| match error {
+                CliError::UnknownKey(key) => {
+                    CliError::InvalidArgument(format!("--{}", key.replace('_', "-")))
+                }
+                error => error,
+            })?;
+    }
+    if is_ensemble {
+        if options.restart.is_some() {
+            return Err(CliError::InvalidArgument(String::from(
+                "an ensemble cannot be restarted",
+            )));
+        }
+        Ok(Command::Ensemble(Box::new(options)))
+    } else {
+        if options.is_ensemble() {
+            return Err(CliError::InvalidArgument(String::from(
+                "seeds, sweeps, and threads are given to the ensemble subcommand",
+            )));
+        }
+        Ok(Command::Run(Box::new(options)))
+    }
+}
+
+#[cfg(test)]
+mod test_cli {
+    use super::{parse, parse_face, CliError, Command, Options, Population};
+    use collision::simulator::{Boundary, ScalarCondition, SchedulerKind, SnapshotFormat, NDIMS};
+
+    fn parse_args(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(|arg: &&str| arg.to_string()))
+    }
+
+    fn get_options(args: &[&str]) -> Options {
+        match parse_args(args) {
+            Ok(Command::Run(options)) => *options,
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // command-line options
+        assert_eq!(get_options(&[]), Options::default());
+        assert_eq!(parse_args(&["--seed", "1", "-h"]).unwrap(), Command::Help);
+        let boundaries: String =
+            ["periodic"; NDIMS]
+                .join(",")
+                .replacen("periodic", "specular:0/diffuse:1.5", 1);
+        let options = get_options(&[
+            "--lengths",
+            &["64"; NDIMS].join(","),
+            "--volume-fraction=0.25",
+            "--time-max",
+            "10",
+            "--boundaries",
+            &boundaries,
+            "--format",
+            "none",
+            "--scheduler",
+            "calendar-queue",
+        ]);
+        assert_eq!(options.lengths, [64.; NDIMS]);
+        assert_eq!(options.population, Population::VolumeFraction(0.25));
+        assert_eq!(options.time_max, 10.);
+        assert_eq!(options.format, None);
+        assert_eq!(options.scheduler, Some(SchedulerKind::CalendarQueue));
+        let boundaries = options.boundaries.clone().unwrap();
+        assert_eq!(
+            boundaries[0].min,
+            Boundary::Specular(ScalarCondition::Dirichlet(vec![0.]))
+        );
+        assert_eq!(
+            boundaries[0].max,
+            Boundary::Diffuse {
+                temperature: 1.5,
+                scalar: ScalarCondition::Neumann
+            }
+        );
+        assert_eq!(boundaries[1].min, Boundary::Periodic);
+        // one wall value for each scalar
+        assert_eq!(
+            parse_face("boundaries", "specular:0:1").unwrap(),
+            Boundary::Specular(ScalarCondition::Dirichlet(vec![0., 1.]))
+        );
+        // the volume fraction is converted to the number of particles of radius 0.5
+        let config = options.configure().unwrap();
+        let volume: f64 = 64f64.powi(NDIMS as i32);
+        let expected: f64 = 0.25 * volume / collision::simulator::particle::get_volume(0.5);
+        assert_eq!(config.nparticles(), expected.round() as usize);
+    }
+
+    #[test]
+    fn case2() {
+        // invalid options
+        let result = parse_args(&["--unknown", "1"]);
+        assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+        let result = parse_args(&["seed"]);
+        assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+        let result = parse_args(&["--seed"]);
+        assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+        let result = parse_args(&["--nparticles", "1.5"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["--seed", "0.5"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["ensemble", "--seeds", "1,-2"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["--lengths", "64"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["--boundaries", &["wall"; NDIMS].join(",")]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["--format", "hdf5"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+        let result = parse_args(&["--scheduler", "splay-tree"]);
+        assert!(matches!(result, Err(CliError::InvalidValue(_, _))));
+    }
+
+    #[test]
+    fn case3() {
+        // configuration files are overridden by the command line
+        let directory = std::env::temp_dir().join(format!("cli_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let toml = directory.join("config.toml");
+        std::fs::write(
+            &toml,
+            "nparticles = 128\nseed = 3\noutput = \"sweep/1\"\nformat = \"csv\"\n",
+        )
+        .unwrap();
+        let options = get_options(&["--seed", "4", "--config", toml.to_str().unwrap()]);
+        assert_eq!(options.population, Population::Count(128));
+        assert_eq!(options.seed, 4);
+        assert_eq!(options.output.to_str().unwrap(), "sweep/1");
+        assert_eq!(options.format, Some(SnapshotFormat::Csv));
+        let json = directory.join("config.json");
+        std::fs::write(
+            &json,
+            "{\"sync_rate\": 0.5, \"restart\": \"checkpoint.bin\"}",
+        )
+        .unwrap();
+        let options = get_options(&["--config", json.to_str().unwrap()]);
+        assert_eq!(options.sync_rate, 0.5);
+        assert_eq!(options.restart.unwrap().to_str().unwrap(), "checkpoint.bin");
+        // unknown keys, malformed documents, and missing files
+        std::fs::write(&json, "{\"seedz\": 1}").unwrap();
+        let result = parse_args(&["--config", json.to_str().unwrap()]);
+        assert!(matches!(result, Err(CliError::UnknownKey(_))));
+        std::fs::write(&toml, "seed = \n").unwrap();
+        let result = parse_args(&["--config", toml.to_str().unwrap()]);
+        assert!(matches!(result, Err(CliError::Syntax(_, _))));
+        std::fs::remove_dir_all(&directory).unwrap();
+        let result = parse_args(&["--config", toml.to_str().unwrap()]);
+        assert!(matches!(result, Err(CliError::Io(_, _))));
+    }
+
+    #[test]
+    fn case4() {
+        // ensemble subcommand
+        let args = [
+            "ensemble",
+            "--seeds",
+            "1,2",
+            "--volume-fractions",
+            "0.05,0.1,0.15",
+            "--nthreads",
+            "2",
+        ];
+        let options = match parse_args(&args).unwrap() {
+            Command::Ensemble(options) => options,
+            command => panic!("unexpected {:?}", command),
+        };
+        assert_eq!(options.seeds, vec![1, 2]);
+        assert_eq!(options.get_ensemble().get_runs().len(), 6);
+        // the ensemble options are not given to a single run, which is not restarted
+        let result = parse_args(&args[1..]);
+        assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+        let result = parse_args(&["ensemble", "--restart", "checkpoint.bin"]);
+        assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::cli::value::{Cursor, SyntaxError, Value};
+
+/// Parses a JSON document, whose top level should be an object.
+///
+/// N.B.: `null` is not supported, since no option takes it.
+pub fn parse(text: &str) -> Result<Value, SyntaxError> {
+    let mut cursor = Cursor::new(text);
+    cursor.skip_whitespace(true);
+    if Some('{') != cursor.peek() {
+        return cursor.error("top level should be an object");
+    }
+    let value: Value = parse_value(&mut cursor)?;
+    cursor.skip_whitespace(true);
+    if let Some(c) = cursor.peek() {
+        return cursor.error(format!("unexpected '{}' after the object", c));
+    }
+    Ok(value)
+}
+
+fn parse_value(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    cursor.skip_whitespace(true);
+    match cursor.peek() {
+        Some('{') => parse_object(cursor),
+        Some('[') => parse_array(cursor),
+        Some('"') => Ok(Value::String(cursor.parse_quoted()?)),
+        Some(c) if c.is_ascii_alphabetic() => {
+            let word: String = cursor.take_while(|c: char| c.is_ascii_alphabetic());
+            match word.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => cursor.error(format!("unsupported value {}", word)),
+            }
+        }
+        Some(c) if '-' == c || c.is_ascii_digit() => {
+            let word: String =
+                cursor.take_while(|c: char| "+-.eE".contains(c) || c.is_ascii_digit());
+            match word.parse::<f64>() {
+                Ok(number) => Ok(Value::Number(number)),
+                Err(_) => cursor.error(format!("invalid number {}", word)),
+            }
+        }
+        Some(c) => cursor.error(format!("unexpected '{}'", c)),
+        None => cursor.error("unexpected end of document"),
+    }
+}
+
+/// Parses the items separated by commas up to the closing bracket.
+fn parse_sequence<F>(cursor: &mut Cursor, close: char, mut parse_item: F) -> Result<(), SyntaxError>
+where
+    F: FnMut(&mut Cursor) -> Result<(), SyntaxError>,
+{
+    cursor.skip_whitespace(true);
+    if Some(close) == cursor.peek() {
+        cursor.next();
+        return Ok(());
+    }
+    loop {
+        parse_item(cursor)?;
+        cursor.skip_whitespace(true);
+        match cursor.next() {
+            Some(',') => (),
+            Some(c) if close == c => return Ok(()),
+            _ => return cursor.error(format!("expected ',' or '{}'", close)),
+        }
+    }
+}
+
+fn parse_array(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    cursor.expect('[')?;
+    let mut items = Vec::<Value>::new();
+    parse_sequence(cursor, ']', |cursor: &mut Cursor| {
+        items.push(parse_value(cursor)?);
+        Ok(())
+    })?;
+    Ok(Value::Array(items))
+}
+
+fn parse_object(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    cursor.expect('{')?;
+    let mut pairs = Vec::<(String, Value)>::new();
+    parse_sequence(cursor, '}', |cursor: &mut Cursor| {
+        cursor.skip_whitespace(true);
+        let key: String = cursor.parse_quoted()?;
+        if pairs.iter().any(|(other, _)| key == *other) {
+            return cursor.error(format!("duplicate key {}", key));
+        }
+        cursor.skip_whitespace(true);
+        cursor.expect(':')?;
+        pairs.push((key, parse_value(cursor)?));
+        Ok(())
+    })?;
+    Ok(Value::Table(pairs))
+}
+
+#[cfg(test)]
+mod test_json {
+    use super::{parse, Value};
+
+    #[test]
+    fn case1() {
+        let text = r#"{
+            "lengths": [64, 3.2e1],
+            "output": "run \"1\"!",
+            "nested": {"flag": true, "empty": []}
+        }"#;
+        let value = parse(text).unwrap();
+        assert_eq!(
+            value,
+            Value::Table(vec![
+                (
+                    String::from("lengths"),
+                    Value::Array(vec![Value::Number(64.), Value::Number(32.)])
+                ),
+                (
+                    String::from("output"),
+                    Value::String(String::from("run \"1\"!"))
+                ),
+                (
+                    String::from("nested"),
+                    Value::Table(vec![
+                        (String::from("flag"), Value::Bool(true)),
+                        (String::from("empty"), Value::Array(Vec::new())),
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn case2() {
+        // malformed documents are rejected with the line numbers
+        assert_eq!(parse("{\n\"a\": 1,\n\"a\": 2}").unwrap_err().line, 3);
+        assert_eq!(parse("{\"a\": [1 2]}").unwrap_err().line, 1);
+        assert_eq!(parse("{\"a\": null}").unwrap_err().line, 1);
+        assert_eq!(parse("{\"a\": 1} 2").unwrap_err().line, 1);
+        assert_eq!(parse("[1, 2]").unwrap_err().line, 1);
+        assert_eq!(parse("{\n\"a\": \"b").unwrap_err().line, 2);
+    }
+}
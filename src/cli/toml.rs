@@ -0,0 +1,272 @@
+use crate::cli::value::{Cursor, SyntaxError, Value};
+
+/// Parses a TOML document.
+///
+/// The subset needed by the configuration files is supported:
+///   bare or quoted keys, tables (`[name]`), basic and literal strings, numbers, booleans,
+///   (multi-line) arrays, and inline tables.
+/// Dotted keys, multi-line strings, arrays of tables, and dates are not supported.
+pub fn parse(text: &str) -> Result<Value, SyntaxError> {
+    let mut cursor = Cursor::new(text);
+    let mut root = Vec::<(String, Value)>::new();
+    // table to which the following pairs belong
+    let mut path = Vec::<String>::new();
+    loop {
+        skip_blank(&mut cursor);
+        match cursor.peek() {
+            None => break,
+            Some('[') => {
+                cursor.next();
+                cursor.skip_whitespace(false);
+                path = vec![parse_key(&mut cursor)?];
+                loop {
+                    cursor.skip_whitespace(false);
+                    if Some('.') != cursor.peek() {
+                        break;
+                    }
+                    cursor.next();
+                    cursor.skip_whitespace(false);
+                    path.push(parse_key(&mut cursor)?);
+                }
+                cursor.expect(']')?;
+                if let Err(message) = get_table(&mut root, &path) {
+                    return cursor.error(message);
+                }
+            }
+            Some(_) => {
+                let (key, value) = parse_pair(&mut cursor)?;
+                let table = match get_table(&mut root, &path) {
+                    Ok(table) => table,
+                    Err(message) => return cursor.error(message),
+                };
+                insert(&cursor, table, key, value)?;
+            }
+        }
+        // nothing but a comment follows on the same line
+        cursor.skip_whitespace(false);
+        skip_comment(&mut cursor);
+        match cursor.next() {
+            Some('\n') | None => (),
+            Some('\r') if Some('\n') == cursor.next() => (),
+            Some(c) => return cursor.error(format!("unexpected '{}' at the end of line", c)),
+        }
+    }
+    Ok(Value::Table(root))
+}
+
+fn skip_comment(cursor: &mut Cursor) {
+    if Some('#') == cursor.peek() {
+        cursor.take_while(|c: char| '\n' != c);
+    }
+}
+
+/// Skips whitespaces, line breaks, and comments.
+fn skip_blank(cursor: &mut Cursor) {
+    loop {
+        cursor.skip_whitespace(true);
+        if Some('#') != cursor.peek() {
+            break;
+        }
+        skip_comment(cursor);
+    }
+}
+
+/// Returns the table at the given path, which is created if it does not exist.
+fn get_table<'a>(
+    table: &'a mut Vec<(String, Value)>,
+    path: &[String],
+) -> Result<&'a mut Vec<(String, Value)>, String> {
+    let Some((key, rest)) = path.split_first() else {
+        return Ok(table);
+    };
+    let index: usize = match table.iter().position(|(other, _)| key == other) {
+        Some(index) => index,
+        None => {
+            table.push((key.clone(), Value::Table(Vec::new())));
+            table.len() - 1
+        }
+    };
+    match &mut table[index].1 {
+        Value::Table(pairs) => get_table(pairs, rest),
+        _ => Err(format!("{} is not a table", key)),
+    }
+}
+
+fn insert(
+    cursor: &Cursor,
+    table: &mut Vec<(String, Value)>,
+    key: String,
+    value: Value,
+) -> Result<(), SyntaxError> {
+    if table.iter().any(|(other, _)| key == *other) {
+        return cursor.error(format!("duplicate key {}", key));
+    }
+    table.push((key, value));
+    Ok(())
+}
+
+fn parse_key(cursor: &mut Cursor) -> Result<String, SyntaxError> {
+    match cursor.peek() {
+        Some('"') => cursor.parse_quoted(),
+        Some('\'') => parse_literal(cursor),
+        _ => {
+            let key: String =
+                cursor.take_while(|c: char| c.is_ascii_alphanumeric() || '_' == c || '-' == c);
+            if key.is_empty() {
+                return cursor.error("expected a key");
+            }
+            Ok(key)
+        }
+    }
+}
+
+fn parse_pair(cursor: &mut Cursor) -> Result<(String, Value), SyntaxError> {
+    let key: String = parse_key(cursor)?;
+    cursor.skip_whitespace(false);
+    cursor.expect('=')?;
+    cursor.skip_whitespace(false);
+    let value: Value = parse_value(cursor)?;
+    Ok((key, value))
+}
+
+/// Parses a string enclosed by the single quotes, where no escape is processed.
+fn parse_literal(cursor: &mut Cursor) -> Result<String, SyntaxError> {
+    cursor.expect('\'')?;
+    let string: String = cursor.take_while(|c: char| '\'' != c && '\n' != c);
+    cursor.expect('\'')?;
+    Ok(string)
+}
+
+fn parse_value(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    match cursor.peek() {
+        Some('"') => Ok(Value::String(cursor.parse_quoted()?)),
+        Some('\'') => Ok(Value::String(parse_literal(cursor)?)),
+        Some('[') => parse_array(cursor),
+        Some('{') => parse_inline_table(cursor),
+        Some(_) => {
+            let word: String =
+                cursor.take_while(|c: char| c.is_ascii_alphanumeric() || "+-._".contains(c));
+            match word.as_str() {
+                "true" => return Ok(Value::Bool(true)),
+                "false" => return Ok(Value::Bool(false)),
+                _ => (),
+            }
+            // underscores separate digits
+            match word.replace('_', "").parse::<f64>() {
+                Ok(number) if !word.is_empty() => Ok(Value::Number(number)),
+                _ => cursor.error(format!("unsupported value {}", word)),
+            }
+        }
+        None => cursor.error("unexpected end of document"),
+    }
+}
+
+fn parse_array(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    cursor.expect('[')?;
+    let mut items = Vec::<Value>::new();
+    loop {
+        skip_blank(cursor);
+        if Some(']') == cursor.peek() {
+            break;
+        }
+        items.push(parse_value(cursor)?);
+        skip_blank(cursor);
+        match cursor.peek() {
+            Some(',') => {
+                cursor.next();
+            }
+            Some(']') => break,
+            _ => return cursor.error("expected ',' or ']'"),
+        }
+    }
+    cursor.expect(']')?;
+    Ok(Value::Array(items))
+}
+
+fn parse_inline_table(cursor: &mut Cursor) -> Result<Value, SyntaxError> {
+    cursor.expect('{')?;
+    let mut pairs = Vec::<(String, Value)>::new();
+    cursor.skip_whitespace(false);
+    if Some('}') == cursor.peek() {
+        cursor.next();
+        return Ok(Value::Table(pairs));
+    }
+    loop {
+        cursor.skip_whitespace(false);
+        let (key, value) = parse_pair(cursor)?;
+        insert(cursor, &mut pairs, key, value)?;
+        cursor.skip_whitespace(false);
+        match cursor.next() {
+            Some(',') => (),
+            Some('}') => return Ok(Value::Table(pairs)),
+            _ => return cursor.error("expected ',' or '}'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_toml {
+    use super::{parse, Value};
+
+    #[test]
+    fn case1() {
+        let text = "
+# sweep over the particle numbers
+lengths = [ 64, 3.2e1, ]  # trailing comma
+nparticles = 1_024
+boundaries = [
+    'periodic',  # first direction
+    \"specular:0/specular:1\",
+]
+
+[output]
+directory = \"run\\t1\"
+point = { x = -1.5, flag = false }
+";
+        let value = parse(text).unwrap();
+        assert_eq!(
+            value,
+            Value::Table(vec![
+                (
+                    String::from("lengths"),
+                    Value::Array(vec![Value::Number(64.), Value::Number(32.)])
+                ),
+                (String::from("nparticles"), Value::Number(1024.)),
+                (
+                    String::from("boundaries"),
+                    Value::Array(vec![
+                        Value::String(String::from("periodic")),
+                        Value::String(String::from("specular:0/specular:1")),
+                    ])
+                ),
+                (
+                    String::from("output"),
+                    Value::Table(vec![
+                        (
+                            String::from("directory"),
+                            Value::String(String::from("run\t1"))
+                        ),
+                        (
+                            String::from("point"),
+                            Value::Table(vec![
+                                (String::from("x"), Value::Number(-1.5)),
+                                (String::from("flag"), Value::Bool(false)),
+                            ])
+                        ),
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn case2() {
+        // malformed documents are rejected with the line numbers
+        assert_eq!(parse("a = 1\na = 2").unwrap_err().line, 2);
+        assert_eq!(parse("a = 1 b = 2").unwrap_err().line, 1);
+        assert_eq!(parse("a = 1979-05-27").unwrap_err().line, 1);
+        assert_eq!(parse("\n\na = [1 2]").unwrap_err().line, 3);
+        assert_eq!(parse("a = 1\n[a]").unwrap_err().line, 2);
+        assert_eq!(parse("a =").unwrap_err().line, 1);
+    }
+}
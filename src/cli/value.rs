@@ -0,0 +1,131 @@
+/// Tree of the values given by a configuration file, which is shared by TOML and JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    /// Integers and floating-point numbers are not distinguished.
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Pairs of the keys and the values in the order of appearance.
+    Table(Vec<(String, Value)>),
+}
+
+/// Position and reason of a malformed document.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError {
+    /// Line number starting from one.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Walks through the characters of a document, keeping track of the line number.
+pub struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Cursor {
+    pub fn new(text: &str) -> Cursor {
+        Cursor {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    pub fn next(&mut self) -> Option<char> {
+        let c: Option<char> = self.peek();
+        if let Some(c) = c {
+            self.pos += 1;
+            if '\n' == c {
+                self.line += 1;
+            }
+        }
+        c
+    }
+
+    pub fn error<T, S: Into<String>>(&self, message: S) -> Result<T, SyntaxError> {
+        Err(SyntaxError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    /// Consumes the given character, which should come next.
+    pub fn expect(&mut self, expected: char) -> Result<(), SyntaxError> {
+        match self.next() {
+            Some(c) if expected == c => Ok(()),
+            Some(c) => self.error(format!("expected '{}' but found '{}'", expected, c)),
+            None => self.error(format!("expected '{}' but reached the end", expected)),
+        }
+    }
+
+    /// Skips spaces and tabs, and also line breaks if `newlines` is true.
+    pub fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' => (),
+                '\n' | '\r' if newlines => (),
+                _ => break,
+            }
+            self.next();
+        }
+    }
+
+    /// Consumes the characters as long as the predicate holds.
+    pub fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            taken.push(c);
+            self.next();
+        }
+        taken
+    }
+
+    /// Parses a string enclosed by the double quotes with the backslash escapes,
+    ///   which is common to TOML (basic strings) and JSON.
+    pub fn parse_quoted(&mut self) -> Result<String, SyntaxError> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let c: char = match self.next() {
+                Some('\n') | None => return self.error("unterminated string"),
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(c) => c,
+                            None => return self.error(format!("invalid unicode escape {}", hex)),
+                        }
+                    }
+                    _ => return self.error("invalid escape sequence"),
+                },
+                Some(c) => c,
+            };
+            string.push(c);
+        }
+    }
+}
@@ -0,0 +1,455 @@
+//! Runs many independent simulations over a grid of parameters and seeds in parallel,
+//!   and averages their observables over the seeds.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use crate::simulator::{
+    ConfigError, InitialConditionError, Observables, SimulationConfig, SimulationConfigBuilder,
+    Simulator,
+};
+
+/// Quantities extracted from the observables, which are written and averaged.
+pub const QUANTITIES: [&str; 4] = [
+    "kinetic_energy",
+    "rotational_energy",
+    "temperature",
+    "pressure",
+];
+
+fn get_quantities(observables: &Observables) -> [f64; QUANTITIES.len()] {
+    [
+        observables.kinetic_energy,
+        observables.rotational_energy,
+        observables.temperature,
+        observables.get_pressure(),
+    ]
+}
+
+/// Reasons why an ensemble cannot be run.
+#[derive(Debug, PartialEq)]
+pub enum EnsembleError {
+    /// Configuration of a member is rejected.
+    InvalidConfig(ConfigError),
+    /// Particles of the given member cannot be placed.
+    InvalidInitialCondition(usize, InitialConditionError),
+}
+
+impl std::fmt::Display for EnsembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnsembleError::InvalidConfig(error) => write!(f, "invalid configuration: {}", error),
+            EnsembleError::InvalidInitialCondition(index, error) => {
+                write!(f, "invalid initial condition of run {}: {}", index, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnsembleError {}
+
+/// Parameter varied over the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parameter {
+    /// Of the smooth particles, see [`SimulationConfigBuilder::restitution_coefficient`].
+    RestitutionCoefficient,
+    /// See [`SimulationConfigBuilder::volume_fraction`].
+    VolumeFraction,
+    /// See [`SimulationConfigBuilder::lees_edwards`].
+    ShearRate,
+}
+
+impl Parameter {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Parameter::RestitutionCoefficient => "restitution_coefficient",
+            Parameter::VolumeFraction => "volume_fraction",
+            Parameter::ShearRate => "shear_rate",
+        }
+    }
+
+    fn apply(&self, builder: SimulationConfigBuilder, value: f64) -> SimulationConfigBuilder {
+        match self {
+            Parameter::RestitutionCoefficient => builder.restitution_coefficient(value),
+            Parameter::VolumeFraction => builder.volume_fraction(value),
+            Parameter::ShearRate => builder.lees_edwards(value),
+        }
+    }
+}
+
+/// One member of the ensemble.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    /// Index of the grid point, which is shared by the runs with different seeds.
+    pub point: usize,
+    pub seed: u64,
+    /// Values of the swept parameters in the order of [`Ensemble::sweep`].
+    pub parameters: Vec<(Parameter, f64)>,
+}
+
+/// Observables of a run at each synchronisation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunResult {
+    pub run: Run,
+    pub observables: Vec<Observables>,
+}
+
+/// Means and standard errors of the quantities over the runs at a grid point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub parameters: Vec<(Parameter, f64)>,
+    pub nruns: usize,
+    /// Times of the synchronisations.
+    pub times: Vec<f64>,
+    /// Means of [`QUANTITIES`] at each synchronisation.
+    pub means: Vec<[f64; QUANTITIES.len()]>,
+    /// Standard errors of the means, which are zero for a single run.
+    pub errors: Vec<[f64; QUANTITIES.len()]>,
+}
+
+/// Grid of the parameters and the seeds, which is the Cartesian product of all values.
+///
+/// N.B.: Each simulator is created and dropped by a worker thread,
+///   and only the configurations and the observables cross the threads.
+#[derive(Clone, Debug)]
+pub struct Ensemble {
+    builder: SimulationConfigBuilder,
+    time_max: f64,
+    seeds: Vec<u64>,
+    axes: Vec<(Parameter, Vec<f64>)>,
+    nthreads: usize,
+}
+
+impl Ensemble {
+    /// Runs the configuration given by the builder until `time_max`,
+    ///   with its own seed and one thread for each available core by default.
+    pub fn new(builder: SimulationConfigBuilder, time_max: f64) -> Ensemble {
+        let seed: u64 = builder.clone().build().map_or(0, |config| config.seed());
+        Ensemble {
+            builder,
+            time_max,
+            seeds: vec![seed],
+            axes: Vec::new(),
+            nthreads: std::thread::available_parallelism().map_or(1, |nthreads| nthreads.get()),
+        }
+    }
+
+    /// Seeds of the runs at each grid point, which should be in [0, 1).
+    pub fn seeds(mut self, seeds: Vec<u64>) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Adds a direction of the grid.
+    pub fn sweep(mut self, parameter: Parameter, values: Vec<f64>) -> Self {
+        self.axes.push((parameter, values));
+        self
+    }
+
+    pub fn nthreads(mut self, nthreads: usize) -> Self {
+        self.nthreads = nthreads.max(1);
+        self
+    }
+
+    /// Lists the runs, where the seeds vary fastest and the first parameter slowest.
+    pub fn get_runs(&self) -> Vec<Run> {
+        let mut points: Vec<Vec<(Parameter, f64)>> = vec![Vec::new()];
+        for (parameter, values) in self.axes.iter() {
+            points = points
+                .into_iter()
+                .flat_map(|point: Vec<(Parameter, f64)>| {
+                    values.iter().map(move |&value: &f64| {
+                        let mut point = point.clone();
+                        point.push((*parameter, value));
+                        point
+                    })
+                })
+                .collect();
+        }
+        points
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, parameters)| {
+                self.seeds.iter().map(move |&seed: &u64| Run {
+                    point: index,
+                    seed,
+                    parameters: parameters.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn configure(&self, run: &Run) -> Result<SimulationConfig, ConfigError> {
+        let mut builder: SimulationConfigBuilder = self.builder.clone().seed(run.seed);
+        for &(parameter, value) in run.parameters.iter() {
+            builder = parameter.apply(builder, value);
+        }
+        builder.build()
+    }
+
+    /// Runs all members, whose configurations are validated beforehand,
+    ///   and returns the results in the order of [`Ensemble::get_runs`].
+    pub fn run(&self) -> Result<Vec<RunResult>, EnsembleError> {
+        let runs: Vec<Run> = self.get_runs();
+        let configs: Vec<SimulationConfig> = runs
+            .iter()
+            .map(|run: &Run| self.configure(run))
+            .collect::<Result<_, _>>()
+            .map_err(EnsembleError::InvalidConfig)?;
+        // the workers take the runs one by one
+        let next = AtomicUsize::new(0);
+        type Item = Result<Vec<Observables>, InitialConditionError>;
+        let (sender, receiver) = mpsc::channel::<(usize, Item)>();
+        std::thread::scope(|scope| {
+            for _ in 0..self.nthreads.min(runs.len()) {
+                let sender = sender.clone();
+                let next: &AtomicUsize = &next;
+                let configs: &Vec<SimulationConfig> = &configs;
+                scope.spawn(move || loop {
+                    let index: usize = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(config) = configs.get(index) else {
+                        break;
+                    };
+                    let observables: Item = simulate(config.clone(), self.time_max);
+                    sender.send((index, observables)).unwrap();
+                });
+            }
+        });
+        drop(sender);
+        let mut observables: Vec<Option<Item>> = (0..runs.len()).map(|_| None).collect();
+        for (index, item) in receiver.iter() {
+            observables[index] = Some(item);
+        }
+        runs.into_iter()
+            .zip(observables)
+            .enumerate()
+            .map(|(index, (run, observables))| match observables.unwrap() {
+                Ok(observables) => Ok(RunResult { run, observables }),
+                Err(error) => Err(EnsembleError::InvalidInitialCondition(index, error)),
+            })
+            .collect()
+    }
+}
+
+/// Integrates a new simulator, collecting the observables at each synchronisation.
+fn simulate(
+    config: SimulationConfig,
+    time_max: f64,
+) -> Result<Vec<Observables>, InitialConditionError> {
+    let mut simulator = Simulator::new(config)?;
+    let mut observables = Vec::<Observables>::new();
+    loop {
+        simulator.integrate();
+        if let Some(item) = simulator.get_observables() {
+            observables.push(item.clone());
+        }
+        if time_max <= simulator.get_time() {
+            break;
+        }
+    }
+    Ok(observables)
+}
+
+/// Averages the runs sharing the grid points,
+///   where the synchronisations are matched by their order.
+pub fn summarise(results: &[RunResult]) -> Vec<Summary> {
+    let npoints: usize = results
+        .iter()
+        .map(|result: &RunResult| result.run.point + 1)
+        .max()
+        .unwrap_or(0);
+    (0..npoints)
+        .filter_map(|point: usize| {
+            let members: Vec<&RunResult> = results
+                .iter()
+                .filter(|result: &&RunResult| point == result.run.point)
+                .collect();
+            let first: &RunResult = members.first()?;
+            let nruns: usize = members.len();
+            let nsyncs: usize = members
+                .iter()
+                .map(|result: &&RunResult| result.observables.len())
+                .min()
+                .unwrap_or(0);
+            let mut summary = Summary {
+                parameters: first.run.parameters.clone(),
+                nruns,
+                times: Vec::new(),
+                means: Vec::new(),
+                errors: Vec::new(),
+            };
+            for sync in 0..nsyncs {
+                let samples: Vec<[f64; QUANTITIES.len()]> = members
+                    .iter()
+                    .map(|result: &&RunResult| get_quantities(&result.observables[sync]))
+                    .collect();
+                let mut means = [0.; QUANTITIES.len()];
+                let mut errors = [0.; QUANTITIES.len()];
+                for (n, (mean, error)) in means.iter_mut().zip(errors.iter_mut()).enumerate() {
+                    *mean = samples.iter().map(|sample| sample[n]).sum::<f64>() / nruns as f64;
+                    if 1 < nruns {
+                        let variance: f64 = samples
+                            .iter()
+                            .map(|sample| (sample[n] - *mean).powi(2))
+                            .sum::<f64>()
+                            / (nruns - 1) as f64;
+                        *error = (variance / nruns as f64).sqrt();
+                    }
+                }
+                summary.times.push(first.observables[sync].time);
+                summary.means.push(means);
+                summary.errors.push(errors);
+            }
+            Some(summary)
+        })
+        .collect()
+}
+
+fn get_names(parameters: &[(Parameter, f64)]) -> Vec<String> {
+    parameters
+        .iter()
+        .map(|(parameter, _)| String::from(parameter.name()))
+        .collect()
+}
+
+fn get_values(parameters: &[(Parameter, f64)]) -> Vec<String> {
+    parameters
+        .iter()
+        .map(|(_, value)| value.to_string())
+        .collect()
+}
+
+/// Writes the quantities of each run at each synchronisation as comma-separated values.
+pub fn write_runs(writer: &mut dyn Write, results: &[RunResult]) -> std::io::Result<()> {
+    let Some(first) = results.first() else {
+        return Ok(());
+    };
+    let mut columns: Vec<String> = vec![String::from("run"), String::from("seed")];
+    columns.extend(get_names(&first.run.parameters));
+    columns.push(String::from("time"));
+    columns.extend(QUANTITIES.iter().map(|name: &&str| name.to_string()));
+    writeln!(writer, "{}", columns.join(","))?;
+    for (index, result) in results.iter().enumerate() {
+        for observables in result.observables.iter() {
+            let mut row: Vec<String> = vec![index.to_string(), result.run.seed.to_string()];
+            row.extend(get_values(&result.run.parameters));
+            row.push(observables.time.to_string());
+            row.extend(get_quantities(observables).iter().map(f64::to_string));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the means and the standard errors at each grid point as comma-separated values.
+pub fn write_summaries(writer: &mut dyn Write, summaries: &[Summary]) -> std::io::Result<()> {
+    let Some(first) = summaries.first() else {
+        return Ok(());
+    };
+    let mut columns: Vec<String> = get_names(&first.parameters);
+    columns.push(String::from("nruns"));
+    columns.push(String::from("time"));
+    for name in QUANTITIES.iter() {
+        columns.push(format!("{}_mean", name));
+        columns.push(format!("{}_error", name));
+    }
+    writeln!(writer, "{}", columns.join(","))?;
+    for summary in summaries.iter() {
+        for (sync, time) in summary.times.iter().enumerate() {
+            let mut row: Vec<String> = get_values(&summary.parameters);
+            row.push(summary.nruns.to_string());
+            row.push(time.to_string());
+            for n in 0..QUANTITIES.len() {
+                row.push(summary.means[sync][n].to_string());
+                row.push(summary.errors[sync][n].to_string());
+            }
+            writeln!(writer, "{}", row.join(","))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_ensemble {
+    use super::{
+        summarise, write_runs, write_summaries, Ensemble, EnsembleError, Parameter, QUANTITIES,
+    };
+    use crate::simulator::{ConfigError, SimulationConfig, SimulationConfigBuilder, NDIMS};
+
+    fn init_builder() -> SimulationConfigBuilder {
+        SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(16)
+            .buoyancy(None)
+    }
+
+    #[test]
+    fn case1() {
+        // grid of 2 x 2 points with 3 seeds each
+        let ensemble = Ensemble::new(init_builder(), 2.)
+            .seeds(vec![1, 2, 3])
+            .sweep(Parameter::RestitutionCoefficient, vec![0.9, 1.])
+            .sweep(Parameter::VolumeFraction, vec![0.05, 0.1])
+            .nthreads(3);
+        let runs = ensemble.get_runs();
+        assert_eq!(runs.len(), 12);
+        assert_eq!(runs[4].point, 1);
+        assert_eq!(runs[4].seed, 2);
+        assert_eq!(
+            runs[4].parameters,
+            vec![
+                (Parameter::RestitutionCoefficient, 0.9),
+                (Parameter::VolumeFraction, 0.1)
+            ]
+        );
+        let results = ensemble.run().unwrap();
+        // identical to the serial runs
+        for (run, result) in runs.iter().zip(results.iter()) {
+            assert_eq!(*run, result.run);
+            let serial = super::simulate(ensemble.configure(run).unwrap(), 2.).unwrap();
+            assert_eq!(serial, result.observables);
+        }
+        // the seeds give different initial conditions
+        assert_ne!(results[3].observables, results[4].observables);
+        let summaries = summarise(&results);
+        assert_eq!(summaries.len(), 4);
+        let summary = &summaries[1];
+        assert_eq!(summary.nruns, 3);
+        assert_eq!(summary.times, vec![0., 1., 2.]);
+        let energies: Vec<f64> = results[3..6]
+            .iter()
+            .map(|result| result.observables[2].kinetic_energy)
+            .collect();
+        let mean: f64 = energies.iter().sum::<f64>() / 3.;
+        let variance: f64 = energies.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / 2.;
+        assert!((summary.means[2][0] - mean).abs() < 1e-12);
+        assert!((summary.errors[2][0] - (variance / 3.).sqrt()).abs() < 1e-12);
+        // one row for each synchronisation and a header
+        let mut buffer = Vec::<u8>::new();
+        write_runs(&mut buffer, &results).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert_eq!(text.lines().count(), 1 + 12 * 3);
+        assert!(text.starts_with("run,seed,restitution_coefficient,volume_fraction,time,"));
+        let mut buffer = Vec::<u8>::new();
+        write_summaries(&mut buffer, &summaries).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert_eq!(text.lines().count(), 1 + 4 * 3);
+        let header: Vec<&str> = text.lines().next().unwrap().split(',').collect();
+        assert_eq!(header.len(), 4 + 2 * QUANTITIES.len());
+    }
+
+    #[test]
+    fn case2() {
+        // invalid members are rejected before running
+        let result = Ensemble::new(init_builder(), 1.)
+            .sweep(Parameter::RestitutionCoefficient, vec![0.9, 1.5])
+            .run();
+        assert!(matches!(
+            result,
+            Err(EnsembleError::InvalidConfig(
+                ConfigError::InvalidCollisionModel(_)
+            ))
+        ));
+    }
+}
@@ -1,155 +1,155 @@
-mod myvec;
-mod random;
-mod simulator;
+mod cli;
 
-use myvec::MyVec;
-use std::cell::{Ref, RefCell};
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
 
-use crate::simulator::{Particle, Simulator, NDIMS};
+use cli::{Command, Options};
+use collision::ensemble::{self, RunResult, Summary};
+use collision::simulator::{CheckpointError, Simulator, SnapshotSeries};
 
 fn main() {
-    const SEED: f64 = 0.;
-    let mut time: f64 = 0.;
-    let time_max: f64 = 50.;
-    let sync_rate: f64 = 1.;
-    let lengths: [f64; NDIMS] = [800., 800.];
-    let nparticles: usize = 65536;
-    let mut simulator = Simulator::new(sync_rate, lengths, nparticles, SEED);
-    loop {
-        simulator.integrate();
-        time += sync_rate;
-        println!("time: {:8.2e}", time);
-        let _ = save_particles(&lengths, &simulator);
-        if time_max <= time {
-            break;
+    // e.g. `cargo run --release -- --config sweep.toml --seed 1`
+    let options: Options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => *options,
+        Ok(Command::Ensemble(options)) => {
+            run_ensemble(&options);
+            return;
         }
-    }
-}
-
-fn save_particles(lengths: &[f64; NDIMS], simulator: &Simulator) -> Result<(), ()> {
-    let particles: &Vec<Rc<RefCell<Particle>>> = simulator.get_particles();
-    let canvas_size: [usize; 2] = [800, 800];
-    let nitems: usize = canvas_size[0] * canvas_size[1];
-    let mut pixels: Vec<u8> = vec![0u8; nitems * 3];
-    for p in particles.iter() {
-        let p: Ref<Particle> = p.borrow();
-        let pos: &MyVec = &p.pos;
-        let _radius: f64 = simulator::radius();
-        let index: usize = (pos[1] / lengths[1] * canvas_size[1] as f64) as usize * canvas_size[0]
-            + (pos[0] / lengths[0] * canvas_size[0] as f64) as usize;
-        fit(p.val, &mut pixels[3 * index..3 * index + 3]);
-    }
-    {
-        let fname: &str = "image.ppm";
-        const MAGIC_NUMBER: &str = "P6";
-        // open and prepare stream
-        let file: std::fs::File = match std::fs::File::create(fname) {
-            Ok(file) => file,
-            Err(_) => {
-                println!("failed to open file");
-                return Err(());
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            eprint!("{}", cli::USAGE);
+            std::process::exit(1);
+        }
+    };
+    let mut simulator = match &options.restart {
+        Some(path) => match read_checkpoint(path) {
+            Ok(simulator) => simulator,
+            Err(error) => {
+                eprintln!("failed to restart from {}: {}", path.display(), error);
+                std::process::exit(1);
             }
-        };
-        let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
-        // fwrite
-        let _size: usize = match std::io::Write::write(
-            &mut stream,
-            format!(
-                "{}\n{} {}\n255\n",
-                MAGIC_NUMBER, &canvas_size[0], &canvas_size[1]
-            )
-            .as_bytes(),
-        ) {
-            Ok(size) => size,
-            Err(_) => {
-                println!("file write failed");
-                return Err(());
+        },
+        None => match options.configure() {
+            Ok(config) => match Simulator::new(config) {
+                Ok(simulator) => simulator,
+                Err(error) => {
+                    eprintln!("invalid initial condition: {}", error);
+                    std::process::exit(1);
+                }
+            },
+            Err(error) => {
+                eprintln!("invalid configuration: {}", error);
+                std::process::exit(1);
             }
-        };
-        let _size: usize = match std::io::Write::write(&mut stream, &pixels) {
-            Ok(size) => size,
-            Err(_) => {
-                println!("file write failed");
-                return Err(());
+        },
+    };
+    let output: &Path = &options.output;
+    if let Err(error) = std::fs::create_dir_all(output) {
+        eprintln!("failed to prepare {}: {}", output.display(), error);
+        std::process::exit(1);
+    }
+    let mut series: Option<SnapshotSeries> = options.format.map(|format| {
+        let series: SnapshotSeries = match SnapshotSeries::new(output) {
+            Ok(series) => series.with_format(format),
+            Err(error) => {
+                eprintln!("failed to prepare {}: {}", output.display(), error);
+                std::process::exit(1);
             }
         };
-    }
-    {
-        let mut xs = Vec::<u8>::new();
-        let mut ys = Vec::<u8>::new();
-        let mut vs = Vec::<u8>::new();
-        for p in particles.iter() {
-            let p: Ref<Particle> = p.borrow();
-            let pos: &MyVec = &p.pos;
-            let val: f64 = p.val;
-            xs.extend_from_slice(&pos[0].to_le_bytes());
-            ys.extend_from_slice(&pos[1].to_le_bytes());
-            vs.extend_from_slice(&val.to_le_bytes());
+        // continue the numbering of the previous run when resumed
+        let index: usize = match options.restart {
+            Some(_) => (0..)
+                .find(|&index: &usize| !series.get_path(index).exists())
+                .unwrap(),
+            None => 0,
+        };
+        series.with_index(index)
+    });
+    loop {
+        simulator.integrate();
+        let time: f64 = simulator.get_time();
+        match simulator.get_observables() {
+            Some(observables) => println!(
+                "time: {:8.2e}, energy: {:8.2e}, temperature: {:8.2e}, pressure: {:8.2e}",
+                time,
+                observables.kinetic_energy,
+                observables.temperature,
+                observables.get_pressure()
+            ),
+            None => println!("time: {:8.2e}", time),
         }
-        {
-            let file: std::fs::File = match std::fs::File::create("xs.bin") {
-                Ok(file) => file,
-                Err(_) => {
-                    println!("failed to open file");
-                    return Err(());
-                }
-            };
-            let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
-            let _size: usize = match std::io::Write::write(&mut stream, &xs) {
-                Ok(size) => size,
-                Err(_) => {
-                    println!("file write failed");
-                    return Err(());
-                }
-            };
+        if let Some(series) = series.as_mut() {
+            if let Err(error) = series.write(&simulator.get_snapshot()) {
+                eprintln!("failed to write snapshot: {}", error);
+                std::process::exit(1);
+            }
         }
-        {
-            let file: std::fs::File = match std::fs::File::create("ys.bin") {
-                Ok(file) => file,
-                Err(_) => {
-                    println!("failed to open file");
-                    return Err(());
-                }
-            };
-            let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
-            let _size: usize = match std::io::Write::write(&mut stream, &ys) {
-                Ok(size) => size,
-                Err(_) => {
-                    println!("file write failed");
-                    return Err(());
-                }
-            };
+        if options.time_max <= time {
+            break;
         }
-        {
-            let file: std::fs::File = match std::fs::File::create("vs.bin") {
-                Ok(file) => file,
-                Err(_) => {
-                    println!("failed to open file");
-                    return Err(());
-                }
-            };
-            let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
-            let _size: usize = match std::io::Write::write(&mut stream, &vs) {
-                Ok(size) => size,
-                Err(_) => {
-                    println!("file write failed");
-                    return Err(());
-                }
-            };
+    }
+    let path: PathBuf = output.join("checkpoint.bin");
+    if let Err(error) = write_checkpoint(&path, &simulator) {
+        eprintln!("failed to write checkpoint: {}", error);
+    }
+}
+
+/// Runs the members of the ensemble and writes their observables and the statistics.
+fn run_ensemble(options: &Options) {
+    let results: Vec<RunResult> = match options.get_ensemble().run() {
+        Ok(results) => results,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
         }
+    };
+    let summaries: Vec<Summary> = ensemble::summarise(&results);
+    println!(
+        "{} runs at {} grid points finished",
+        results.len(),
+        summaries.len()
+    );
+    let output: &Path = &options.output;
+    let result = std::fs::create_dir_all(output)
+        .and_then(|_| {
+            write_csv(&output.join("runs.csv"), |writer| {
+                ensemble::write_runs(writer, &results)
+            })
+        })
+        .and_then(|_| {
+            write_csv(&output.join("ensemble.csv"), |writer| {
+                ensemble::write_summaries(writer, &summaries)
+            })
+        });
+    if let Err(error) = result {
+        eprintln!("failed to write to {}: {}", output.display(), error);
+        std::process::exit(1);
     }
-    Ok(())
 }
 
-fn fit(val: f64, rgb: &mut [u8]) {
-    const PI: f64 = std::f64::consts::PI;
-    let rgb_f64: [f64; 3] = [
-        0.5 * (1. + (2. * PI * (val + 0. / 3.)).sin()),
-        0.5 * (1. + (2. * PI * (val + 1. / 3.)).sin()),
-        0.5 * (1. + (2. * PI * (val + 2. / 3.)).sin()),
-    ];
-    rgb[0] = (255. * rgb_f64[0]) as u8;
-    rgb[1] = (255. * rgb_f64[1]) as u8;
-    rgb[2] = (255. * rgb_f64[2]) as u8;
+fn write_csv<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut dyn std::io::Write) -> std::io::Result<()>,
+{
+    let file: std::fs::File = std::fs::File::create(path)?;
+    let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
+    write(&mut stream)?;
+    std::io::Write::flush(&mut stream)
+}
+
+fn read_checkpoint(path: &Path) -> Result<Simulator, CheckpointError> {
+    let file: std::fs::File = std::fs::File::open(path)?;
+    let mut stream: std::io::BufReader<std::fs::File> = std::io::BufReader::new(file);
+    Simulator::read_checkpoint(&mut stream)
+}
+
+fn write_checkpoint(path: &Path, simulator: &Simulator) -> Result<(), CheckpointError> {
+    let file: std::fs::File = std::fs::File::create(path)?;
+    let mut stream: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(file);
+    simulator.write_checkpoint(&mut stream)?;
+    std::io::Write::flush(&mut stream)?;
+    Ok(())
 }
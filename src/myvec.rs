@@ -1,6 +1,9 @@
 use crate::simulator::NDIMS;
 
-#[derive(Clone, Copy)]
+/// N.B.: The layout is identical to `[f64; NDIMS]`,
+///   so that an array of vectors can be shared as a flat array of numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(transparent)]
 pub struct MyVec {
     vec: [f64; NDIMS],
 }
@@ -11,6 +14,41 @@ impl MyVec {
     }
 }
 
+/// Pseudo-vector such as the angular velocity,
+///   which only has the component normal to the plane in two dimensions.
+#[cfg(not(feature = "3d"))]
+pub type PseudoVec = f64;
+#[cfg(feature = "3d")]
+pub type PseudoVec = MyVec;
+
+/// Cross product of a pseudo-vector and a vector, e.g. the velocity due to the rotation.
+#[cfg(not(feature = "3d"))]
+pub fn cross(a: PseudoVec, b: MyVec) -> MyVec {
+    MyVec::new([-a * b[1], a * b[0]])
+}
+
+/// Cross product of a pseudo-vector and a vector, e.g. the velocity due to the rotation.
+#[cfg(feature = "3d")]
+pub fn cross(a: PseudoVec, b: MyVec) -> MyVec {
+    MyVec::new([
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ])
+}
+
+/// Cross product of two vectors, e.g. the torque.
+#[cfg(not(feature = "3d"))]
+pub fn wedge(a: MyVec, b: MyVec) -> PseudoVec {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// Cross product of two vectors, e.g. the torque.
+#[cfg(feature = "3d")]
+pub fn wedge(a: MyVec, b: MyVec) -> PseudoVec {
+    cross(a, b)
+}
+
 impl core::ops::Add for MyVec {
     type Output = MyVec;
     fn add(self, other: MyVec) -> MyVec {
@@ -24,6 +24,17 @@ impl Random {
         random.gen_range(0., 0.);
         random
     }
+    /// Returns the internal state, which is used to resume the sequence.
+    pub fn get_state(&self) -> (u64, u64) {
+        (self.state, self.inc)
+    }
+    /// Constructor resuming the sequence from the state given by [`Random::get_state`].
+    ///
+    /// * `state` - Internal state.
+    /// * `inc` - Increment of the sequence.
+    pub fn from_state(state: u64, inc: u64) -> Random {
+        Random { state, inc }
+    }
     /// Returns a random number which is larger than `min` and smaller than `max`.
     ///   
     /// * `min` - Small limit.
@@ -61,4 +72,22 @@ impl Random {
         // cast it into f64
         min + (max - min) * (val as f64 / u32::MAX as f64)
     }
+    /// Returns a random number following the normal distribution.
+    ///
+    /// * `mean` - Mean.
+    /// * `stddev` - Standard deviation.
+    ///
+    /// The Box-Muller transform is adopted.
+    pub fn gen_normal(&mut self, mean: f64, stddev: f64) -> f64 {
+        loop {
+            let u0: f64 = self.gen_range(0., 1.);
+            let u1: f64 = self.gen_range(0., 1.);
+            // avoid log(0)
+            if 0. == u0 {
+                continue;
+            }
+            let z: f64 = (-2. * u0.ln()).sqrt() * (2. * std::f64::consts::PI * u1).cos();
+            break mean + stddev * z;
+        }
+    }
 }
@@ -1,78 +1,689 @@
+mod binning;
+mod boundary;
+mod buoyancy;
 mod cell;
+mod checkpoint;
+mod collision_model;
+mod config;
 mod debug;
+mod diffusion;
 mod event;
 mod extrema;
+mod generator;
+mod initial_condition;
+mod lees_edwards;
+mod observables;
 pub mod particle;
+mod scalar_transport;
 mod scheduler;
+mod size_distribution;
+mod snapshot;
+mod structure;
 mod util;
 
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::io::{Read, Write};
+use std::sync::Arc;
 
+use crate::myvec::MyVec;
+use crate::random::Random;
+pub use binning::{Binning, Fields};
+pub use boundary::{Boundary, ScalarCondition, WallMotion};
+pub use buoyancy::Buoyancy;
 use cell::Cell;
-pub use particle::Particle;
+pub use checkpoint::CheckpointError;
+pub use collision_model::{
+    CollisionModel, ConstantRestitution, Contact, Elastic, RoughSphere, Viscoelastic,
+};
+pub use config::{ConfigError, SimulationConfig, SimulationConfigBuilder};
+pub use diffusion::Diffusion;
+pub use event::EventCounts;
+pub use extrema::Extrema;
+pub use generator::{Placement, Stratification, VelocityDistribution};
+pub use initial_condition::{InitialCondition, InitialConditionError};
+use observables::Accumulators;
+pub use observables::{Observables, WallStatistics};
+pub use particle::{FreeFlights, Images, Particle, Particles};
+pub use scalar_transport::{Conductive, Passive, ScalarTransport};
 use scheduler::Scheduler;
+pub use scheduler::SchedulerKind;
+pub use size_distribution::SizeDistribution;
+pub use snapshot::{Snapshot, SnapshotError, SnapshotFormat, SnapshotSeries};
+pub use structure::{AnalysisError, RadialDistribution, StructureFactor};
 
+#[cfg(not(feature = "3d"))]
 pub const NDIMS: usize = 2usize;
+#[cfg(feature = "3d")]
+pub const NDIMS: usize = 3usize;
 
+/// Geometry of the domain and the physical parameters shared by the events.
 pub struct Domain {
     lengths: [f64; NDIMS],
     periodicities: [bool; NDIMS],
+    /// Conditions on the negative and positive faces in each direction.
+    boundaries: [Extrema<Boundary>; NDIMS],
+    /// Law of the momentum exchange between colliding particles.
+    collision_model: Arc<dyn CollisionModel>,
+    /// Constant acceleration felt by all particles.
+    gravity: MyVec,
+    /// Transport models of the scalars carried by each particle.
+    scalar_transports: Vec<Arc<dyn ScalarTransport>>,
+    /// Acceleration depending on the first scalar value.
+    buoyancy: Option<Buoyancy>,
+    /// Motions of the walls on the negative and positive faces in each direction.
+    wall_motions: [Extrema<WallMotion>; NDIMS],
+    /// Number of cells in each direction.
+    ncells: [usize; NDIMS],
+    /// Shear rate imposed by the Lees-Edwards boundaries.
+    shear_rate: Option<f64>,
+}
+
+impl Domain {
+    pub fn new(config: &SimulationConfig) -> Domain {
+        Domain {
+            lengths: config.lengths(),
+            periodicities: config.periodicities(),
+            boundaries: config.boundaries().clone(),
+            collision_model: config.collision_model(),
+            gravity: MyVec::new(config.gravity()),
+            scalar_transports: config.scalar_transports(),
+            buoyancy: config.buoyancy(),
+            wall_motions: config.wall_motions(),
+            ncells: config
+                .lengths()
+                .map(|length: f64| cell::get_ncells(length, config.cell_size())),
+            shear_rate: config.shear_rate(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Domain {
+    /// Periodic box shared by the tests, sheared by the Lees-Edwards boundaries if `shear_rate` is given.
+    pub fn periodic(lengths: [f64; NDIMS], shear_rate: Option<f64>) -> Domain {
+        let mut builder = SimulationConfig::builder()
+            .lengths(lengths)
+            .boundaries(boundary::get_periodic_boundaries());
+        if let Some(shear_rate) = shear_rate {
+            builder = builder.lees_edwards(shear_rate);
+        }
+        Domain::new(&builder.build().unwrap())
+    }
 }
 
 pub struct Simulator {
     time: f64,
     domain: Domain,
-    sync_rate: f64,
-    particles: Vec<Rc<RefCell<Particle>>>,
-    cells: Vec<Rc<RefCell<Cell>>>,
-    scheduler: Scheduler,
+    config: SimulationConfig,
+    particles: Particles,
+    cells: Vec<Cell>,
+    scheduler: Box<dyn Scheduler>,
+    rng: Random,
+    /// Numbers of the events processed since the beginning.
+    counts: EventCounts,
+    /// Quantities accumulated since the latest synchronisation.
+    accumulators: Accumulators,
 }
 
 impl Simulator {
-    pub fn new(sync_rate: f64, lengths: [f64; NDIMS], nparticles: usize, seed: f64) -> Simulator {
-        let domain = Domain {
-            lengths,
-            periodicities: {
-                let mut periodicities = [true; NDIMS];
-                if 1 < NDIMS {
-                    periodicities[1] = false;
-                }
-                periodicities
-            },
-        };
+    /// Builds a simulator with `nparticles` placed by the generators given by the configuration.
+    ///
+    /// It fails when all of them cannot be placed, e.g. beyond `max_volume_fraction`.
+    pub fn new(config: SimulationConfig) -> Result<Simulator, InitialConditionError> {
+        let domain = Domain::new(&config);
         let time: f64 = 0.;
-        let (ncells, cells): ([usize; NDIMS], Vec<Rc<RefCell<Cell>>>) = cell::init_cells(&domain);
-        let particles: Vec<Rc<RefCell<Particle>>> =
-            particle::init_particles(&domain, &ncells, &cells, nparticles, time, seed);
-        let mut scheduler = Scheduler::new(&cells);
-        event::init_events(&domain, &cells, &mut scheduler);
+        let (ncells, mut cells): ([usize; NDIMS], Vec<Cell>) =
+            cell::init_cells(&domain, config.cell_size());
+        let mut rng = Random::new(config.seed());
+        let particles: Particles =
+            particle::init_particles(&domain, &ncells, &mut cells, &config, time, &mut rng)?;
+        Ok(Simulator::assemble(
+            config, domain, time, particles, cells, rng,
+        ))
+    }
+
+    /// Builds a simulator from the particles given explicitly,
+    ///   instead of placing `nparticles` randomly following the size distribution.
+    ///
+    /// The particles should be inside the domain without overlapping each other.
+    pub fn from_initial_condition(
+        config: SimulationConfig,
+        initial_condition: &InitialCondition,
+    ) -> Result<Simulator, InitialConditionError> {
+        let domain = Domain::new(&config);
+        let time: f64 = 0.;
+        let (ncells, mut cells): ([usize; NDIMS], Vec<Cell>) =
+            cell::init_cells(&domain, config.cell_size());
+        let rng = Random::new(config.seed());
+        let particles: Particles =
+            particle::init_particles_from(&domain, &ncells, &mut cells, initial_condition, time)?;
+        Ok(Simulator::assemble(
+            config, domain, time, particles, cells, rng,
+        ))
+    }
+
+    /// Schedules the initial events of the placed particles.
+    fn assemble(
+        config: SimulationConfig,
+        domain: Domain,
+        time: f64,
+        particles: Particles,
+        mut cells: Vec<Cell>,
+        rng: Random,
+    ) -> Simulator {
+        let mut scheduler: Box<dyn Scheduler> = scheduler::new(config.scheduler(), &cells);
+        event::init_events(&domain, &particles, &mut cells, scheduler.as_mut());
         Simulator {
             domain,
             time,
-            sync_rate,
+            config,
             particles,
             cells,
             scheduler,
+            rng,
+            counts: EventCounts::default(),
+            accumulators: Accumulators::default(),
         }
     }
 
-    pub fn integrate(&mut self) {
+    /// Processes events until the next synchronisation,
+    ///   after which all particles are at the same time.
+    pub fn integrate(&mut self) -> EventCounts {
+        let mut counts = EventCounts::default();
         self.time = event::process_events(
             &self.domain,
-            &self.particles,
-            &self.cells,
-            &mut self.scheduler,
-            self.sync_rate,
+            &mut self.particles,
+            &mut self.cells,
+            self.scheduler.as_mut(),
+            &mut self.rng,
+            self.config.sync_rate(),
+            &mut counts,
+            &mut self.accumulators,
+        );
+        self.counts += counts;
+        counts
+    }
+
+    /// Processes all events until the given time,
+    ///   and updates all particles to that time.
+    ///
+    /// N.B.: The given time should not be earlier than the current time,
+    ///   which is asserted in debug builds, while nothing happens in release builds.
+    pub fn advance_to(&mut self, time: f64) -> EventCounts {
+        debug_assert!(
+            self.time <= time,
+            "cannot go back from {} to {}",
+            self.time,
+            time
+        );
+        let mut counts = EventCounts::default();
+        if time < self.time {
+            return counts;
+        }
+        while event::get_next_event_time(&self.particles, &mut self.cells, self.scheduler.as_mut())
+            <= time
+        {
+            self.process_event(&mut counts);
+        }
+        event::synchronise(&self.domain, time, &mut self.particles);
+        self.time = time;
+        counts
+    }
+
+    /// Processes exactly the given number of events.
+    ///
+    /// N.B.: The particles are generally not at the same time afterwards.
+    pub fn step_events(&mut self, nevents: usize) -> EventCounts {
+        let mut counts = EventCounts::default();
+        for _ in 0..nevents {
+            self.process_event(&mut counts);
+        }
+        counts
+    }
+
+    /// Processes events until the given predicate, which is evaluated after each event, holds.
+    ///
+    /// N.B.: The particles are generally not at the same time afterwards.
+    pub fn run_until<F>(&mut self, mut predicate: F) -> EventCounts
+    where
+        F: FnMut(&Simulator) -> bool,
+    {
+        let mut counts = EventCounts::default();
+        loop {
+            self.process_event(&mut counts);
+            if predicate(self) {
+                break counts;
+            }
+        }
+    }
+
+    /// Changes the interval between the synchronisations,
+    ///   where the next one happens after the new interval from the current time.
+    pub fn set_sync_rate(&mut self, sync_rate: f64) -> Result<(), ConfigError> {
+        self.config.set_sync_rate(sync_rate)?;
+        event::reschedule_synchronisation(
+            &mut self.cells,
+            self.scheduler.as_mut(),
+            self.time + sync_rate,
+        );
+        Ok(())
+    }
+
+    fn process_event(&mut self, counts: &mut EventCounts) {
+        let mut count = EventCounts::default();
+        self.time = event::process_event(
+            &self.domain,
+            &mut self.particles,
+            &mut self.cells,
+            self.scheduler.as_mut(),
+            &mut self.rng,
+            self.config.sync_rate(),
+            &mut count,
+            &mut self.accumulators,
         );
+        *counts += count;
+        self.counts += count;
+    }
+
+    /// Saves the whole state, from which [`Simulator::read_checkpoint`] resumes the run.
+    ///
+    /// Only the built-in collision and scalar transport models can be saved.
+    pub fn write_checkpoint(&self, writer: &mut dyn Write) -> Result<(), CheckpointError> {
+        checkpoint::write(self, writer)
+    }
+
+    /// Restores the simulator saved by [`Simulator::write_checkpoint`],
+    ///   which reproduces the uninterrupted run bit-for-bit.
+    pub fn read_checkpoint(reader: &mut dyn Read) -> Result<Simulator, CheckpointError> {
+        checkpoint::read(reader)
+    }
+
+    /// Returns the time of the latest event, or the one given to [`Simulator::advance_to`].
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns the positions, velocities, radii, and scalars of all particles at the current time.
+    pub fn get_snapshot(&self) -> Snapshot {
+        Snapshot::new(&self.domain, self.time, &self.particles)
+    }
+
+    /// Returns the observables evaluated at the latest synchronisation.
+    pub fn get_observables(&self) -> Option<&Observables> {
+        self.accumulators.latest.as_ref()
+    }
+
+    /// Starts sampling the Eulerian averages at each synchronisation,
+    ///   or stops it if `None` is given.
+    pub fn set_binning(&mut self, binning: Option<Binning>) {
+        self.accumulators.binning = binning;
     }
 
-    pub fn get_particles(&self) -> &Vec<Rc<RefCell<Particle>>> {
+    /// Returns the Eulerian averages sampled so far.
+    pub fn get_binning(&self) -> Option<&Binning> {
+        self.accumulators.binning.as_ref()
+    }
+
+    /// Discards the samples of the Eulerian averages, e.g. after the initial transient.
+    pub fn reset_binning(&mut self) {
+        if let Some(binning) = self.accumulators.binning.as_mut() {
+            binning.reset();
+        }
+    }
+
+    /// Starts sampling the mean-squared displacement and the velocity autocorrelation
+    ///   at each synchronisation, or stops it if `None` is given.
+    pub fn set_diffusion(&mut self, diffusion: Option<Diffusion>) {
+        self.accumulators.diffusion = diffusion;
+    }
+
+    /// Returns the mean-squared displacement and the velocity autocorrelation sampled so far.
+    pub fn get_diffusion(&self) -> Option<&Diffusion> {
+        self.accumulators.diffusion.as_ref()
+    }
+
+    /// Discards the samples of the mean-squared displacement and the velocity autocorrelation.
+    pub fn reset_diffusion(&mut self) {
+        if let Some(diffusion) = self.accumulators.diffusion.as_mut() {
+            diffusion.reset();
+        }
+    }
+
+    /// Returns the exchanges on the negative and positive walls normal to `dim`
+    ///   per unit area per unit time, averaged since the beginning or the latest reset.
+    pub fn get_wall_statistics(&self, dim: usize) -> Option<Extrema<WallStatistics>> {
+        self.accumulators
+            .get_wall_statistics(&self.domain, self.time, dim)
+    }
+
+    /// Returns the Nusselt number of the walls normal to `dim` for the given scalar,
+    ///   i.e. the mean magnitude of the scalar fluxes through both walls
+    ///   relative to `conductive_flux`, the flux of the purely conductive state
+    ///   (e.g. measured without gravity).
+    pub fn get_nusselt_number(
+        &self,
+        dim: usize,
+        scalar: usize,
+        conductive_flux: f64,
+    ) -> Option<f64> {
+        let statistics: Extrema<WallStatistics> = self.get_wall_statistics(dim)?;
+        let min: f64 = *statistics.min.scalar_fluxes.get(scalar)?;
+        let max: f64 = *statistics.max.scalar_fluxes.get(scalar)?;
+        Some(0.5 * (min.abs() + max.abs()) / conductive_flux)
+    }
+
+    /// Restarts the averaging of the wall exchanges, e.g. after the initial transient.
+    pub fn reset_wall_statistics(&mut self) {
+        self.accumulators.reset_wall_fluxes(self.time);
+    }
+
+    /// Returns the numbers of the events processed since the beginning.
+    pub fn get_event_counts(&self) -> EventCounts {
+        self.counts
+    }
+
+    /// Returns the number of collisions per particle per unit time since the beginning,
+    ///   which is computed from the current number of particles.
+    pub fn get_collision_frequency(&self) -> Option<f64> {
+        let nparticles: usize = self.particles.len();
+        if 0 == nparticles || self.time <= 0. {
+            return None;
+        }
+        let ncollisions: f64 = 2. * self.counts.inter_particle_collision as f64;
+        Some(ncollisions / (nparticles as f64 * self.time))
+    }
+
+    /// Returns the mean duration of the flights between two successive collisions,
+    ///   which is averaged over the particles in the domain.
+    pub fn get_mean_free_time(&self) -> Option<f64> {
+        self.get_mean_free_flight(|flights: &FreeFlights| flights.time)
+    }
+
+    /// Returns the mean length of the flights between two successive collisions,
+    ///   which is averaged over the particles in the domain.
+    pub fn get_mean_free_path(&self) -> Option<f64> {
+        self.get_mean_free_flight(|flights: &FreeFlights| flights.path)
+    }
+
+    fn get_mean_free_flight<F>(&self, get: F) -> Option<f64>
+    where
+        F: Fn(&FreeFlights) -> f64,
+    {
+        let (sum, nflights): (f64, usize) = self.particles.flights.iter().fold(
+            (0., 0),
+            |(sum, nflights): (f64, usize), flights: &FreeFlights| {
+                (sum + get(flights), nflights + flights.nflights())
+            },
+        );
+        if 0 == nflights {
+            None
+        } else {
+            Some(sum / nflights as f64)
+        }
+    }
+
+    /// Computes the radial distribution function g(r) of the current configuration
+    ///   on `nbins` uniform bins up to `cutoff`.
+    ///
+    /// Only the neighbouring cells are visited, and thus the cost is proportional to
+    ///   the number of particles for a fixed cutoff.
+    pub fn get_radial_distribution(
+        &self,
+        cutoff: f64,
+        nbins: usize,
+    ) -> Result<RadialDistribution, AnalysisError> {
+        structure::get_radial_distribution(&self.domain, self.time, &self.particles, cutoff, nbins)
+    }
+
+    /// Computes the static structure factor S(k) of the current configuration
+    ///   for the wave vectors allowed by the periodic directions up to `max_wavenumber`.
+    pub fn get_structure_factor(
+        &self,
+        max_wavenumber: f64,
+    ) -> Result<StructureFactor, AnalysisError> {
+        structure::get_structure_factor(&self.domain, self.time, &self.particles, max_wavenumber)
+    }
+
+    pub fn get_particles(&self) -> &Particles {
         &self.particles
     }
+
+    pub fn get_config(&self) -> &SimulationConfig {
+        &self.config
+    }
 }
 
-pub fn radius() -> f64 {
-    use particle::RADIUS;
-    RADIUS
+#[cfg(test)]
+mod test_new {
+    use super::{InitialConditionError, SimulationConfig, Simulator, NDIMS};
+
+    #[test]
+    fn case1() {
+        // particles beyond the cap on the volume fraction are not silently dropped
+        let builder = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(1024)
+            .max_volume_fraction(0.1);
+        let nplaced: usize = match Simulator::new(builder.clone().build().unwrap()) {
+            Err(InitialConditionError::Shortfall(1024, nplaced)) => nplaced,
+            _ => panic!("shortfall is not reported"),
+        };
+        assert!(0 < nplaced && nplaced < 1024);
+        // the placed ones fit
+        let simulator = Simulator::new(builder.nparticles(nplaced).build().unwrap()).unwrap();
+        assert_eq!(simulator.get_particles().len(), nplaced);
+    }
+}
+
+#[cfg(test)]
+mod test_advance {
+    use super::{Boundary, ConfigError, SimulationConfig, Simulator, NDIMS};
+
+    fn init_simulator() -> Simulator {
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(32)
+            .build()
+            .unwrap();
+        Simulator::new(config).unwrap()
+    }
+
+    #[test]
+    fn case1() {
+        // exactly the given number of events are processed
+        let mut simulator = init_simulator();
+        let counts = simulator.step_events(100);
+        assert_eq!(counts.total(), 100);
+        let time: f64 = simulator.get_time();
+        let counts = simulator.step_events(100);
+        assert_eq!(counts.total(), 100);
+        assert!(time <= simulator.get_time());
+    }
+
+    #[test]
+    fn case2() {
+        // all particles are brought to the given time,
+        //   passing the synchronisations at 0, 1, and 2
+        let mut simulator = init_simulator();
+        let counts = simulator.advance_to(2.5);
+        assert_eq!(counts.synchronisation, 3);
+        assert_eq!(simulator.get_time(), 2.5);
+        for &time in simulator.get_particles().times.iter() {
+            assert_eq!(time, 2.5);
+        }
+        // the synchronisations are not shifted
+        let counts = simulator.integrate();
+        assert_eq!(counts.synchronisation, 1);
+        assert_eq!(simulator.get_time(), 3.);
+    }
+
+    #[test]
+    fn case3() {
+        // stops at the first event satisfying the predicate
+        let mut simulator = init_simulator();
+        let counts = simulator.run_until(|simulator: &Simulator| 1.5 <= simulator.get_time());
+        assert!(1.5 <= simulator.get_time());
+        assert_eq!(counts.synchronisation, 2);
+        assert!(0 < counts.total());
+    }
+
+    #[test]
+    fn case4() {
+        // the synchronisation rate is changed at runtime
+        let mut simulator = init_simulator();
+        simulator.integrate();
+        simulator.integrate();
+        assert_eq!(simulator.get_time(), 1.);
+        simulator.set_sync_rate(0.25).unwrap();
+        assert_eq!(simulator.get_config().sync_rate(), 0.25);
+        simulator.integrate();
+        assert_eq!(simulator.get_time(), 1.25);
+        simulator.integrate();
+        assert_eq!(simulator.get_time(), 1.5);
+        assert_eq!(
+            simulator.set_sync_rate(-1.),
+            Err(ConfigError::InvalidSyncRate(-1.))
+        );
+    }
+
+    #[test]
+    fn case5() {
+        // the simulator is moved to another thread and continues there
+        let mut simulator = init_simulator();
+        simulator.integrate();
+        let handle = std::thread::spawn(move || {
+            simulator.integrate();
+            simulator
+        });
+        let simulator: Simulator = handle.join().unwrap();
+        assert_eq!(simulator.get_time(), 1.);
+    }
+
+    #[test]
+    fn case6() {
+        // the particles leaving the domain are removed,
+        //   while the remaining ones keep their identities and continue
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(64)
+            .boundary(0, Boundary::Outflow, Boundary::Outflow)
+            .build()
+            .unwrap();
+        let mut simulator = Simulator::new(config).unwrap();
+        let counts = simulator.advance_to(8.);
+        assert!(0 < counts.outflow);
+        let particles = simulator.get_particles();
+        assert_eq!(particles.len() + counts.outflow, 64);
+        let mut indices: Vec<usize> = particles.indices.clone();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), particles.len());
+        for p in 0..particles.len() {
+            for &cell_index in particles.cells[p].iter() {
+                assert!(simulator.cells[cell_index].particles.contains(&p));
+            }
+        }
+        simulator.integrate();
+    }
+}
+
+#[cfg(test)]
+mod test_statistics {
+    use super::boundary::get_periodic_boundaries;
+    use super::{SimulationConfig, Simulator, NDIMS};
+
+    /// Elastic particles in a periodic box without any force.
+    fn init_simulator() -> Simulator {
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(if 2 == NDIMS { 64 } else { 256 })
+            .boundaries(get_periodic_boundaries())
+            .buoyancy(None)
+            .restitution_coefficient(1.)
+            .build()
+            .unwrap();
+        Simulator::new(config).unwrap()
+    }
+
+    #[test]
+    fn case1() {
+        // the counts are accumulated over all calls,
+        //   and each collision is experienced by two particles
+        let mut simulator = init_simulator();
+        let mut counts = simulator.step_events(100);
+        counts += simulator.integrate();
+        counts += simulator.advance_to(4.5);
+        assert_eq!(simulator.get_event_counts(), counts);
+        let ncollisions: usize = simulator
+            .get_particles()
+            .flights
+            .iter()
+            .map(|flights| flights.ncollisions)
+            .sum();
+        assert_eq!(ncollisions, 2 * counts.inter_particle_collision);
+    }
+
+    #[test]
+    fn case2() {
+        // the mean free time is consistent with the collision frequency,
+        //   and the mean free path is given by the constant speeds
+        let mut simulator = init_simulator();
+        assert_eq!(simulator.get_mean_free_time(), None);
+        simulator.advance_to(50.);
+        let frequency: f64 = simulator.get_collision_frequency().unwrap();
+        let mean_free_time: f64 = simulator.get_mean_free_time().unwrap();
+        let mean_free_path: f64 = simulator.get_mean_free_path().unwrap();
+        assert!((frequency * mean_free_time - 1.).abs() < 0.1);
+        // bounded by the largest speed
+        let max_speed: f64 = (NDIMS as f64).sqrt();
+        assert!(0. < mean_free_path && mean_free_path < max_speed * mean_free_time);
+    }
+
+    #[test]
+    fn case3() {
+        // the energy is conserved and the collisions push the particles apart
+        let mut simulator = init_simulator();
+        assert_eq!(simulator.get_observables(), None);
+        simulator.integrate();
+        let initial = simulator.get_observables().unwrap().clone();
+        assert_eq!(initial.time, 0.);
+        simulator.advance_to(10.);
+        let observables = simulator.get_observables().unwrap();
+        assert_eq!(observables.time, 10.);
+        let error: f64 = observables.kinetic_energy / initial.kinetic_energy - 1.;
+        assert!(error.abs() < 1e-10);
+        for dim in 0..NDIMS {
+            assert!(observables.momentum[dim].abs() < 1e-10);
+            assert!(0. < observables.collisional_pressure[dim][dim]);
+        }
+        assert!(observables.get_pressure() > 0.);
+    }
+
+    #[test]
+    fn case4() {
+        // walls at rest in the second direction, which are pushed equally
+        //   and conduct the scalar from the hot ceiling to the cold floor
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(if 2 == NDIMS { 64 } else { 256 })
+            .buoyancy(None)
+            .restitution_coefficient(1.)
+            .build()
+            .unwrap();
+        let mut simulator = Simulator::new(config).unwrap();
+        assert_eq!(simulator.get_wall_statistics(0), None);
+        simulator.advance_to(20.);
+        simulator.reset_wall_statistics();
+        simulator.advance_to(220.);
+        let statistics = simulator.get_wall_statistics(1).unwrap();
+        let pressure: f64 = simulator.get_observables().unwrap().get_pressure();
+        for wall in [&statistics.min, &statistics.max] {
+            assert!(0 < wall.nreflections);
+            // frictionless walls
+            assert_eq!(wall.shear_stress, [0.; NDIMS]);
+            assert!((wall.pressure / pressure - 1.).abs() < 0.3);
+        }
+        assert!(statistics.min.scalar_fluxes[0] < 0.);
+        assert!(0. < statistics.max.scalar_fluxes[0]);
+        assert!(0. < simulator.get_nusselt_number(1, 0, 1.).unwrap());
+    }
 }
@@ -0,0 +1,375 @@
+use crate::myvec::MyVec;
+use crate::simulator::cell;
+use crate::simulator::particle::Particles;
+use crate::simulator::structure::AnalysisError;
+use crate::simulator::{Domain, NDIMS};
+
+/// Coarse-grained fields on a uniform grid,
+///   whose bins are ordered with the first direction varying fastest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fields {
+    /// Number of particles per unit volume.
+    pub densities: Vec<f64>,
+    /// Mass-weighted mean velocities, which are zero in the empty bins.
+    pub velocities: Vec<[f64; NDIMS]>,
+    /// Granular temperatures relative to the mean velocities of the bins.
+    pub temperatures: Vec<f64>,
+    /// Mean scalars of each bin, one for each scalar transport model.
+    pub vals: Vec<Vec<f64>>,
+}
+
+/// Sums of the particle quantities in each bin, from which the fields are evaluated.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldSums {
+    pub counts: Vec<usize>,
+    pub masses: Vec<f64>,
+    pub momenta: Vec<[f64; NDIMS]>,
+    /// Twice the kinetic energies.
+    pub energies: Vec<f64>,
+    pub vals: Vec<Vec<f64>>,
+}
+
+impl FieldSums {
+    fn new(nbins: usize, nscalars: usize) -> FieldSums {
+        FieldSums {
+            counts: vec![0; nbins],
+            masses: vec![0.; nbins],
+            momenta: vec![[0.; NDIMS]; nbins],
+            energies: vec![0.; nbins],
+            vals: vec![vec![0.; nscalars]; nbins],
+        }
+    }
+
+    fn add(&mut self, other: &FieldSums) {
+        for n in 0..self.counts.len() {
+            self.counts[n] += other.counts[n];
+            self.masses[n] += other.masses[n];
+            self.energies[n] += other.energies[n];
+            for dim in 0..NDIMS {
+                self.momenta[n][dim] += other.momenta[n][dim];
+            }
+            for (val, &other) in self.vals[n].iter_mut().zip(other.vals[n].iter()) {
+                *val += other;
+            }
+        }
+    }
+
+    /// Converts the sums over `nsamples` samples into the fields.
+    fn to_fields(&self, nsamples: usize, volume: f64) -> Fields {
+        let mut fields = Fields::default();
+        for n in 0..self.counts.len() {
+            let count: usize = self.counts[n];
+            let mass: f64 = self.masses[n];
+            fields
+                .densities
+                .push(count as f64 / (nsamples as f64 * volume));
+            if 0 == count {
+                fields.velocities.push([0.; NDIMS]);
+                fields.temperatures.push(0.);
+                fields.vals.push(vec![0.; self.vals[n].len()]);
+                continue;
+            }
+            let momentum: &[f64; NDIMS] = &self.momenta[n];
+            let energy: f64 = momentum.iter().map(|m: &f64| m * m).sum::<f64>() / mass;
+            fields
+                .velocities
+                .push(momentum.map(|momentum: f64| momentum / mass));
+            fields
+                .temperatures
+                .push(((self.energies[n] - energy) / (NDIMS * count) as f64).max(0.));
+            fields.vals.push(
+                self.vals[n]
+                    .iter()
+                    .map(|val: &f64| val / count as f64)
+                    .collect(),
+            );
+        }
+        fields
+    }
+}
+
+/// Eulerian averages of the particle quantities on a grid,
+///   which is independent of the cells used to find the events.
+///
+/// The particles are sampled at each synchronisation,
+///   and the running averages are taken over all samples since the latest reset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binning {
+    /// Number of bins in each direction.
+    pub(crate) nbins: [usize; NDIMS],
+    /// Number of bins of the velocity histograms.
+    pub(crate) nvelbins: usize,
+    /// Histograms cover the velocity components between `-max_velocity` and `max_velocity`.
+    pub(crate) max_velocity: f64,
+    /// Number of samples since the latest reset.
+    pub(crate) nsamples: usize,
+    pub(crate) sums: FieldSums,
+    pub(crate) latest: Fields,
+    pub(crate) averages: Fields,
+    /// Velocity histograms, one for each direction.
+    pub(crate) histograms: Vec<Vec<usize>>,
+    /// Number of the sampled velocities including the ones out of the histograms.
+    pub(crate) nvelocities: usize,
+}
+
+impl Binning {
+    /// Prepares `nbins` bins in each direction,
+    ///   and `nvelbins` velocity bins between `-max_velocity` and `max_velocity`.
+    pub fn new(
+        nbins: [usize; NDIMS],
+        nvelbins: usize,
+        max_velocity: f64,
+    ) -> Result<Binning, AnalysisError> {
+        if nbins.contains(&0) || 0 == nvelbins {
+            return Err(AnalysisError::InvalidNumberOfBins);
+        }
+        if !(max_velocity.is_finite() && 0. < max_velocity) {
+            return Err(AnalysisError::InvalidVelocityRange(max_velocity));
+        }
+        Ok(Binning {
+            nbins,
+            nvelbins,
+            max_velocity,
+            nsamples: 0,
+            sums: FieldSums::default(),
+            latest: Fields::default(),
+            averages: Fields::default(),
+            histograms: vec![vec![0; nvelbins]; NDIMS],
+            nvelocities: 0,
+        })
+    }
+
+    /// Discards the samples taken so far, e.g. after the initial transient.
+    pub fn reset(&mut self) {
+        *self = Binning::new(self.nbins, self.nvelbins, self.max_velocity).unwrap();
+    }
+
+    /// Returns the number of bins in each direction.
+    pub fn get_nbins(&self) -> [usize; NDIMS] {
+        self.nbins
+    }
+
+    /// Returns the number of samples since the latest reset.
+    pub fn get_nsamples(&self) -> usize {
+        self.nsamples
+    }
+
+    /// Returns the fields of the latest sample.
+    pub fn get_latest(&self) -> &Fields {
+        &self.latest
+    }
+
+    /// Returns the fields averaged over all samples,
+    ///   where the temperatures include the temporal fluctuations of the mean velocities.
+    pub fn get_averages(&self) -> &Fields {
+        &self.averages
+    }
+
+    /// Returns the centres of the velocity bins.
+    pub fn get_velocities(&self) -> Vec<f64> {
+        let width: f64 = 2. * self.max_velocity / self.nvelbins as f64;
+        (0..self.nvelbins)
+            .map(|n: usize| -self.max_velocity + width * (n as f64 + 0.5))
+            .collect()
+    }
+
+    /// Returns the probability density functions of the velocity components,
+    ///   one for each direction.
+    ///
+    /// N.B.: The velocities out of the histograms are included in the normalisation,
+    ///   and thus the integral is smaller than one if `max_velocity` is too small.
+    pub fn get_velocity_pdfs(&self) -> Vec<Vec<f64>> {
+        let width: f64 = 2. * self.max_velocity / self.nvelbins as f64;
+        let total: f64 = (self.nvelocities.max(1)) as f64 * width;
+        self.histograms
+            .iter()
+            .map(|histogram: &Vec<usize>| {
+                histogram
+                    .iter()
+                    .map(|&count: &usize| count as f64 / total)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Adds the synchronised particles to the running averages.
+    pub(crate) fn sample(&mut self, domain: &Domain, particles: &Particles) {
+        let nbins: usize = self.nbins.iter().product();
+        let nscalars: usize = domain.scalar_transports.len();
+        let bin_sizes: [f64; NDIMS] =
+            std::array::from_fn(|dim: usize| domain.lengths[dim] / self.nbins[dim] as f64);
+        let volume: f64 = bin_sizes.iter().product();
+        let width: f64 = 2. * self.max_velocity / self.nvelbins as f64;
+        let mut sums = FieldSums::new(nbins, nscalars);
+        for p in 0..particles.len() {
+            let pos: MyVec = particles.positions[p];
+            let vel: MyVec = particles.velocities[p];
+            let mass: f64 = particles.masses[p];
+            let indices: [usize; NDIMS] = std::array::from_fn(|dim: usize| {
+                ((pos[dim] / bin_sizes[dim]).max(0.) as usize).min(self.nbins[dim] - 1)
+            });
+            let n: usize = cell::get_index(NDIMS, &self.nbins, &indices);
+            sums.counts[n] += 1;
+            sums.masses[n] += mass;
+            sums.energies[n] += mass * (vel * vel);
+            for dim in 0..NDIMS {
+                sums.momenta[n][dim] += mass * vel[dim];
+                let k: f64 = (vel[dim] + self.max_velocity) / width;
+                if 0. <= k && k < self.nvelbins as f64 {
+                    self.histograms[dim][k as usize] += 1;
+                }
+            }
+            for (val, &pval) in sums.vals[n].iter_mut().zip(particles.vals[p].iter()) {
+                *val += pval;
+            }
+        }
+        if 0 == self.nsamples {
+            self.sums = FieldSums::new(nbins, nscalars);
+        }
+        self.sums.add(&sums);
+        self.nsamples += 1;
+        self.nvelocities += particles.len();
+        self.latest = sums.to_fields(1, volume);
+        self.averages = self.sums.to_fields(self.nsamples, volume);
+    }
+}
+
+#[cfg(test)]
+mod test_binning {
+    use super::Binning;
+    use crate::myvec::{MyVec, PseudoVec};
+    use crate::simulator::boundary::get_periodic_boundaries;
+    use crate::simulator::particle::{FreeFlights, Images, Particle, Particles};
+    use crate::simulator::{AnalysisError, Domain, SimulationConfig, Simulator, NDIMS};
+
+    fn init_particle(pos: f64, vel: f64, val: f64) -> Particle {
+        let mut p = MyVec::new([1.; NDIMS]);
+        p[0] = pos;
+        let mut v = MyVec::new([0.; NDIMS]);
+        v[0] = vel;
+        Particle {
+            index: 0,
+            rad: 0.5,
+            mass: 2.,
+            pos: p,
+            vel: v,
+            angvel: PseudoVec::default(),
+            time: 0.,
+            vals: vec![val],
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // two particles in the first bin and one in the second bin
+        let config = SimulationConfig::builder()
+            .lengths([8.; NDIMS])
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let mut nbins = [1; NDIMS];
+        nbins[0] = 4;
+        let volume: f64 = 2. * 8f64.powi(NDIMS as i32 - 1);
+        let mut binning = Binning::new(nbins, 4, 2.).unwrap();
+        let particles: Particles = [
+            init_particle(0.5, 1., 0.),
+            init_particle(1.5, -1., 1.),
+            init_particle(2.5, 0.5, 1.),
+        ]
+        .into_iter()
+        .collect();
+        binning.sample(&domain, &particles);
+        let fields = binning.get_latest();
+        assert_eq!(fields.densities, vec![2. / volume, 1. / volume, 0., 0.]);
+        assert_eq!(fields.velocities[0], [0.; NDIMS]);
+        assert_eq!(fields.velocities[1][0], 0.5);
+        assert_eq!(fields.temperatures, vec![2. / NDIMS as f64, 0., 0., 0.]);
+        assert_eq!(fields.vals[0], vec![0.5]);
+        assert_eq!(fields.vals[1], vec![1.]);
+        assert_eq!(fields.vals[2], vec![0.]);
+        // a single sample is averaged
+        assert_eq!(binning.get_averages(), fields);
+        assert_eq!(binning.get_velocities(), vec![-1.5, -0.5, 0.5, 1.5]);
+        let pdfs = binning.get_velocity_pdfs();
+        assert_eq!(pdfs[0], vec![0., 1. / 3., 1. / 3., 1. / 3.]);
+        assert_eq!(pdfs[1], vec![0., 0., 1., 0.]);
+    }
+
+    #[test]
+    fn case2() {
+        // running averages over the samples, which are discarded by the reset
+        let config = SimulationConfig::builder()
+            .lengths([8.; NDIMS])
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let volume: f64 = 8f64.powi(NDIMS as i32);
+        let mut binning = Binning::new([1; NDIMS], 2, 2.).unwrap();
+        binning.sample(&domain, &Particles::from_iter([init_particle(0.5, 1., 0.)]));
+        binning.sample(
+            &domain,
+            &Particles::from_iter([init_particle(0.5, -1., 1.)]),
+        );
+        let fields = binning.get_averages();
+        assert_eq!(binning.get_nsamples(), 2);
+        assert_eq!(fields.densities, vec![1. / volume]);
+        assert_eq!(fields.velocities, vec![[0.; NDIMS]]);
+        assert_eq!(fields.temperatures, vec![2. / NDIMS as f64]);
+        assert_eq!(fields.vals, vec![vec![0.5]]);
+        assert_eq!(binning.get_velocity_pdfs()[0], vec![0.25, 0.25]);
+        binning.reset();
+        assert_eq!(binning.get_nsamples(), 0);
+        assert_eq!(binning.get_velocity_pdfs()[0], vec![0., 0.]);
+    }
+
+    #[test]
+    fn case3() {
+        // invalid grids are rejected
+        assert_eq!(
+            Binning::new([0; NDIMS], 4, 1.),
+            Err(AnalysisError::InvalidNumberOfBins)
+        );
+        assert_eq!(
+            Binning::new([4; NDIMS], 0, 1.),
+            Err(AnalysisError::InvalidNumberOfBins)
+        );
+        assert_eq!(
+            Binning::new([4; NDIMS], 4, -1.),
+            Err(AnalysisError::InvalidVelocityRange(-1.))
+        );
+    }
+
+    #[test]
+    fn case4() {
+        // the simulator samples all particles at each synchronisation
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(64)
+            .boundaries(get_periodic_boundaries())
+            .buoyancy(None)
+            .build()
+            .unwrap();
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_binning(Some(Binning::new([4; NDIMS], 10, 2.).unwrap()));
+        simulator.advance_to(5.5);
+        // including the initial synchronisation
+        let binning = simulator.get_binning().unwrap();
+        assert_eq!(binning.get_nsamples(), 6);
+        let volume: f64 = 4f64.powi(NDIMS as i32);
+        let nparticles: f64 = binning
+            .get_averages()
+            .densities
+            .iter()
+            .map(|density: &f64| density * volume)
+            .sum();
+        assert!((nparticles - 64.).abs() < 1e-10);
+        // all velocities are in the histograms
+        let width: f64 = 0.4;
+        for pdf in binning.get_velocity_pdfs().iter() {
+            let integral: f64 = pdf.iter().sum::<f64>() * width;
+            assert!((integral - 1.).abs() < 1e-10);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::myvec::MyVec;
+#[cfg(test)]
+use crate::simulator::extrema::Extrema;
+use crate::simulator::NDIMS;
+
+/// Condition of the scalar value imposed on a wall.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScalarCondition {
+    /// The particle values are exchanged with the given wall values, one for each scalar,
+    ///   as much as [`ScalarTransport::exchange_with_wall`] decides.
+    ///
+    /// [`ScalarTransport::exchange_with_wall`]: crate::simulator::ScalarTransport::exchange_with_wall
+    Dirichlet(Vec<f64>),
+    /// The particle value is unchanged.
+    Neumann,
+}
+
+/// Condition imposed on one face of the domain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Boundary {
+    /// Particles leaving the domain come back from the opposite face.
+    /// Both faces in the same direction should be periodic.
+    Periodic,
+    /// The wall-normal velocity is reversed.
+    Specular(ScalarCondition),
+    /// The velocity is resampled from the Maxwellian distribution at the wall temperature,
+    ///   which should be positive so that the particles leave the wall.
+    Diffuse {
+        temperature: f64,
+        scalar: ScalarCondition,
+    },
+    /// Particles touching the face are removed from the system.
+    Outflow,
+}
+
+impl Boundary {
+    /// Checks if the parameters are meaningful for the particles carrying `nscalars` scalars.
+    pub fn is_valid(&self, nscalars: usize) -> bool {
+        let is_valid_scalar = |scalar: &ScalarCondition| match scalar {
+            ScalarCondition::Dirichlet(vals) => {
+                nscalars == vals.len() && vals.iter().all(|val: &f64| val.is_finite())
+            }
+            ScalarCondition::Neumann => true,
+        };
+        match self {
+            Boundary::Periodic | Boundary::Outflow => true,
+            Boundary::Specular(scalar) => is_valid_scalar(scalar),
+            Boundary::Diffuse {
+                temperature,
+                scalar,
+            } => temperature.is_finite() && 0. < *temperature && is_valid_scalar(scalar),
+        }
+    }
+
+    pub fn is_periodic(&self) -> bool {
+        matches!(self, Boundary::Periodic)
+    }
+
+    /// Returns the scalar condition if the face is a wall.
+    pub fn scalar_condition(&self) -> Option<&ScalarCondition> {
+        match self {
+            Boundary::Specular(scalar) | Boundary::Diffuse { scalar, .. } => Some(scalar),
+            _ => None,
+        }
+    }
+}
+
+/// Periodic faces in all directions, which are shared by the tests.
+#[cfg(test)]
+pub fn get_periodic_boundaries() -> [Extrema<Boundary>; NDIMS] {
+    std::array::from_fn(|_| Extrema::both(Boundary::Periodic))
+}
+
+/// Motion of a wall, which is stationary by default.
+///
+/// The wall position is displaced by `amplitude * sin(angular_frequency * time)`
+///   in the wall-normal direction, while the tangential `velocity` is felt by the particles
+///   through diffuse reflections, and thus is only allowed on diffuse walls
+///   (specular walls are frictionless).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WallMotion {
+    /// Tangential velocity, whose wall-normal component should be zero.
+    pub velocity: [f64; NDIMS],
+    pub amplitude: f64,
+    pub angular_frequency: f64,
+}
+
+impl WallMotion {
+    /// Checks if the parameters are meaningful for a wall normal to `dim`.
+    pub fn is_valid(&self, dim: usize) -> bool {
+        self.velocity.iter().all(|v: &f64| v.is_finite())
+            && 0. == self.velocity[dim]
+            && self.amplitude.is_finite()
+            && 0. <= self.amplitude
+            && self.angular_frequency.is_finite()
+    }
+
+    pub fn is_stationary(&self) -> bool {
+        !self.is_sliding() && !self.is_oscillating()
+    }
+
+    /// Checks if the wall moves tangentially.
+    pub fn is_sliding(&self) -> bool {
+        self.velocity.iter().any(|&v: &f64| 0. != v)
+    }
+
+    pub fn is_oscillating(&self) -> bool {
+        0. != self.amplitude && 0. != self.angular_frequency
+    }
+
+    /// Wall-normal displacement from the rest position.
+    pub fn get_displacement(&self, time: f64) -> f64 {
+        self.amplitude * (self.angular_frequency * time).sin()
+    }
+
+    /// Upper bound of the wall-normal speed.
+    pub fn get_max_speed(&self) -> f64 {
+        (self.amplitude * self.angular_frequency).abs()
+    }
+
+    /// Upper bound of the wall-normal acceleration.
+    pub fn get_max_acceleration(&self) -> f64 {
+        self.amplitude * self.angular_frequency.powi(2)
+    }
+
+    /// Wall-normal velocity.
+    pub fn get_normal_velocity(&self, time: f64) -> f64 {
+        self.amplitude * self.angular_frequency * (self.angular_frequency * time).cos()
+    }
+
+    /// Wall velocity including the wall-normal component.
+    pub fn get_velocity(&self, dim: usize, time: f64) -> MyVec {
+        let mut vel = MyVec::new(self.velocity);
+        vel[dim] = self.get_normal_velocity(time);
+        vel
+    }
+}
@@ -0,0 +1,24 @@
+use crate::myvec::MyVec;
+use crate::simulator::NDIMS;
+
+/// Body force depending on the first scalar value carried by each particle,
+///   which mimics the thermal buoyancy.
+///
+/// A particle feels `(val - reference) * acceleration` in addition to the gravity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Buoyancy {
+    pub acceleration: [f64; NDIMS],
+    pub reference: f64,
+}
+
+impl Buoyancy {
+    /// Checks if the parameters are meaningful.
+    pub fn is_valid(&self) -> bool {
+        self.acceleration.iter().all(|a: &f64| a.is_finite()) && self.reference.is_finite()
+    }
+
+    /// Acceleration felt by a particle with the given scalar value.
+    pub fn get_acceleration(&self, val: f64) -> MyVec {
+        (val - self.reference) * MyVec::new(self.acceleration)
+    }
+}
@@ -1,18 +1,11 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
+use std::collections::BinaryHeap;
 
 use crate::simulator::event::Event;
 use crate::simulator::extrema::Extrema;
-use crate::simulator::particle::Particle;
+use crate::simulator::lees_edwards::{FLOW_DIM, GRADIENT_DIM};
 use crate::simulator::util::vec_to_array;
 use crate::simulator::{Domain, NDIMS};
 
-/// Reference cell size.
-///
-/// N.B.: This is a "typical" size of a cell and is not necessarily the exact size.
-///       For safety give more than 4 times larger than the radius of particles.
-const CELL_SIZE: f64 = 3.;
-
 /// Used to take into account the periodicity.
 pub enum CellPosition {
     NegativeEdge,
@@ -23,24 +16,25 @@ pub enum CellPosition {
 pub struct Cell {
     pub index: usize,
     pub bounds: [Extrema<f64>; NDIMS],
-    pub particles: Rc<RefCell<Vec<Rc<RefCell<Particle>>>>>,
-    pub events: Rc<RefCell<Vec<Event>>>,
+    /// Slots of the particles overlapping with this cell.
+    pub particles: Vec<usize>,
+    /// Queue of the events, the earliest of which is on the top.
+    ///
+    /// N.B.: Some of them may have been invalidated by the changes of the particles,
+    ///   which are discarded when they come, or when the queue grows too long.
+    pub events: BinaryHeap<Event>,
+    /// Number of the events left by the latest discard of the invalidated events.
+    pub nkept: usize,
     pub positions: [CellPosition; NDIMS],
     pub neighbours: [Extrema<usize>; NDIMS],
 }
 
 #[allow(dead_code)]
-fn check_duplication(cell_index: usize, particles: &Ref<Vec<Rc<RefCell<Particle>>>>) {
+fn check_duplication(cell_index: usize, particles: &[usize]) {
     // check no duplication
     for (n, p) in particles.iter().enumerate() {
-        for q in particles[n + 1..].iter() {
-            if Rc::ptr_eq(p, q) {
-                let p: Ref<Particle> = p.borrow();
-                panic!(
-                    "particle {} at ({}, {}) is duplicated in cell {}",
-                    p.index, p.pos[0], p.pos[1], cell_index
-                );
-            }
+        if particles[n + 1..].contains(p) {
+            panic!("particle {} is duplicated in cell {}", p, cell_index);
         }
     }
 }
@@ -55,7 +49,7 @@ fn get_indices(ndims: usize, ncells: &[usize], mut index: usize) -> Vec<usize> {
     indices
 }
 
-fn get_index(ndims: usize, ncells: &[usize], indices: &[usize]) -> usize {
+pub fn get_index(ndims: usize, ncells: &[usize], indices: &[usize]) -> usize {
     if ndims != ncells.len() {
         panic!("invalid length: {}", ncells.len());
     }
@@ -109,55 +103,86 @@ fn get_neighbour(ncells: &[usize], dim: usize, index: usize) -> Extrema<usize> {
 }
 
 impl Cell {
-    pub fn append(&mut self, p: &Rc<RefCell<Particle>>) {
-        {
-            let mut particles: RefMut<Vec<Rc<RefCell<Particle>>>> = self.particles.borrow_mut();
-            particles.push(p.clone());
+    /// Finds the cells across the shear-gradient boundary which overlap the sliding image
+    ///   of a particle, i.e. the time-dependent counterpart of `neighbours`
+    ///   under the Lees-Edwards boundaries.
+    ///
+    /// # Arguments
+    /// * `domain` - the domain with the Lees-Edwards boundaries.
+    /// * `rad`    - radius of the particle.
+    /// * `pos`    - position of the image in the flow direction.
+    pub fn get_sheared_neighbours(&self, domain: &Domain, rad: f64, pos: f64) -> Vec<usize> {
+        let ncells: &[usize; NDIMS] = &domain.ncells;
+        let mut indices: Vec<usize> = get_indices(NDIMS, ncells, self.index);
+        // the image appears at the other edge
+        indices[GRADIENT_DIM] = match self.positions[GRADIENT_DIM] {
+            CellPosition::NegativeEdge => ncells[GRADIENT_DIM] - 1,
+            CellPosition::PositiveEdge => 0,
+            CellPosition::Centre => panic!("cell {} is not at the edge", self.index),
+        };
+        let n: i64 = ncells[FLOW_DIM] as i64;
+        let size: f64 = domain.lengths[FLOW_DIM] / n as f64;
+        let min: i64 = ((pos - rad) / size).floor() as i64;
+        let max: i64 = ((pos + rad) / size).floor() as i64;
+        let mut neighbours = Vec::<usize>::new();
+        for index in min..=max {
+            indices[FLOW_DIM] = index.rem_euclid(n) as usize;
+            neighbours.push(get_index(NDIMS, ncells, &indices));
         }
+        neighbours
+    }
+
+    pub fn append(&mut self, p: usize) {
+        self.particles.push(p);
         if cfg!(debug_assertions) {
-            check_duplication(self.index, &self.particles.borrow());
+            check_duplication(self.index, &self.particles);
         }
     }
 
-    pub fn remove(&mut self, p: &Rc<RefCell<Particle>>) {
-        {
-            let mut particles: RefMut<Vec<Rc<RefCell<Particle>>>> = self.particles.borrow_mut();
-            let pos: usize = particles.iter().position(|q| Rc::ptr_eq(p, q)).unwrap();
-            particles.remove(pos);
-        }
-        if cfg!(debug_assertions) {
-            check_duplication(self.index, &self.particles.borrow());
+    pub fn remove(&mut self, p: usize) {
+        let pos: usize = self.particles.iter().position(|&q| p == q).unwrap();
+        self.particles.remove(pos);
+    }
+
+    /// Returns the time of the coming event in this cell.
+    pub fn get_head_event_time(&self) -> f64 {
+        match self.events.peek() {
+            Some(event) => event.time,
+            None => f64::MAX,
         }
     }
 }
 
-pub fn init_cells(domain: &Domain) -> ([usize; NDIMS], Vec<Rc<RefCell<Cell>>>) {
+/// Number of cells in one direction, where at least three cells are required.
+pub fn get_ncells(length: f64, cell_size: f64) -> usize {
+    3usize.max((length / cell_size) as usize)
+}
+
+/// Creates cells which split the domain.
+///
+/// # Arguments
+/// * `domain`    - the domain to be split.
+/// * `cell_size` - a "typical" size of a cell, which is not necessarily the exact size;
+///   for safety give more than 4 times larger than the radius of particles.
+pub fn init_cells(domain: &Domain, cell_size: f64) -> ([usize; NDIMS], Vec<Cell>) {
     let lengths: &[f64; NDIMS] = &domain.lengths;
     // decide number of cells
     // require at least three cells for each direction
-    let ncells: [usize; NDIMS] = {
-        let mut ncells = Vec::<usize>::with_capacity(NDIMS);
-        for dim in 0..NDIMS {
-            ncells.push(3usize.max((lengths[dim] / CELL_SIZE) as usize));
-        }
-        vec_to_array(ncells)
-    };
+    let ncells: [usize; NDIMS] = lengths.map(|length: f64| get_ncells(length, cell_size));
     // create cells
-    let mut cells = Vec::<Rc<RefCell<Cell>>>::new();
+    let mut cells = Vec::<Cell>::new();
     for n in 0..ncells.iter().product() {
         let indices: [usize; NDIMS] = vec_to_array(get_indices(NDIMS, &ncells, n));
         let bounds: [Extrema<f64>; NDIMS] = {
             let mut bounds = Vec::<Extrema<f64>>::with_capacity(NDIMS);
             for dim in 0..NDIMS {
                 bounds.push(Extrema::<f64> {
-                    min: lengths[dim] / ncells[dim] as f64 * (indices[dim] + 0) as f64,
+                    min: lengths[dim] / ncells[dim] as f64 * indices[dim] as f64,
                     max: lengths[dim] / ncells[dim] as f64 * (indices[dim] + 1) as f64,
                 });
             }
             vec_to_array::<Extrema<f64>>(bounds)
         };
-        let particles = Rc::new(RefCell::new(Vec::<Rc<RefCell<Particle>>>::new()));
-        let events = Rc::new(RefCell::new(Vec::<Event>::new()));
         let positions: [CellPosition; NDIMS] = {
             let mut positions = Vec::<CellPosition>::with_capacity(NDIMS);
             for dim in 0..NDIMS {
@@ -181,12 +206,13 @@ pub fn init_cells(domain: &Domain) -> ([usize; NDIMS], Vec<Rc<RefCell<Cell>>>) {
         let cell = Cell {
             index: n,
             bounds,
-            particles,
-            events,
+            particles: Vec::new(),
+            events: BinaryHeap::new(),
+            nkept: 0,
             positions,
             neighbours,
         };
-        cells.push(Rc::new(RefCell::new(cell)));
+        cells.push(cell);
     }
     (ncells, cells)
 }
@@ -286,7 +312,7 @@ mod test_get_index {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "3d")))]
 mod test_get_neighbour {
     use super::get_neighbour;
     use crate::simulator::extrema::Extrema;
@@ -350,3 +376,83 @@ mod test_get_neighbour {
         );
     }
 }
+
+#[cfg(all(test, not(feature = "3d")))]
+mod test_get_sheared_neighbours {
+    use super::{init_cells, Cell};
+    use crate::simulator::boundary::get_periodic_boundaries;
+    use crate::simulator::{Domain, SimulationConfig};
+
+    #[test]
+    fn case1() {
+        // four by four cells whose size is 2
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([8., 8.])
+            .radius(0.25)
+            .cell_size(2.)
+            .boundaries(get_periodic_boundaries())
+            .lees_edwards(1.)
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        // from the top to the bottom
+        assert_eq!(cells[12].get_sheared_neighbours(&domain, 0.25, 3.), [1]);
+        assert_eq!(cells[12].get_sheared_neighbours(&domain, 0.25, 3.9), [1, 2]);
+        // from the bottom to the top, across the periodic boundary in the flow direction
+        assert_eq!(
+            cells[0].get_sheared_neighbours(&domain, 0.25, 0.1),
+            [15, 12]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "3d"))]
+mod test_get_neighbour_3d {
+    use super::get_neighbour;
+    use crate::simulator::extrema::Extrema;
+    use crate::simulator::NDIMS;
+
+    #[test]
+    fn neighbor_x() {
+        let ncells: [usize; NDIMS] = [3, 2, 4];
+        assert_eq!(
+            get_neighbour(&ncells, 0, 0),
+            Extrema::<usize> { min: 2, max: 1 }
+        );
+        assert_eq!(
+            get_neighbour(&ncells, 0, 23),
+            Extrema::<usize> { min: 22, max: 21 }
+        );
+    }
+
+    #[test]
+    fn neighbor_y() {
+        let ncells: [usize; NDIMS] = [3, 2, 4];
+        assert_eq!(
+            get_neighbour(&ncells, 1, 0),
+            Extrema::<usize> { min: 3, max: 3 }
+        );
+        assert_eq!(
+            get_neighbour(&ncells, 1, 23),
+            Extrema::<usize> { min: 20, max: 20 }
+        );
+    }
+
+    #[test]
+    fn neighbor_z() {
+        let ncells: [usize; NDIMS] = [3, 2, 4];
+        assert_eq!(
+            get_neighbour(&ncells, 2, 0),
+            Extrema::<usize> { min: 18, max: 6 }
+        );
+        assert_eq!(
+            get_neighbour(&ncells, 2, 8),
+            Extrema::<usize> { min: 2, max: 14 }
+        );
+        assert_eq!(
+            get_neighbour(&ncells, 2, 23),
+            Extrema::<usize> { min: 17, max: 5 }
+        );
+    }
+}
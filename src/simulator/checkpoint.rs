@@ -0,0 +1,1233 @@
+//! Versioned binary format to save and restore the whole state of a simulator.
+//!
+//! All numbers are stored in the little-endian byte order,
+//!   and the floating-point numbers are stored bitwise,
+//!   so that a restarted run reproduces the uninterrupted one.
+//! Particles and cells are referred to by their positions in the lists,
+//!   except in the invalidated events, whose particles may have been removed.
+
+use std::collections::BinaryHeap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use crate::myvec::{MyVec, PseudoVec};
+use crate::random::Random;
+use crate::simulator::binning::{Binning, FieldSums, Fields};
+use crate::simulator::boundary::{Boundary, ScalarCondition, WallMotion};
+use crate::simulator::buoyancy::Buoyancy;
+use crate::simulator::cell::{self, Cell};
+use crate::simulator::collision_model::{
+    CollisionModel, ConstantRestitution, Elastic, RoughSphere, Viscoelastic,
+};
+use crate::simulator::config::{ConfigError, SimulationConfig, SimulationConfigBuilder};
+use crate::simulator::diffusion::{Diffusion, Origin};
+use crate::simulator::event::{Event, EventCounts};
+use crate::simulator::extrema::Extrema;
+use crate::simulator::generator::{Placement, Stratification, VelocityDistribution};
+use crate::simulator::observables::{Accumulators, Observables, WallFlux};
+use crate::simulator::particle::{FreeFlights, Images, Particle, Particles, Stamp};
+use crate::simulator::scalar_transport::{Conductive, Passive, ScalarTransport};
+use crate::simulator::scheduler::{self, Scheduler, SchedulerKind};
+use crate::simulator::size_distribution::SizeDistribution;
+use crate::simulator::{Domain, Simulator, NDIMS};
+
+/// Identifies the format.
+const MAGIC: [u8; 8] = *b"COLLISON";
+/// Incremented whenever the layout changes after the format is released.
+pub const VERSION: u32 = 1;
+
+/// Reasons why a checkpoint cannot be written or read.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Underlying stream failed.
+    Io(std::io::Error),
+    /// The stream does not start with the magic number.
+    InvalidMagic,
+    /// The format version is not supported by this build.
+    UnsupportedVersion(u32),
+    /// The checkpoint is written by a build with the different number of dimensions.
+    DimensionMismatch(usize),
+    /// The model is user-defined and cannot be saved, or is unknown to this build.
+    UnsupportedModel(String),
+    /// The stored configuration is rejected.
+    InvalidConfig(ConfigError),
+    /// The stored data are inconsistent.
+    Corrupted(&'static str),
+}
+
+impl std::fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "{}", error),
+            CheckpointError::InvalidMagic => write!(f, "not a checkpoint"),
+            CheckpointError::UnsupportedVersion(version) => write!(
+                f,
+                "checkpoint version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            CheckpointError::DimensionMismatch(ndims) => write!(
+                f,
+                "checkpoint is {}-dimensional while this build is {}-dimensional",
+                ndims, NDIMS
+            ),
+            CheckpointError::UnsupportedModel(model) => {
+                write!(f, "model cannot be saved or restored: {}", model)
+            }
+            CheckpointError::InvalidConfig(error) => {
+                write!(f, "invalid configuration: {}", error)
+            }
+            CheckpointError::Corrupted(reason) => write!(f, "corrupted checkpoint: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(error: std::io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+/// Writes items to a stream.
+pub struct Encoder<'a> {
+    writer: &'a mut dyn Write,
+}
+
+impl Encoder<'_> {
+    pub fn put<T: Codec>(&mut self, item: &T) -> Result<(), CheckpointError> {
+        item.encode(self)
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) -> Result<(), CheckpointError> {
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+/// Reads items from a stream.
+pub struct Decoder<'a> {
+    reader: &'a mut dyn Read,
+    /// Number of the particles which can be referred to.
+    nparticles: usize,
+    /// Number of the cells which can be referred to.
+    ncells: usize,
+}
+
+impl Decoder<'_> {
+    pub fn get<T: Codec>(&mut self) -> Result<T, CheckpointError> {
+        T::decode(self)
+    }
+
+    fn get_bytes<const N: usize>(&mut self) -> Result<[u8; N], CheckpointError> {
+        let mut bytes = [0u8; N];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads the number of items to follow, which is bounded to reject broken streams.
+    pub fn get_len(&mut self) -> Result<usize, CheckpointError> {
+        const MAX_LEN: usize = 1 << 40;
+        let len: usize = self.get()?;
+        if MAX_LEN < len {
+            return Err(CheckpointError::Corrupted("too long sequence"));
+        }
+        Ok(len)
+    }
+
+    /// Reads a slot of a particle.
+    pub fn get_particle(&mut self) -> Result<usize, CheckpointError> {
+        let p: usize = self.get()?;
+        if self.nparticles <= p {
+            return Err(CheckpointError::Corrupted("particle index out of range"));
+        }
+        Ok(p)
+    }
+
+    /// Reads an index of a cell.
+    pub fn get_cell_index(&mut self) -> Result<usize, CheckpointError> {
+        let index: usize = self.get()?;
+        if self.ncells <= index {
+            return Err(CheckpointError::Corrupted("cell index out of range"));
+        }
+        Ok(index)
+    }
+}
+
+/// Items which can be stored in checkpoints.
+pub trait Codec: Sized {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError>;
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError>;
+}
+
+impl Codec for u8 {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put_bytes(&[*self])
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(decoder.get_bytes::<1>()?[0])
+    }
+}
+
+impl Codec for u32 {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put_bytes(&self.to_le_bytes())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(u32::from_le_bytes(decoder.get_bytes()?))
+    }
+}
+
+impl Codec for u64 {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put_bytes(&self.to_le_bytes())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(u64::from_le_bytes(decoder.get_bytes()?))
+    }
+}
+
+impl Codec for i64 {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put_bytes(&self.to_le_bytes())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(i64::from_le_bytes(decoder.get_bytes()?))
+    }
+}
+
+/// Stored as a 64-bit integer to be independent of the platform.
+impl Codec for usize {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&(*self as u64))
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let val: u64 = decoder.get()?;
+        usize::try_from(val).map_err(|_| CheckpointError::Corrupted("too large integer"))
+    }
+}
+
+impl Codec for f64 {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put_bytes(&self.to_le_bytes())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(f64::from_le_bytes(decoder.get_bytes()?))
+    }
+}
+
+impl Codec for bool {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&(*self as u8))
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CheckpointError::Corrupted("invalid boolean")),
+        }
+    }
+}
+
+impl Codec for String {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.len())?;
+        encoder.put_bytes(self.as_bytes())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let len: usize = decoder.get_len()?;
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| decoder.get::<u8>())
+            .collect::<Result<_, _>>()?;
+        String::from_utf8(bytes).map_err(|_| CheckpointError::Corrupted("invalid string"))
+    }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.len())?;
+        for item in self.iter() {
+            encoder.put(item)?;
+        }
+        Ok(())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let len: usize = decoder.get_len()?;
+        (0..len).map(|_| decoder.get::<T>()).collect()
+    }
+}
+
+impl<T: Codec, const N: usize> Codec for [T; N] {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        for item in self.iter() {
+            encoder.put(item)?;
+        }
+        Ok(())
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let items: Vec<T> = (0..N)
+            .map(|_| decoder.get::<T>())
+            .collect::<Result<_, _>>()?;
+        match items.try_into() {
+            Ok(items) => Ok(items),
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.is_some())?;
+        match self {
+            Some(item) => encoder.put(item),
+            None => Ok(()),
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        if decoder.get::<bool>()? {
+            Ok(Some(decoder.get()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Codec> Codec for Extrema<T> {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.min)?;
+        encoder.put(&self.max)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let min: T = decoder.get()?;
+        let max: T = decoder.get()?;
+        Ok(Extrema::<T> { min, max })
+    }
+}
+
+impl Codec for Stamp {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.counter)?;
+        encoder.put(&self.epoch)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let counter: u64 = decoder.get()?;
+        let epoch: u64 = decoder.get()?;
+        Ok(Stamp { counter, epoch })
+    }
+}
+
+impl Codec for MyVec {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        let vec: [f64; NDIMS] = std::array::from_fn(|dim: usize| self[dim]);
+        encoder.put(&vec)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(MyVec::new(decoder.get()?))
+    }
+}
+
+impl Codec for ScalarCondition {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        match self {
+            ScalarCondition::Dirichlet(vals) => {
+                encoder.put(&0u8)?;
+                encoder.put(vals)
+            }
+            ScalarCondition::Neumann => encoder.put(&1u8),
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(ScalarCondition::Dirichlet(decoder.get()?)),
+            1 => Ok(ScalarCondition::Neumann),
+            _ => Err(CheckpointError::Corrupted("invalid scalar condition")),
+        }
+    }
+}
+
+impl Codec for Boundary {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        match self {
+            Boundary::Periodic => encoder.put(&0u8),
+            Boundary::Specular(scalar) => {
+                encoder.put(&1u8)?;
+                encoder.put(scalar)
+            }
+            Boundary::Diffuse {
+                temperature,
+                scalar,
+            } => {
+                encoder.put(&2u8)?;
+                encoder.put(temperature)?;
+                encoder.put(scalar)
+            }
+            Boundary::Outflow => encoder.put(&3u8),
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(Boundary::Periodic),
+            1 => Ok(Boundary::Specular(decoder.get()?)),
+            2 => {
+                let temperature: f64 = decoder.get()?;
+                let scalar: ScalarCondition = decoder.get()?;
+                Ok(Boundary::Diffuse {
+                    temperature,
+                    scalar,
+                })
+            }
+            3 => Ok(Boundary::Outflow),
+            _ => Err(CheckpointError::Corrupted("invalid boundary")),
+        }
+    }
+}
+
+impl Codec for WallMotion {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.velocity)?;
+        encoder.put(&self.amplitude)?;
+        encoder.put(&self.angular_frequency)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let velocity: [f64; NDIMS] = decoder.get()?;
+        let amplitude: f64 = decoder.get()?;
+        let angular_frequency: f64 = decoder.get()?;
+        Ok(WallMotion {
+            velocity,
+            amplitude,
+            angular_frequency,
+        })
+    }
+}
+
+impl Codec for Buoyancy {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.acceleration)?;
+        encoder.put(&self.reference)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let acceleration: [f64; NDIMS] = decoder.get()?;
+        let reference: f64 = decoder.get()?;
+        Ok(Buoyancy {
+            acceleration,
+            reference,
+        })
+    }
+}
+
+impl Codec for SizeDistribution {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        match *self {
+            SizeDistribution::Monodisperse { radius } => {
+                encoder.put(&0u8)?;
+                encoder.put(&radius)
+            }
+            SizeDistribution::Bidisperse {
+                small,
+                large,
+                fraction,
+            } => {
+                encoder.put(&1u8)?;
+                encoder.put(&[small, large, fraction])
+            }
+            SizeDistribution::Lognormal { median, sigma, max } => {
+                encoder.put(&2u8)?;
+                encoder.put(&[median, sigma, max])
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(SizeDistribution::Monodisperse {
+                radius: decoder.get()?,
+            }),
+            1 => {
+                let [small, large, fraction]: [f64; 3] = decoder.get()?;
+                Ok(SizeDistribution::Bidisperse {
+                    small,
+                    large,
+                    fraction,
+                })
+            }
+            2 => {
+                let [median, sigma, max]: [f64; 3] = decoder.get()?;
+                Ok(SizeDistribution::Lognormal { median, sigma, max })
+            }
+            _ => Err(CheckpointError::Corrupted("invalid size distribution")),
+        }
+    }
+}
+
+impl Codec for Placement {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        let tag: u8 = match *self {
+            Placement::RandomSequential => 0,
+            Placement::SquareLattice => 1,
+            Placement::HexagonalLattice => 2,
+            Placement::Compression => 3,
+        };
+        encoder.put(&tag)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(Placement::RandomSequential),
+            1 => Ok(Placement::SquareLattice),
+            2 => Ok(Placement::HexagonalLattice),
+            3 => Ok(Placement::Compression),
+            _ => Err(CheckpointError::Corrupted("invalid placement")),
+        }
+    }
+}
+
+impl Codec for SchedulerKind {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        let tag: u8 = match *self {
+            SchedulerKind::BinaryHeap => 0,
+            SchedulerKind::CalendarQueue => 1,
+            SchedulerKind::TournamentTree => 2,
+            SchedulerKind::PairingHeap => 3,
+        };
+        encoder.put(&tag)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(SchedulerKind::BinaryHeap),
+            1 => Ok(SchedulerKind::CalendarQueue),
+            2 => Ok(SchedulerKind::TournamentTree),
+            3 => Ok(SchedulerKind::PairingHeap),
+            _ => Err(CheckpointError::Corrupted("invalid scheduler")),
+        }
+    }
+}
+
+impl Codec for VelocityDistribution {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        match *self {
+            VelocityDistribution::Uniform { max } => {
+                encoder.put(&0u8)?;
+                encoder.put(&max)
+            }
+            VelocityDistribution::Maxwellian { temperature } => {
+                encoder.put(&1u8)?;
+                encoder.put(&temperature)
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(VelocityDistribution::Uniform {
+                max: decoder.get()?,
+            }),
+            1 => Ok(VelocityDistribution::Maxwellian {
+                temperature: decoder.get()?,
+            }),
+            _ => Err(CheckpointError::Corrupted("invalid velocity distribution")),
+        }
+    }
+}
+
+impl Codec for Stratification {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        match *self {
+            Stratification::Diagonal => encoder.put(&0u8),
+            Stratification::Linear { dim, min, max } => {
+                encoder.put(&1u8)?;
+                encoder.put(&dim)?;
+                encoder.put(&[min, max])
+            }
+            Stratification::Step {
+                dim,
+                fraction,
+                min,
+                max,
+            } => {
+                encoder.put(&2u8)?;
+                encoder.put(&dim)?;
+                encoder.put(&[fraction, min, max])
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        match decoder.get::<u8>()? {
+            0 => Ok(Stratification::Diagonal),
+            1 => {
+                let dim: usize = decoder.get()?;
+                let [min, max]: [f64; 2] = decoder.get()?;
+                Ok(Stratification::Linear { dim, min, max })
+            }
+            2 => {
+                let dim: usize = decoder.get()?;
+                let [fraction, min, max]: [f64; 3] = decoder.get()?;
+                Ok(Stratification::Step {
+                    dim,
+                    fraction,
+                    min,
+                    max,
+                })
+            }
+            _ => Err(CheckpointError::Corrupted("invalid stratification")),
+        }
+    }
+}
+
+impl Codec for FreeFlights {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.ncollisions)?;
+        encoder.put(&self.last_time)?;
+        encoder.put(&self.last_speed)?;
+        encoder.put(&self.time)?;
+        encoder.put(&self.path)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(FreeFlights {
+            ncollisions: decoder.get()?,
+            last_time: decoder.get()?,
+            last_speed: decoder.get()?,
+            time: decoder.get()?,
+            path: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Images {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.counts)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Images {
+            counts: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for EventCounts {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.inter_particle_collision)?;
+        encoder.put(&self.move_to_neighbour)?;
+        encoder.put(&self.move_out_of_cell)?;
+        encoder.put(&self.wall_reflection)?;
+        encoder.put(&self.outflow)?;
+        encoder.put(&self.lees_edwards_shift)?;
+        encoder.put(&self.synchronisation)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(EventCounts {
+            inter_particle_collision: decoder.get()?,
+            move_to_neighbour: decoder.get()?,
+            move_out_of_cell: decoder.get()?,
+            wall_reflection: decoder.get()?,
+            outflow: decoder.get()?,
+            lees_edwards_shift: decoder.get()?,
+            synchronisation: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Observables {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.time)?;
+        encoder.put(&self.kinetic_energy)?;
+        encoder.put(&self.rotational_energy)?;
+        encoder.put(&self.temperature)?;
+        encoder.put(&self.momentum)?;
+        encoder.put(&self.val_means)?;
+        encoder.put(&self.val_variances)?;
+        encoder.put(&self.kinetic_pressure)?;
+        encoder.put(&self.collisional_pressure)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Observables {
+            time: decoder.get()?,
+            kinetic_energy: decoder.get()?,
+            rotational_energy: decoder.get()?,
+            temperature: decoder.get()?,
+            momentum: decoder.get()?,
+            val_means: decoder.get()?,
+            val_variances: decoder.get()?,
+            kinetic_pressure: decoder.get()?,
+            collisional_pressure: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Accumulators {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.since)?;
+        encoder.put(&self.virial)?;
+        encoder.put(&self.latest)?;
+        encoder.put(&self.binning)?;
+        encoder.put(&self.diffusion)?;
+        encoder.put(&self.walls_since)?;
+        encoder.put(&self.wall_fluxes)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Accumulators {
+            since: decoder.get()?,
+            virial: decoder.get()?,
+            latest: decoder.get()?,
+            binning: decoder.get()?,
+            diffusion: decoder.get()?,
+            walls_since: decoder.get()?,
+            wall_fluxes: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Origin {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.sample)?;
+        encoder.put(&self.time)?;
+        encoder.put(&self.indices)?;
+        encoder.put(&self.positions)?;
+        encoder.put(&self.velocities)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Origin {
+            sample: decoder.get()?,
+            time: decoder.get()?,
+            indices: decoder.get()?,
+            positions: decoder.get()?,
+            velocities: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Diffusion {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.nlags)?;
+        encoder.put(&self.interval)?;
+        encoder.put(&self.nsamples)?;
+        encoder.put(&self.origins)?;
+        encoder.put(&self.times)?;
+        encoder.put(&self.displacements)?;
+        encoder.put(&self.correlations)?;
+        encoder.put(&self.norigins)?;
+        encoder.put(&self.counts)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Diffusion {
+            nlags: decoder.get()?,
+            interval: decoder.get()?,
+            nsamples: decoder.get()?,
+            origins: decoder.get()?,
+            times: decoder.get()?,
+            displacements: decoder.get()?,
+            correlations: decoder.get()?,
+            norigins: decoder.get()?,
+            counts: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for WallFlux {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.nreflections)?;
+        encoder.put(&self.scalars)?;
+        encoder.put(&self.momentum)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(WallFlux {
+            nreflections: decoder.get()?,
+            scalars: decoder.get()?,
+            momentum: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Fields {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.densities)?;
+        encoder.put(&self.velocities)?;
+        encoder.put(&self.temperatures)?;
+        encoder.put(&self.vals)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Fields {
+            densities: decoder.get()?,
+            velocities: decoder.get()?,
+            temperatures: decoder.get()?,
+            vals: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for FieldSums {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.counts)?;
+        encoder.put(&self.masses)?;
+        encoder.put(&self.momenta)?;
+        encoder.put(&self.energies)?;
+        encoder.put(&self.vals)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(FieldSums {
+            counts: decoder.get()?,
+            masses: decoder.get()?,
+            momenta: decoder.get()?,
+            energies: decoder.get()?,
+            vals: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Binning {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.nbins)?;
+        encoder.put(&self.nvelbins)?;
+        encoder.put(&self.max_velocity)?;
+        encoder.put(&self.nsamples)?;
+        encoder.put(&self.sums)?;
+        encoder.put(&self.latest)?;
+        encoder.put(&self.averages)?;
+        encoder.put(&self.histograms)?;
+        encoder.put(&self.nvelocities)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Binning {
+            nbins: decoder.get()?,
+            nvelbins: decoder.get()?,
+            max_velocity: decoder.get()?,
+            nsamples: decoder.get()?,
+            sums: decoder.get()?,
+            latest: decoder.get()?,
+            averages: decoder.get()?,
+            histograms: decoder.get()?,
+            nvelocities: decoder.get()?,
+        })
+    }
+}
+
+impl Codec for Random {
+    fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        let (state, inc): (u64, u64) = self.get_state();
+        encoder.put(&state)?;
+        encoder.put(&inc)
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let state: u64 = decoder.get()?;
+        let inc: u64 = decoder.get()?;
+        Ok(Random::from_state(state, inc))
+    }
+}
+
+/// Writes the name and the parameters of a built-in model.
+fn put_model(
+    encoder: &mut Encoder,
+    parameters: Option<(&'static str, Vec<f64>)>,
+    model: &dyn std::fmt::Debug,
+) -> Result<(), CheckpointError> {
+    match parameters {
+        Some((name, parameters)) => {
+            encoder.put(&String::from(name))?;
+            encoder.put(&parameters)
+        }
+        None => Err(CheckpointError::UnsupportedModel(format!("{:?}", model))),
+    }
+}
+
+/// Reads the name and the parameters of a built-in model.
+fn get_model(decoder: &mut Decoder) -> Result<(String, Vec<f64>), CheckpointError> {
+    let name: String = decoder.get()?;
+    let parameters: Vec<f64> = decoder.get()?;
+    Ok((name, parameters))
+}
+
+/// Rebuilds a built-in collision model, which is given to the builder.
+fn set_collision_model(
+    builder: SimulationConfigBuilder,
+    name: &str,
+    parameters: &[f64],
+) -> Result<SimulationConfigBuilder, CheckpointError> {
+    match (name, parameters) {
+        ("elastic", []) => Ok(builder.collision_model(Elastic)),
+        ("constant_restitution", &[coefficient]) => {
+            Ok(builder.collision_model(ConstantRestitution { coefficient }))
+        }
+        ("viscoelastic", &[damping]) => Ok(builder.collision_model(Viscoelastic { damping })),
+        ("rough_sphere", &[normal, tangential]) => {
+            Ok(builder.collision_model(RoughSphere { normal, tangential }))
+        }
+        _ => Err(CheckpointError::UnsupportedModel(String::from(name))),
+    }
+}
+
+/// Rebuilds a built-in scalar transport model.
+fn get_scalar_transport(
+    name: &str,
+    parameters: &[f64],
+) -> Result<Arc<dyn ScalarTransport>, CheckpointError> {
+    match (name, parameters) {
+        ("conductive", &[coefficient]) => Ok(Arc::new(Conductive { coefficient })),
+        ("passive", []) => Ok(Arc::new(Passive)),
+        _ => Err(CheckpointError::UnsupportedModel(String::from(name))),
+    }
+}
+
+fn put_config(encoder: &mut Encoder, config: &SimulationConfig) -> Result<(), CheckpointError> {
+    encoder.put(&config.sync_rate())?;
+    encoder.put(&config.lengths())?;
+    encoder.put(&config.nparticles())?;
+    encoder.put(&config.seed())?;
+    encoder.put(&config.size_distribution())?;
+    encoder.put(&config.cell_size())?;
+    let collision_model: Arc<dyn CollisionModel> = config.collision_model();
+    put_model(encoder, collision_model.get_parameters(), &collision_model)?;
+    encoder.put(&config.gravity())?;
+    encoder.put(&config.max_volume_fraction())?;
+    encoder.put(config.boundaries())?;
+    encoder.put(&config.shear_rate())?;
+    encoder.put(&config.wall_motions())?;
+    let scalar_transports: Vec<Arc<dyn ScalarTransport>> = config.scalar_transports();
+    encoder.put(&scalar_transports.len())?;
+    for scalar_transport in scalar_transports.iter() {
+        put_model(encoder, scalar_transport.get_parameters(), scalar_transport)?;
+    }
+    encoder.put(&config.buoyancy())?;
+    encoder.put(&config.placement())?;
+    encoder.put(&config.velocity_distribution())?;
+    encoder.put(&config.stratification())?;
+    encoder.put(&config.scheduler())
+}
+
+fn get_config(decoder: &mut Decoder) -> Result<SimulationConfig, CheckpointError> {
+    let mut builder = SimulationConfig::builder()
+        .sync_rate(decoder.get()?)
+        .lengths(decoder.get()?)
+        .nparticles(decoder.get()?)
+        .seed(decoder.get()?)
+        .size_distribution(decoder.get()?)
+        .cell_size(decoder.get()?);
+    let (name, parameters): (String, Vec<f64>) = get_model(decoder)?;
+    builder = set_collision_model(builder, &name, &parameters)?;
+    builder = builder
+        .gravity(decoder.get()?)
+        .max_volume_fraction(decoder.get()?)
+        .boundaries(decoder.get()?);
+    if let Some(shear_rate) = decoder.get::<Option<f64>>()? {
+        builder = builder.lees_edwards(shear_rate);
+    }
+    let wall_motions: [Extrema<WallMotion>; NDIMS] = decoder.get()?;
+    for (dim, wall_motion) in wall_motions.iter().enumerate() {
+        builder = builder.wall_motion(dim, wall_motion.min, wall_motion.max);
+    }
+    let nscalars: usize = decoder.get_len()?;
+    // the length is not trusted to reserve the memory, which grows as the models are read
+    let mut scalar_transports = Vec::<Arc<dyn ScalarTransport>>::new();
+    for _ in 0..nscalars {
+        let (name, parameters): (String, Vec<f64>) = get_model(decoder)?;
+        scalar_transports.push(get_scalar_transport(&name, &parameters)?);
+    }
+    builder = builder
+        .scalar_transports(scalar_transports)
+        .buoyancy(decoder.get()?)
+        .placement(decoder.get()?)
+        .velocity_distribution(decoder.get()?)
+        .stratification(decoder.get()?)
+        .scheduler(decoder.get()?);
+    builder.build().map_err(CheckpointError::InvalidConfig)
+}
+
+/// Writes the whole state of the simulator.
+pub fn write(simulator: &Simulator, writer: &mut dyn Write) -> Result<(), CheckpointError> {
+    let particles: &Particles = &simulator.particles;
+    let mut encoder = Encoder { writer };
+    encoder.put_bytes(&MAGIC)?;
+    encoder.put(&VERSION)?;
+    encoder.put(&NDIMS)?;
+    put_config(&mut encoder, &simulator.config)?;
+    encoder.put(&simulator.time)?;
+    encoder.put(&simulator.rng)?;
+    encoder.put(&simulator.counts)?;
+    encoder.put(&simulator.accumulators)?;
+    // particles with the cells to which they belong
+    encoder.put(&particles.nepochs)?;
+    encoder.put(&particles.len())?;
+    for p in 0..particles.len() {
+        encoder.put(&particles.indices[p])?;
+        encoder.put(&particles.radii[p])?;
+        encoder.put(&particles.masses[p])?;
+        encoder.put(&particles.positions[p])?;
+        encoder.put(&particles.velocities[p])?;
+        encoder.put(&particles.angvels[p])?;
+        encoder.put(&particles.times[p])?;
+        encoder.put(&particles.vals[p])?;
+        encoder.put(&particles.flights[p])?;
+        encoder.put(&particles.images[p])?;
+        encoder.put(&particles.cells[p])?;
+        encoder.put(&particles.counters[p])?;
+        encoder.put(&particles.epochs[p])?;
+    }
+    // cells with the particles and the events, whose orders are kept,
+    //   including the invalidated events so that the queues are restored as they are
+    encoder.put(&simulator.cells.len())?;
+    for cell in simulator.cells.iter() {
+        encoder.put(&cell.particles)?;
+        encoder.put(&cell.nkept)?;
+        encoder.put(&cell.events.len())?;
+        for event in cell.events.iter() {
+            event.encode(&mut encoder)?;
+        }
+    }
+    Ok(())
+}
+
+/// Restores the simulator from the state written by [`write`].
+pub fn read(reader: &mut dyn Read) -> Result<Simulator, CheckpointError> {
+    let mut decoder = Decoder {
+        reader,
+        nparticles: 0,
+        ncells: 0,
+    };
+    if MAGIC != decoder.get_bytes::<8>()? {
+        return Err(CheckpointError::InvalidMagic);
+    }
+    let version: u32 = decoder.get()?;
+    if VERSION != version {
+        return Err(CheckpointError::UnsupportedVersion(version));
+    }
+    let ndims: usize = decoder.get()?;
+    if NDIMS != ndims {
+        return Err(CheckpointError::DimensionMismatch(ndims));
+    }
+    let config: SimulationConfig = get_config(&mut decoder)?;
+    let domain = Domain::new(&config);
+    let time: f64 = decoder.get()?;
+    let rng: Random = decoder.get()?;
+    let counts: EventCounts = decoder.get()?;
+    let accumulators: Accumulators = decoder.get()?;
+    let (_, mut cells): (_, Vec<Cell>) = cell::init_cells(&domain, config.cell_size());
+    decoder.ncells = cells.len();
+    // particles with the cells to which they belong
+    let mut particles = Particles {
+        nepochs: decoder.get()?,
+        ..Particles::default()
+    };
+    let nparticles: usize = decoder.get_len()?;
+    for _ in 0..nparticles {
+        let index: usize = decoder.get()?;
+        let rad: f64 = decoder.get()?;
+        let mass: f64 = decoder.get()?;
+        let pos: MyVec = decoder.get()?;
+        let vel: MyVec = decoder.get()?;
+        let angvel: PseudoVec = decoder.get()?;
+        let time: f64 = decoder.get()?;
+        let vals: Vec<f64> = decoder.get()?;
+        if config.scalar_transports().len() != vals.len() {
+            return Err(CheckpointError::Corrupted("inconsistent number of scalars"));
+        }
+        let flights: FreeFlights = decoder.get()?;
+        let images: Images = decoder.get()?;
+        let ncells: usize = decoder.get_len()?;
+        let cell_indices: Vec<usize> = (0..ncells)
+            .map(|_| decoder.get_cell_index())
+            .collect::<Result<_, _>>()?;
+        let counter: u64 = decoder.get()?;
+        let epochs: Vec<u64> = decoder.get()?;
+        if cell_indices.len() != epochs.len() {
+            return Err(CheckpointError::Corrupted(
+                "inconsistent number of memberships",
+            ));
+        }
+        let p: usize = particles.push(Particle {
+            index,
+            rad,
+            mass,
+            pos,
+            vel,
+            angvel,
+            time,
+            vals,
+            flights,
+            images,
+        });
+        particles.cells[p] = cell_indices;
+        particles.counters[p] = counter;
+        particles.epochs[p] = epochs;
+    }
+    decoder.nparticles = particles.len();
+    // cells with the particles and the events
+    if cells.len() != decoder.get::<usize>()? {
+        return Err(CheckpointError::Corrupted("inconsistent number of cells"));
+    }
+    for cell in cells.iter_mut() {
+        let nparticles: usize = decoder.get_len()?;
+        let ps: Vec<usize> = (0..nparticles)
+            .map(|_| decoder.get_particle())
+            .collect::<Result<_, _>>()?;
+        let nkept: usize = decoder.get()?;
+        let nevents: usize = decoder.get_len()?;
+        let events: Vec<Event> = (0..nevents)
+            .map(|_| Event::decode(&mut decoder))
+            .collect::<Result<_, _>>()?;
+        cell.particles = ps;
+        cell.nkept = nkept;
+        // a valid heap is kept as it is
+        cell.events = BinaryHeap::from(events);
+    }
+    // the scheduler gives the same order of the events as before, which is rebuilt
+    let scheduler: Box<dyn Scheduler> = scheduler::new(config.scheduler(), &cells);
+    Ok(Simulator {
+        time,
+        domain,
+        config,
+        particles,
+        cells,
+        scheduler,
+        rng,
+        counts,
+        accumulators,
+    })
+}
+
+#[cfg(test)]
+mod test_checkpoint {
+    use super::CheckpointError;
+    use crate::simulator::{
+        Binning, Boundary, Diffusion, ScalarCondition, SchedulerKind, SimulationConfig, Simulator,
+        NDIMS,
+    };
+
+    /// Asserts that all particles are bitwise identical.
+    fn assert_identical(a: &Simulator, b: &Simulator) {
+        let ps = a.get_particles();
+        let qs = b.get_particles();
+        assert_eq!(a.get_event_counts(), b.get_event_counts());
+        assert_eq!(a.get_observables(), b.get_observables());
+        assert_eq!(a.get_binning(), b.get_binning());
+        assert_eq!(a.get_diffusion(), b.get_diffusion());
+        assert_eq!(a.get_wall_statistics(1), b.get_wall_statistics(1));
+        assert_eq!(ps.len(), qs.len());
+        assert_eq!(ps.indices, qs.indices);
+        assert_eq!(ps.cells, qs.cells);
+        assert_eq!(ps.counters, qs.counters);
+        assert_eq!(ps.epochs, qs.epochs);
+        assert_eq!(ps.nepochs, qs.nepochs);
+        assert_eq!(ps.vals, qs.vals);
+        assert_eq!(ps.flights, qs.flights);
+        assert_eq!(ps.images, qs.images);
+        for n in 0..ps.len() {
+            assert_eq!(ps.times[n].to_bits(), qs.times[n].to_bits());
+            for dim in 0..NDIMS {
+                assert_eq!(
+                    ps.positions[n][dim].to_bits(),
+                    qs.positions[n][dim].to_bits()
+                );
+                assert_eq!(
+                    ps.velocities[n][dim].to_bits(),
+                    qs.velocities[n][dim].to_bits()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // restarted run reproduces the uninterrupted one,
+        //   where the diffuse walls consume random numbers
+        let wall = Boundary::Diffuse {
+            temperature: 0.5,
+            scalar: ScalarCondition::Dirichlet(vec![1.]),
+        };
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(64)
+            .boundary(1, wall.clone(), wall)
+            .build()
+            .unwrap();
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.set_binning(Some(Binning::new([4; NDIMS], 8, 2.).unwrap()));
+        simulator.set_diffusion(Some(Diffusion::new(4, 2).unwrap()));
+        for _ in 0..4 {
+            simulator.integrate();
+        }
+        let mut buffer = Vec::<u8>::new();
+        simulator.write_checkpoint(&mut buffer).unwrap();
+        let mut restarted = Simulator::read_checkpoint(&mut buffer.as_slice()).unwrap();
+        assert_identical(&simulator, &restarted);
+        for _ in 0..8 {
+            simulator.integrate();
+            restarted.integrate();
+        }
+        assert_identical(&simulator, &restarted);
+    }
+
+    #[test]
+    fn case2() {
+        // broken streams are rejected
+        let result = Simulator::read_checkpoint(&mut b"NOTACKPT".as_slice());
+        assert!(matches!(result, Err(CheckpointError::InvalidMagic)));
+        let simulator = Simulator::new(SimulationConfig::builder().build().unwrap()).unwrap();
+        let mut buffer = Vec::<u8>::new();
+        simulator.write_checkpoint(&mut buffer).unwrap();
+        buffer.truncate(buffer.len() / 2);
+        let result = Simulator::read_checkpoint(&mut buffer.as_slice());
+        assert!(matches!(result, Err(CheckpointError::Io(_))));
+    }
+
+    #[test]
+    fn case3() {
+        // all schedulers process the events in the same order,
+        //   and a restarted run keeps the scheduler
+        let builder = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(64);
+        let mut reference = Simulator::new(builder.clone().build().unwrap()).unwrap();
+        for _ in 0..4 {
+            reference.integrate();
+        }
+        for scheduler in [
+            SchedulerKind::CalendarQueue,
+            SchedulerKind::TournamentTree,
+            SchedulerKind::PairingHeap,
+        ] {
+            let config: SimulationConfig = builder.clone().scheduler(scheduler).build().unwrap();
+            let mut simulator = Simulator::new(config).unwrap();
+            for _ in 0..2 {
+                simulator.integrate();
+            }
+            let mut buffer = Vec::<u8>::new();
+            simulator.write_checkpoint(&mut buffer).unwrap();
+            let mut restarted = Simulator::read_checkpoint(&mut buffer.as_slice()).unwrap();
+            assert_eq!(restarted.get_config().scheduler(), scheduler);
+            for _ in 0..2 {
+                restarted.integrate();
+            }
+            assert_identical(&reference, &restarted);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::myvec::MyVec;
+
+/// Kinematic information of a colliding pair at the moment of contact.
+#[derive(Clone, Copy)]
+pub struct Contact {
+    /// Unit vector connecting the particle centres, from the first to the second particle.
+    pub normal: MyVec,
+    /// Velocity of the second particle relative to the first one at the contact point,
+    ///   including the contributions of the rotations.
+    pub vel: MyVec,
+    /// Reduced mass, which resists the impulse in the normal direction.
+    pub mass: f64,
+    /// Effective mass resisting the impulse in the tangential direction,
+    ///   which is reduced by the rotations.
+    pub tangential_mass: f64,
+}
+
+impl Contact {
+    /// Normal component of the relative velocity, which is negative when approaching.
+    pub fn get_normal_vel(&self) -> f64 {
+        self.vel * self.normal
+    }
+
+    /// Tangential component of the relative velocity.
+    pub fn get_tangential_vel(&self) -> MyVec {
+        self.vel - self.get_normal_vel() * self.normal
+    }
+
+    /// Impulse realising the given restitution coefficient in the normal direction.
+    fn get_normal_impulse(&self, restitution_coefficient: f64) -> MyVec {
+        -(1. + restitution_coefficient) * self.mass * self.get_normal_vel() * self.normal
+    }
+}
+
+/// Law deciding the momentum exchanged by a colliding pair.
+pub trait CollisionModel: std::fmt::Debug + Send + Sync {
+    /// Checks if the parameters are meaningful.
+    fn is_valid(&self) -> bool;
+
+    /// Impulse given to the second particle,
+    ///   while the first particle receives the opposite one.
+    fn get_impulse(&self, contact: &Contact) -> MyVec;
+
+    /// Checks if the impulse is always along the normal,
+    ///   so that the particles never start spinning.
+    fn is_smooth(&self) -> bool {
+        false
+    }
+
+    /// Name and parameters to save the model in checkpoints,
+    ///   which are only available for the built-in models.
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        None
+    }
+}
+
+/// Perfectly elastic smooth particles, conserving the kinetic energy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Elastic;
+
+impl CollisionModel for Elastic {
+    fn is_valid(&self) -> bool {
+        true
+    }
+
+    fn get_impulse(&self, contact: &Contact) -> MyVec {
+        contact.get_normal_impulse(1.)
+    }
+
+    fn is_smooth(&self) -> bool {
+        true
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("elastic", Vec::new()))
+    }
+}
+
+/// Smooth particles losing a constant fraction of the normal relative velocity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstantRestitution {
+    /// Restitution coefficient in [0, 1].
+    pub coefficient: f64,
+}
+
+impl CollisionModel for ConstantRestitution {
+    fn is_valid(&self) -> bool {
+        (0. ..=1.).contains(&self.coefficient)
+    }
+
+    fn get_impulse(&self, contact: &Contact) -> MyVec {
+        contact.get_normal_impulse(self.coefficient)
+    }
+
+    fn is_smooth(&self) -> bool {
+        true
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("constant_restitution", vec![self.coefficient]))
+    }
+}
+
+/// Smooth viscoelastic particles, whose restitution coefficient decreases with the impact velocity.
+///
+/// The expansion by Schwager and Pöschel (1998)
+///   `e = 1 - C1 d g^(1/5) + C2 d^2 g^(2/5)` is adopted,
+///   where `g` is the normal impact velocity and `d` is the damping parameter
+///   combining the dissipative and the elastic material constants.
+/// The coefficient is clipped to [0, 1], out of which the expansion is not valid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viscoelastic {
+    /// Non-negative damping parameter, recovering the elastic particles when zero.
+    pub damping: f64,
+}
+
+impl Viscoelastic {
+    const C1: f64 = 1.15344;
+    const C2: f64 = 0.79826;
+
+    /// Restitution coefficient for the given normal impact velocity.
+    pub fn get_restitution_coefficient(&self, vel: f64) -> f64 {
+        let x: f64 = self.damping * vel.abs().powf(0.2);
+        (1. - Self::C1 * x + Self::C2 * x * x).clamp(0., 1.)
+    }
+}
+
+impl CollisionModel for Viscoelastic {
+    fn is_valid(&self) -> bool {
+        self.damping.is_finite() && 0. <= self.damping
+    }
+
+    fn get_impulse(&self, contact: &Contact) -> MyVec {
+        let coefficient: f64 = self.get_restitution_coefficient(contact.get_normal_vel());
+        contact.get_normal_impulse(coefficient)
+    }
+
+    fn is_smooth(&self) -> bool {
+        true
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("viscoelastic", vec![self.damping]))
+    }
+}
+
+/// Rough particles with constant normal and tangential restitution coefficients.
+///
+/// The tangential relative velocity at the contact point is reversed and scaled by
+///   the tangential coefficient in [-1, 1],
+///   where -1 (1) corresponds to the perfectly smooth (rough) surfaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoughSphere {
+    /// Normal restitution coefficient in [0, 1].
+    pub normal: f64,
+    /// Tangential restitution coefficient in [-1, 1].
+    pub tangential: f64,
+}
+
+impl CollisionModel for RoughSphere {
+    fn is_valid(&self) -> bool {
+        (0. ..=1.).contains(&self.normal) && (-1. ..=1.).contains(&self.tangential)
+    }
+
+    fn get_impulse(&self, contact: &Contact) -> MyVec {
+        contact.get_normal_impulse(self.normal)
+            - (1. + self.tangential) * contact.tangential_mass * contact.get_tangential_vel()
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("rough_sphere", vec![self.normal, self.tangential]))
+    }
+}
+
+#[cfg(test)]
+mod test_get_impulse {
+    use super::{CollisionModel, ConstantRestitution, Contact, Elastic, RoughSphere, Viscoelastic};
+    use crate::myvec::MyVec;
+    use crate::simulator::NDIMS;
+
+    /// Head-on and grazing components with unit masses.
+    fn init_contact() -> Contact {
+        let mut normal = MyVec::new([0.; NDIMS]);
+        normal[0] = 1.;
+        let mut vel = MyVec::new([0.; NDIMS]);
+        vel[0] = -2.;
+        vel[1] = 1.;
+        Contact {
+            normal,
+            vel,
+            mass: 0.5,
+            tangential_mass: 0.25,
+        }
+    }
+
+    /// Relative velocity after the impulse is exchanged.
+    fn get_new_vel(contact: &Contact, impulse: MyVec) -> MyVec {
+        let normal: MyVec = (impulse * contact.normal) * contact.normal;
+        contact.vel + normal / contact.mass + (impulse - normal) / contact.tangential_mass
+    }
+
+    #[test]
+    fn case1() {
+        // elastic and constant restitution only alter the normal component
+        let contact = init_contact();
+        let vel: MyVec = get_new_vel(&contact, Elastic.get_impulse(&contact));
+        assert!((vel[0] - 2.).abs() < 1e-12);
+        assert!((vel[1] - 1.).abs() < 1e-12);
+        let model = ConstantRestitution { coefficient: 0.5 };
+        let vel: MyVec = get_new_vel(&contact, model.get_impulse(&contact));
+        assert!((vel[0] - 1.).abs() < 1e-12);
+        assert!((vel[1] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn case2() {
+        // viscoelastic particles dissipate more for faster impacts
+        let model = Viscoelastic { damping: 0.1 };
+        let slow: f64 = model.get_restitution_coefficient(0.1);
+        let fast: f64 = model.get_restitution_coefficient(10.);
+        assert!(0. < fast && fast < slow && slow < 1.);
+        let model = Viscoelastic { damping: 0. };
+        assert_eq!(model.get_restitution_coefficient(10.), 1.);
+    }
+
+    #[test]
+    fn case3() {
+        // perfectly rough particles reverse the tangential component
+        let contact = init_contact();
+        let model = RoughSphere {
+            normal: 1.,
+            tangential: 1.,
+        };
+        let vel: MyVec = get_new_vel(&contact, model.get_impulse(&contact));
+        assert!((vel[0] - 2.).abs() < 1e-12);
+        assert!((vel[1] + 1.).abs() < 1e-12);
+        // while perfectly smooth ones do not
+        let model = RoughSphere {
+            normal: 1.,
+            tangential: -1.,
+        };
+        let vel: MyVec = get_new_vel(&contact, model.get_impulse(&contact));
+        assert!((vel[1] - 1.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,846 @@
+use std::sync::Arc;
+
+use crate::simulator::boundary::{Boundary, ScalarCondition, WallMotion};
+use crate::simulator::buoyancy::Buoyancy;
+use crate::simulator::cell::get_ncells;
+use crate::simulator::collision_model::{CollisionModel, ConstantRestitution};
+use crate::simulator::extrema::Extrema;
+use crate::simulator::generator::{self, Placement, Stratification, VelocityDistribution};
+use crate::simulator::lees_edwards::{FLOW_DIM, GRADIENT_DIM};
+use crate::simulator::scalar_transport::{Conductive, ScalarTransport};
+use crate::simulator::scheduler::SchedulerKind;
+use crate::simulator::size_distribution::SizeDistribution;
+use crate::simulator::NDIMS;
+
+/// Reasons why a configuration is rejected.
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// Domain length is not positive or not finite.
+    InvalidLength(usize, f64),
+    /// Synchronisation rate is not positive or not finite.
+    InvalidSyncRate(f64),
+    /// Parameters of the particle-size distribution are not meaningful.
+    InvalidSizeDistribution(SizeDistribution),
+    /// Reference cell size is not positive or not finite.
+    InvalidCellSize(f64),
+    /// Cells in the given direction are not larger than twice the particle diameter.
+    TooSmallCell(usize, f64),
+    /// Parameters of the collision model are not meaningful.
+    InvalidCollisionModel(String),
+    /// Volume fraction or its cap is out of (0, 1).
+    InvalidVolumeFraction(f64),
+    /// Gravity is not finite.
+    InvalidGravity(usize, f64),
+    /// Boundary conditions in the given direction are not meaningful,
+    ///   the wall values are not given one for each scalar,
+    ///   only one of the two faces is periodic, or there is no such direction.
+    InvalidBoundary(usize),
+    /// Shear rate is not finite,
+    ///   or the flow and the gradient directions are not periodic.
+    InvalidLeesEdwards(f64),
+    /// Wall motion in the given direction is not meaningful, is given to a face which is not a wall,
+    ///   the tangential velocity is given to a specular wall which cannot feel it,
+    ///   the oscillation does not fit in the cell next to the wall, or there is no such direction.
+    InvalidWallMotion(usize),
+    /// Parameters of the scalar transport model with the given index are not meaningful.
+    InvalidScalarTransport(usize),
+    /// Parameters of the buoyancy model are not finite,
+    ///   or no scalar is carried by the particles.
+    InvalidBuoyancy(Buoyancy),
+    /// More particles are requested than the lattice sites, whose number is given.
+    TooManyParticles(usize),
+    /// Parameters of the initial velocity distribution are not meaningful.
+    InvalidVelocityDistribution(VelocityDistribution),
+    /// Parameters of the initial scalar distribution are not meaningful.
+    InvalidStratification(Stratification),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidLength(dim, val) => {
+                write!(
+                    f,
+                    "domain length in dim {} should be positive: {:+.2e}",
+                    dim, val
+                )
+            }
+            ConfigError::InvalidSyncRate(val) => {
+                write!(f, "sync rate should be positive: {:+.2e}", val)
+            }
+            ConfigError::InvalidSizeDistribution(distribution) => {
+                write!(f, "invalid size distribution: {:?}", distribution)
+            }
+            ConfigError::InvalidCellSize(val) => {
+                write!(f, "cell size should be positive: {:+.2e}", val)
+            }
+            ConfigError::TooSmallCell(dim, val) => write!(
+                f,
+                "cell size {:+.2e} in dim {} should be larger than twice the particle diameter",
+                val, dim
+            ),
+            ConfigError::InvalidCollisionModel(model) => {
+                write!(f, "invalid collision model: {}", model)
+            }
+            ConfigError::InvalidVolumeFraction(val) => {
+                write!(f, "volume fraction should be in (0, 1): {:+.2e}", val)
+            }
+            ConfigError::InvalidGravity(dim, val) => {
+                write!(f, "gravity in dim {} should be finite: {:+.2e}", dim, val)
+            }
+            ConfigError::InvalidBoundary(dim) => {
+                write!(f, "invalid boundary conditions in dim {}", dim)
+            }
+            ConfigError::InvalidLeesEdwards(val) => write!(
+                f,
+                "shear rate should be finite with periodic flow and gradient directions: {:+.2e}",
+                val
+            ),
+            ConfigError::InvalidWallMotion(dim) => {
+                write!(f, "invalid wall motion in dim {}", dim)
+            }
+            ConfigError::InvalidScalarTransport(index) => {
+                write!(f, "invalid transport model of scalar {}", index)
+            }
+            ConfigError::InvalidBuoyancy(buoyancy) => {
+                write!(f, "invalid buoyancy: {:?}", buoyancy)
+            }
+            ConfigError::TooManyParticles(capacity) => {
+                write!(f, "lattice only accommodates {} particles", capacity)
+            }
+            ConfigError::InvalidVelocityDistribution(distribution) => {
+                write!(f, "invalid velocity distribution: {:?}", distribution)
+            }
+            ConfigError::InvalidStratification(stratification) => {
+                write!(f, "invalid stratification: {:?}", stratification)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Validated set of parameters to construct a simulator.
+///
+/// Use [`SimulationConfig::builder`] to create one.
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    sync_rate: f64,
+    lengths: [f64; NDIMS],
+    nparticles: usize,
+    seed: u64,
+    size_distribution: SizeDistribution,
+    cell_size: f64,
+    collision_model: Arc<dyn CollisionModel>,
+    gravity: [f64; NDIMS],
+    max_volume_fraction: f64,
+    boundaries: [Extrema<Boundary>; NDIMS],
+    shear_rate: Option<f64>,
+    wall_motions: [Extrema<WallMotion>; NDIMS],
+    scalar_transports: Vec<Arc<dyn ScalarTransport>>,
+    buoyancy: Option<Buoyancy>,
+    placement: Placement,
+    velocity_distribution: VelocityDistribution,
+    stratification: Stratification,
+    scheduler: SchedulerKind,
+}
+
+impl SimulationConfig {
+    /// Starts building a configuration from the default values.
+    pub fn builder() -> SimulationConfigBuilder {
+        SimulationConfigBuilder::default()
+    }
+
+    pub fn sync_rate(&self) -> f64 {
+        self.sync_rate
+    }
+
+    /// Changes the synchronisation rate of a running simulator,
+    ///   see [`crate::simulator::Simulator::set_sync_rate`].
+    pub(crate) fn set_sync_rate(&mut self, sync_rate: f64) -> Result<(), ConfigError> {
+        if !(sync_rate.is_finite() && 0. < sync_rate) {
+            return Err(ConfigError::InvalidSyncRate(sync_rate));
+        }
+        self.sync_rate = sync_rate;
+        Ok(())
+    }
+
+    pub fn lengths(&self) -> [f64; NDIMS] {
+        self.lengths
+    }
+
+    pub fn nparticles(&self) -> usize {
+        self.nparticles
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn size_distribution(&self) -> SizeDistribution {
+        self.size_distribution
+    }
+
+    /// Largest particle radius, which the cell size should accommodate.
+    pub fn max_radius(&self) -> f64 {
+        self.size_distribution.max_radius()
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn collision_model(&self) -> Arc<dyn CollisionModel> {
+        self.collision_model.clone()
+    }
+
+    pub fn gravity(&self) -> [f64; NDIMS] {
+        self.gravity
+    }
+
+    /// Transport models of the scalars carried by each particle.
+    pub fn scalar_transports(&self) -> Vec<Arc<dyn ScalarTransport>> {
+        self.scalar_transports.clone()
+    }
+
+    pub fn buoyancy(&self) -> Option<Buoyancy> {
+        self.buoyancy
+    }
+
+    pub fn max_volume_fraction(&self) -> f64 {
+        self.max_volume_fraction
+    }
+
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    pub fn velocity_distribution(&self) -> VelocityDistribution {
+        self.velocity_distribution
+    }
+
+    pub fn stratification(&self) -> Stratification {
+        self.stratification
+    }
+
+    pub fn scheduler(&self) -> SchedulerKind {
+        self.scheduler
+    }
+
+    /// Conditions on the negative and positive faces in each direction.
+    pub fn boundaries(&self) -> &[Extrema<Boundary>; NDIMS] {
+        &self.boundaries
+    }
+
+    pub fn periodicities(&self) -> [bool; NDIMS] {
+        std::array::from_fn(|dim: usize| self.boundaries[dim].min.is_periodic())
+    }
+
+    /// Motions of the walls on the negative and positive faces in each direction.
+    pub fn wall_motions(&self) -> [Extrema<WallMotion>; NDIMS] {
+        self.wall_motions
+    }
+
+    /// Shear rate imposed by the Lees-Edwards boundaries, if any.
+    pub fn shear_rate(&self) -> Option<f64> {
+        self.shear_rate
+    }
+}
+
+/// Builds a [`SimulationConfig`] step by step.
+#[derive(Clone, Debug)]
+pub struct SimulationConfigBuilder {
+    config: SimulationConfig,
+    /// Decides the number of particles on build if given.
+    volume_fraction: Option<f64>,
+    /// Direction out of range given to [`SimulationConfigBuilder::boundary`],
+    ///   which is rejected on build.
+    invalid_boundary: Option<usize>,
+    /// Direction out of range given to [`SimulationConfigBuilder::wall_motion`],
+    ///   which is rejected on build.
+    invalid_wall_motion: Option<usize>,
+}
+
+impl Default for SimulationConfigBuilder {
+    /// Wall-bounded in the second direction and periodic in the others,
+    ///   with the bottom (top) walls being cold (hot).
+    /// Each particle carries one conductive scalar.
+    /// No gravity is imposed, while the buoyancy acts in the wall-bounded direction.
+    /// Particles are placed randomly with uniformly-distributed velocities,
+    ///   and the scalars are split by the diagonal.
+    fn default() -> Self {
+        let boundaries: [Extrema<Boundary>; NDIMS] = std::array::from_fn(|dim: usize| {
+            if 1 == dim {
+                Extrema::<Boundary> {
+                    min: Boundary::Specular(ScalarCondition::Dirichlet(vec![0.])),
+                    max: Boundary::Specular(ScalarCondition::Dirichlet(vec![1.])),
+                }
+            } else {
+                Extrema::both(Boundary::Periodic)
+            }
+        });
+        let buoyancy = Buoyancy {
+            acceleration: std::array::from_fn(|dim: usize| {
+                if boundaries[dim].min.is_periodic() {
+                    0f64
+                } else {
+                    -0.5f64
+                }
+            }),
+            reference: 0.5,
+        };
+        let config = SimulationConfig {
+            sync_rate: 1.,
+            lengths: [32.; NDIMS],
+            nparticles: 0,
+            seed: 0,
+            size_distribution: SizeDistribution::Monodisperse { radius: 0.5 },
+            cell_size: 3.,
+            collision_model: Arc::new(ConstantRestitution { coefficient: 0.99 }),
+            gravity: [0.; NDIMS],
+            max_volume_fraction: 0.4,
+            boundaries,
+            shear_rate: None,
+            wall_motions: [Extrema::<WallMotion> {
+                min: WallMotion::default(),
+                max: WallMotion::default(),
+            }; NDIMS],
+            scalar_transports: vec![Arc::new(Conductive { coefficient: 1. })],
+            buoyancy: Some(buoyancy),
+            placement: Placement::RandomSequential,
+            velocity_distribution: VelocityDistribution::Uniform { max: 1. },
+            stratification: Stratification::Diagonal,
+            scheduler: SchedulerKind::BinaryHeap,
+        };
+        SimulationConfigBuilder {
+            config,
+            volume_fraction: None,
+            invalid_boundary: None,
+            invalid_wall_motion: None,
+        }
+    }
+}
+
+impl SimulationConfigBuilder {
+    pub fn sync_rate(mut self, sync_rate: f64) -> Self {
+        self.config.sync_rate = sync_rate;
+        self
+    }
+
+    pub fn lengths(mut self, lengths: [f64; NDIMS]) -> Self {
+        self.config.lengths = lengths;
+        self
+    }
+
+    pub fn nparticles(mut self, nparticles: usize) -> Self {
+        self.config.nparticles = nparticles;
+        self.volume_fraction = None;
+        self
+    }
+
+    /// Gives the number of particles by the ratio of their expected total volume
+    ///   to the domain volume, which is evaluated on build.
+    ///
+    /// N.B.: The random sequential addition is still capped by the maximum volume fraction.
+    pub fn volume_fraction(mut self, volume_fraction: f64) -> Self {
+        self.volume_fraction = Some(volume_fraction);
+        self
+    }
+
+    /// Seed of the random-number generator, where any value gives its own sequence.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = seed;
+        self
+    }
+
+    /// Gives the same radius to all particles.
+    pub fn radius(mut self, radius: f64) -> Self {
+        self.config.size_distribution = SizeDistribution::Monodisperse { radius };
+        self
+    }
+
+    pub fn size_distribution(mut self, size_distribution: SizeDistribution) -> Self {
+        self.config.size_distribution = size_distribution;
+        self
+    }
+
+    /// Reference cell size, which is adjusted to divide the domain evenly.
+    pub fn cell_size(mut self, cell_size: f64) -> Self {
+        self.config.cell_size = cell_size;
+        self
+    }
+
+    /// Smooth particles with the constant restitution coefficient.
+    pub fn restitution_coefficient(self, restitution_coefficient: f64) -> Self {
+        self.collision_model(ConstantRestitution {
+            coefficient: restitution_coefficient,
+        })
+    }
+
+    pub fn collision_model<T: CollisionModel + 'static>(mut self, collision_model: T) -> Self {
+        self.config.collision_model = Arc::new(collision_model);
+        self
+    }
+
+    /// Constant acceleration felt by all particles, which follow parabolic trajectories.
+    pub fn gravity(mut self, gravity: [f64; NDIMS]) -> Self {
+        self.config.gravity = gravity;
+        self
+    }
+
+    /// Replaces the scalars carried by each particle.
+    pub fn scalar_transports(mut self, scalar_transports: Vec<Arc<dyn ScalarTransport>>) -> Self {
+        self.config.scalar_transports = scalar_transports;
+        self
+    }
+
+    /// Appends a scalar carried by each particle.
+    pub fn add_scalar<T: ScalarTransport + 'static>(mut self, scalar_transport: T) -> Self {
+        self.config
+            .scalar_transports
+            .push(Arc::new(scalar_transport));
+        self
+    }
+
+    /// Additional acceleration depending on the first scalar value, which is disabled by `None`.
+    pub fn buoyancy(mut self, buoyancy: Option<Buoyancy>) -> Self {
+        self.config.buoyancy = buoyancy;
+        self
+    }
+
+    /// Upper limit of the volume fraction, above which particles are not placed
+    ///   by the random sequential addition, and thus the simulator is not built.
+    pub fn max_volume_fraction(mut self, max_volume_fraction: f64) -> Self {
+        self.config.max_volume_fraction = max_volume_fraction;
+        self
+    }
+
+    /// Strategy to decide the initial positions of `nparticles` particles.
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.config.placement = placement;
+        self
+    }
+
+    pub fn velocity_distribution(mut self, velocity_distribution: VelocityDistribution) -> Self {
+        self.config.velocity_distribution = velocity_distribution;
+        self
+    }
+
+    /// Initial distribution shared by all scalars.
+    pub fn stratification(mut self, stratification: Stratification) -> Self {
+        self.config.stratification = stratification;
+        self
+    }
+
+    /// Data structure to find the coming event among the cells,
+    ///   which does not change the results.
+    pub fn scheduler(mut self, scheduler: SchedulerKind) -> Self {
+        self.config.scheduler = scheduler;
+        self
+    }
+
+    pub fn boundaries(mut self, boundaries: [Extrema<Boundary>; NDIMS]) -> Self {
+        self.config.boundaries = boundaries;
+        self
+    }
+
+    /// Conditions on the negative and positive faces in `dim`.
+    pub fn boundary(mut self, dim: usize, min: Boundary, max: Boundary) -> Self {
+        match self.config.boundaries.get_mut(dim) {
+            Some(boundaries) => *boundaries = Extrema::<Boundary> { min, max },
+            None => self.invalid_boundary = Some(dim),
+        }
+        self
+    }
+
+    /// Motions of the walls on the negative and positive faces in `dim`.
+    pub fn wall_motion(mut self, dim: usize, min: WallMotion, max: WallMotion) -> Self {
+        match self.config.wall_motions.get_mut(dim) {
+            Some(wall_motions) => *wall_motions = Extrema::<WallMotion> { min, max },
+            None => self.invalid_wall_motion = Some(dim),
+        }
+        self
+    }
+
+    /// Replaces the periodic faces in the second direction with the Lees-Edwards boundaries,
+    ///   which slide in the first direction to impose a simple shear flow.
+    ///
+    /// N.B.: Both directions should be periodic.
+    pub fn lees_edwards(mut self, shear_rate: f64) -> Self {
+        self.config.shear_rate = Some(shear_rate);
+        self
+    }
+
+    /// Validates the parameters and returns the configuration.
+    pub fn build(self) -> Result<SimulationConfig, ConfigError> {
+        let mut config: SimulationConfig = self.config;
+        if let Some(dim) = self.invalid_boundary {
+            return Err(ConfigError::InvalidBoundary(dim));
+        }
+        if let Some(dim) = self.invalid_wall_motion {
+            return Err(ConfigError::InvalidWallMotion(dim));
+        }
+        if !(config.sync_rate.is_finite() && 0. < config.sync_rate) {
+            return Err(ConfigError::InvalidSyncRate(config.sync_rate));
+        }
+        if !config.size_distribution.is_valid() {
+            return Err(ConfigError::InvalidSizeDistribution(
+                config.size_distribution,
+            ));
+        }
+        if !(config.cell_size.is_finite() && 0. < config.cell_size) {
+            return Err(ConfigError::InvalidCellSize(config.cell_size));
+        }
+        let max_radius: f64 = config.max_radius();
+        for dim in 0..NDIMS {
+            let length: f64 = config.lengths[dim];
+            if !(length.is_finite() && 0. < length) {
+                return Err(ConfigError::InvalidLength(dim, length));
+            }
+            // same rule as the one to create cells
+            let ncells: usize = get_ncells(length, config.cell_size);
+            let cell_size: f64 = length / ncells as f64;
+            if cell_size <= 4. * max_radius {
+                return Err(ConfigError::TooSmallCell(dim, cell_size));
+            }
+            if !config.gravity[dim].is_finite() {
+                return Err(ConfigError::InvalidGravity(dim, config.gravity[dim]));
+            }
+            let boundaries: &Extrema<Boundary> = &config.boundaries[dim];
+            let nscalars: usize = config.scalar_transports.len();
+            if !boundaries.min.is_valid(nscalars)
+                || !boundaries.max.is_valid(nscalars)
+                || boundaries.min.is_periodic() != boundaries.max.is_periodic()
+            {
+                return Err(ConfigError::InvalidBoundary(dim));
+            }
+            // the oscillating wall should not leave the cells at the edges,
+            //   and the particles touching it should be inside them
+            for (boundary, motion) in [
+                (&boundaries.min, config.wall_motions[dim].min),
+                (&boundaries.max, config.wall_motions[dim].max),
+            ] {
+                if !motion.is_valid(dim)
+                    || !motion.is_stationary() && boundary.scalar_condition().is_none()
+                    || motion.is_sliding() && matches!(boundary, Boundary::Specular(_))
+                    || cell_size - 2. * max_radius <= motion.amplitude
+                {
+                    return Err(ConfigError::InvalidWallMotion(dim));
+                }
+            }
+        }
+        if !config.collision_model.is_valid() {
+            return Err(ConfigError::InvalidCollisionModel(format!(
+                "{:?}",
+                config.collision_model
+            )));
+        }
+        if !(0. < config.max_volume_fraction && config.max_volume_fraction < 1.) {
+            return Err(ConfigError::InvalidVolumeFraction(
+                config.max_volume_fraction,
+            ));
+        }
+        if let Some(volume_fraction) = self.volume_fraction {
+            if !(0. < volume_fraction && volume_fraction < 1.) {
+                return Err(ConfigError::InvalidVolumeFraction(volume_fraction));
+            }
+            let volume: f64 = config.lengths.iter().product();
+            let nparticles: f64 = volume_fraction * volume / config.size_distribution.mean_volume();
+            config.nparticles = nparticles.round() as usize;
+        }
+        for (index, scalar_transport) in config.scalar_transports.iter().enumerate() {
+            if !scalar_transport.is_valid() {
+                return Err(ConfigError::InvalidScalarTransport(index));
+            }
+        }
+        if let Some(buoyancy) = config.buoyancy {
+            if !buoyancy.is_valid() || config.scalar_transports.is_empty() {
+                return Err(ConfigError::InvalidBuoyancy(buoyancy));
+            }
+        }
+        if let Placement::SquareLattice | Placement::HexagonalLattice = config.placement {
+            let capacity: usize = generator::get_lattice_capacity(
+                &config.lengths,
+                &config.periodicities(),
+                max_radius,
+                &config.placement,
+            );
+            if capacity < config.nparticles {
+                return Err(ConfigError::TooManyParticles(capacity));
+            }
+        }
+        if !config.velocity_distribution.is_valid() {
+            return Err(ConfigError::InvalidVelocityDistribution(
+                config.velocity_distribution,
+            ));
+        }
+        if !config.stratification.is_valid() {
+            return Err(ConfigError::InvalidStratification(config.stratification));
+        }
+        if let Some(shear_rate) = config.shear_rate {
+            if !shear_rate.is_finite()
+                || !config.boundaries[FLOW_DIM].min.is_periodic()
+                || !config.boundaries[GRADIENT_DIM].min.is_periodic()
+            {
+                return Err(ConfigError::InvalidLeesEdwards(shear_rate));
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test_build {
+    use super::{
+        Boundary, ConfigError, Placement, ScalarCondition, SimulationConfig, SizeDistribution,
+        Stratification, WallMotion,
+    };
+    use crate::simulator::scalar_transport::{Conductive, Passive};
+    use crate::simulator::NDIMS;
+
+    #[test]
+    fn case1() {
+        // default values are valid
+        assert!(SimulationConfig::builder().build().is_ok());
+    }
+
+    #[test]
+    fn case2() {
+        let result = SimulationConfig::builder()
+            .restitution_coefficient(1.5)
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            ConfigError::InvalidCollisionModel(String::from(
+                "ConstantRestitution { coefficient: 1.5 }"
+            ))
+        );
+    }
+
+    #[test]
+    fn case3() {
+        // cells cannot accommodate the particles
+        let result = SimulationConfig::builder()
+            .lengths([4.; NDIMS])
+            .radius(0.5)
+            .build();
+        assert!(matches!(result, Err(ConfigError::TooSmallCell(0, _))));
+    }
+
+    #[test]
+    fn case4() {
+        let result = SimulationConfig::builder().lengths([-1.; NDIMS]).build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidLength(0, -1.));
+    }
+
+    #[test]
+    fn case5() {
+        // the largest radius decides the cell size
+        let result = SimulationConfig::builder()
+            .lengths([8.; NDIMS])
+            .size_distribution(SizeDistribution::Bidisperse {
+                small: 0.5,
+                large: 1.,
+                fraction: 0.1,
+            })
+            .build();
+        assert!(matches!(result, Err(ConfigError::TooSmallCell(0, _))));
+        // the cell size is not given properly
+        for cell_size in [0., -1., f64::NAN, f64::INFINITY] {
+            let result = SimulationConfig::builder().cell_size(cell_size).build();
+            assert!(matches!(result, Err(ConfigError::InvalidCellSize(_))));
+        }
+    }
+
+    #[test]
+    fn case6() {
+        // periodic only on one side
+        let result = SimulationConfig::builder()
+            .boundary(0, Boundary::Periodic, Boundary::Outflow)
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidBoundary(0));
+        // no such direction
+        let result = SimulationConfig::builder()
+            .boundary(NDIMS, Boundary::Periodic, Boundary::Periodic)
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidBoundary(NDIMS));
+        // walls at zero temperature, from which the particles never leave
+        let wall = Boundary::Diffuse {
+            temperature: 0.,
+            scalar: ScalarCondition::Neumann,
+        };
+        let result = SimulationConfig::builder()
+            .boundary(0, wall.clone(), wall)
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidBoundary(0));
+    }
+
+    #[test]
+    fn case7() {
+        // sliding faces require the periodicity, which is not the default
+        let result = SimulationConfig::builder().lees_edwards(0.1).build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidLeesEdwards(0.1));
+        let result = SimulationConfig::builder()
+            .boundary(1, Boundary::Periodic, Boundary::Periodic)
+            .lees_edwards(0.1)
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn case8() {
+        // only walls can move, within the cells next to them
+        let motion = WallMotion {
+            velocity: [0.; NDIMS],
+            amplitude: 0.5,
+            angular_frequency: 1.,
+        };
+        let result = SimulationConfig::builder()
+            .wall_motion(1, motion, WallMotion::default())
+            .build();
+        assert!(result.is_ok());
+        let result = SimulationConfig::builder()
+            .wall_motion(0, motion, WallMotion::default())
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidWallMotion(0));
+        let result = SimulationConfig::builder()
+            .wall_motion(
+                1,
+                WallMotion {
+                    amplitude: 4.,
+                    ..motion
+                },
+                WallMotion::default(),
+            )
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidWallMotion(1));
+        // no such direction
+        let result = SimulationConfig::builder()
+            .wall_motion(NDIMS, motion, WallMotion::default())
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidWallMotion(NDIMS));
+        // wall-normal component of the tangential velocity
+        let result = SimulationConfig::builder()
+            .wall_motion(
+                1,
+                WallMotion {
+                    velocity: [1.; NDIMS],
+                    ..motion
+                },
+                WallMotion::default(),
+            )
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidWallMotion(1));
+    }
+
+    #[test]
+    fn case9() {
+        // multiple scalars, each of which is validated
+        let wall = |val: f64| Boundary::Specular(ScalarCondition::Dirichlet(vec![val; 2]));
+        let result = SimulationConfig::builder()
+            .add_scalar(Passive)
+            .boundary(1, wall(0.), wall(1.))
+            .build();
+        assert_eq!(result.unwrap().scalar_transports().len(), 2);
+        let result = SimulationConfig::builder()
+            .add_scalar(Conductive { coefficient: 2. })
+            .boundary(1, wall(0.), wall(1.))
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidScalarTransport(1));
+        // the walls give one value to each scalar
+        let result = SimulationConfig::builder().add_scalar(Passive).build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidBoundary(1));
+        // the buoyancy needs a scalar
+        let insulated = Boundary::Specular(ScalarCondition::Neumann);
+        let result = SimulationConfig::builder()
+            .scalar_transports(Vec::new())
+            .boundary(1, insulated.clone(), insulated)
+            .build();
+        assert!(matches!(result, Err(ConfigError::InvalidBuoyancy(_))));
+    }
+
+    #[test]
+    fn case10() {
+        // the lattice with the walls in the second direction,
+        //   where 31 sites fit in each direction
+        //   since the particles in contact are kept apart by a small margin
+        let capacity: usize = 31usize.pow(NDIMS as u32);
+        let result = SimulationConfig::builder()
+            .nparticles(capacity)
+            .placement(Placement::SquareLattice)
+            .build();
+        assert!(result.is_ok());
+        let result = SimulationConfig::builder()
+            .nparticles(capacity + 1)
+            .placement(Placement::SquareLattice)
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::TooManyParticles(capacity));
+        let stratification = Stratification::Linear {
+            dim: NDIMS,
+            min: 0.,
+            max: 1.,
+        };
+        let result = SimulationConfig::builder()
+            .stratification(stratification)
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            ConfigError::InvalidStratification(stratification)
+        );
+    }
+
+    #[test]
+    fn case11() {
+        // the number of particles is given by the volume fraction,
+        //   where the expected volume of the bidisperse particles is (0.5^D + 0.75^D) / 2
+        let lengths = [32.; NDIMS];
+        let volume: f64 = 32f64.powi(NDIMS as i32);
+        let size_distribution = SizeDistribution::Bidisperse {
+            small: 0.5,
+            large: 0.75,
+            fraction: 0.5,
+        };
+        let config = SimulationConfig::builder()
+            .lengths(lengths)
+            .volume_fraction(0.2)
+            .size_distribution(size_distribution)
+            .build()
+            .unwrap();
+        let mean_volume: f64 = 0.5
+            * (0.5f64.powi(NDIMS as i32) + 0.75f64.powi(NDIMS as i32))
+            * crate::simulator::particle::get_volume(1.);
+        assert_eq!(
+            config.nparticles(),
+            (0.2 * volume / mean_volume).round() as usize
+        );
+        // the latter is used
+        let config = SimulationConfig::builder()
+            .volume_fraction(0.2)
+            .nparticles(10)
+            .build()
+            .unwrap();
+        assert_eq!(config.nparticles(), 10);
+        let result = SimulationConfig::builder().volume_fraction(1.).build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidVolumeFraction(1.));
+    }
+
+    #[test]
+    fn case12() {
+        // sliding walls should be diffuse, since the specular ones are frictionless
+        let mut motion = WallMotion::default();
+        motion.velocity[0] = 1.;
+        let result = SimulationConfig::builder()
+            .wall_motion(1, motion, WallMotion::default())
+            .build();
+        assert_eq!(result.unwrap_err(), ConfigError::InvalidWallMotion(1));
+        let diffuse = Boundary::Diffuse {
+            temperature: 1.,
+            scalar: ScalarCondition::Neumann,
+        };
+        let result = SimulationConfig::builder()
+            .boundary(1, diffuse.clone(), diffuse)
+            .wall_motion(1, motion, WallMotion::default())
+            .build();
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::myvec::MyVec;
+use crate::simulator::particle::Particles;
+use crate::simulator::structure::AnalysisError;
+use crate::simulator::{Domain, NDIMS};
+
+/// States of the particles at a time origin, sorted by the particle indices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Origin {
+    /// Number of samples taken before this origin.
+    pub sample: usize,
+    pub time: f64,
+    pub indices: Vec<usize>,
+    /// Unwrapped positions.
+    pub positions: Vec<MyVec>,
+    pub velocities: Vec<MyVec>,
+}
+
+/// Mean-squared displacement and velocity autocorrelation function
+///   averaged over the particles and multiple time origins.
+///
+/// The particles are sampled at each synchronisation,
+///   and a new origin is placed every `interval` samples,
+///   which is kept until it is `nlags - 1` samples old.
+/// The particles leaving the domain are excluded after they have left.
+///
+/// N.B.: The positions are unwrapped, while the velocities are the ones in the primary domain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diffusion {
+    /// Number of the lags including zero.
+    pub(crate) nlags: usize,
+    /// Number of samples between two successive origins.
+    pub(crate) interval: usize,
+    /// Number of samples so far.
+    pub(crate) nsamples: usize,
+    /// Origins in the order of time.
+    pub(crate) origins: Vec<Origin>,
+    /// Sums of the lag times, one for each lag.
+    pub(crate) times: Vec<f64>,
+    pub(crate) displacements: Vec<f64>,
+    pub(crate) correlations: Vec<f64>,
+    /// Number of the origins contributing to each lag.
+    pub(crate) norigins: Vec<usize>,
+    /// Number of the particles contributing to each lag.
+    pub(crate) counts: Vec<usize>,
+}
+
+fn average(sums: &[f64], counts: &[usize]) -> Vec<f64> {
+    sums.iter()
+        .zip(counts.iter())
+        .map(|(&sum, &count)| if 0 == count { 0. } else { sum / count as f64 })
+        .collect()
+}
+
+impl Diffusion {
+    /// Prepares `nlags` lags (including zero), where a new origin is placed every `interval` samples.
+    pub fn new(nlags: usize, interval: usize) -> Result<Diffusion, AnalysisError> {
+        if 0 == nlags || 0 == interval {
+            return Err(AnalysisError::InvalidNumberOfBins);
+        }
+        Ok(Diffusion {
+            nlags,
+            interval,
+            nsamples: 0,
+            origins: Vec::new(),
+            times: vec![0.; nlags],
+            displacements: vec![0.; nlags],
+            correlations: vec![0.; nlags],
+            norigins: vec![0; nlags],
+            counts: vec![0; nlags],
+        })
+    }
+
+    /// Discards the samples taken so far, e.g. after the initial transient.
+    pub fn reset(&mut self) {
+        *self = Diffusion::new(self.nlags, self.interval).unwrap();
+    }
+
+    /// Returns the number of samples since the latest reset.
+    pub fn get_nsamples(&self) -> usize {
+        self.nsamples
+    }
+
+    /// Returns the mean lag times, which are zero for the lags not sampled yet.
+    pub fn get_lag_times(&self) -> Vec<f64> {
+        average(&self.times, &self.norigins)
+    }
+
+    /// Returns the mean-squared displacements.
+    pub fn get_msd(&self) -> Vec<f64> {
+        average(&self.displacements, &self.counts)
+    }
+
+    /// Returns the velocity autocorrelation function, which is not normalised.
+    pub fn get_vacf(&self) -> Vec<f64> {
+        average(&self.correlations, &self.counts)
+    }
+
+    /// Returns the diffusion coefficient given by the Einstein relation at the longest lag,
+    ///   i.e. MSD / (2 * NDIMS * t).
+    pub fn get_diffusion_coefficient(&self) -> Option<f64> {
+        let lag: usize = self.nlags - 1;
+        if 0 == lag || 0 == self.counts[lag] {
+            return None;
+        }
+        let time: f64 = self.times[lag] / self.norigins[lag] as f64;
+        let msd: f64 = self.displacements[lag] / self.counts[lag] as f64;
+        Some(msd / (2. * NDIMS as f64 * time))
+    }
+
+    /// Correlates the synchronised particles with the origins, and places a new one if due.
+    pub(crate) fn sample(&mut self, domain: &Domain, time: f64, particles: &Particles) {
+        let mut current = Origin {
+            sample: self.nsamples,
+            time,
+            ..Default::default()
+        };
+        let mut states: Vec<(usize, MyVec, MyVec)> = (0..particles.len())
+            .map(|p: usize| {
+                (
+                    particles.indices[p],
+                    particles.get_unwrapped_pos(domain, p),
+                    particles.velocities[p],
+                )
+            })
+            .collect();
+        states.sort_by_key(|state| state.0);
+        for (index, pos, vel) in states.into_iter() {
+            current.indices.push(index);
+            current.positions.push(pos);
+            current.velocities.push(vel);
+        }
+        if self.nsamples.is_multiple_of(self.interval) {
+            self.origins.push(current.clone());
+        }
+        self.nsamples += 1;
+        // forget the origins older than the longest lag
+        let nlags: usize = self.nlags;
+        let nsamples: usize = self.nsamples;
+        self.origins
+            .retain(|origin: &Origin| nsamples - origin.sample <= nlags);
+        for origin in self.origins.iter() {
+            let lag: usize = current.sample - origin.sample;
+            self.times[lag] += current.time - origin.time;
+            self.norigins[lag] += 1;
+            for (n, index) in origin.indices.iter().enumerate() {
+                let m: usize = match current.indices.binary_search(index) {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+                let dpos: MyVec = current.positions[m] - origin.positions[n];
+                self.displacements[lag] += dpos * dpos;
+                self.correlations[lag] += current.velocities[m] * origin.velocities[n];
+                self.counts[lag] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_diffusion {
+    use super::Diffusion;
+    use crate::simulator::boundary::get_periodic_boundaries;
+    use crate::simulator::{AnalysisError, InitialCondition, SimulationConfig, Simulator, NDIMS};
+
+    /// A particle travelling alone, which crosses the periodic faces many times.
+    fn init_simulator(vel: [f64; NDIMS], shear_rate: Option<f64>) -> Simulator {
+        let mut builder = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .boundaries(get_periodic_boundaries())
+            .buoyancy(None);
+        if let Some(shear_rate) = shear_rate {
+            builder = builder.lees_edwards(shear_rate);
+        }
+        let initial_condition = InitialCondition {
+            positions: vec![[8.; NDIMS]],
+            velocities: vec![vel],
+            radii: vec![0.5],
+            vals: vec![vec![0.]],
+        };
+        Simulator::from_initial_condition(builder.build().unwrap(), &initial_condition).unwrap()
+    }
+
+    #[test]
+    fn case1() {
+        // ballistic motion across the periodic faces
+        let mut vel = [0.; NDIMS];
+        vel[0] = 3.;
+        vel[1] = -4.;
+        let mut simulator = init_simulator(vel, None);
+        simulator.set_diffusion(Some(Diffusion::new(4, 2).unwrap()));
+        simulator.advance_to(20.5);
+        let diffusion = simulator.get_diffusion().unwrap();
+        assert_eq!(diffusion.get_nsamples(), 21);
+        assert_eq!(diffusion.get_lag_times(), vec![0., 1., 2., 3.]);
+        let msd: Vec<f64> = diffusion.get_msd();
+        let vacf: Vec<f64> = diffusion.get_vacf();
+        for lag in 0..4 {
+            assert!((msd[lag] - 25. * (lag * lag) as f64).abs() < 1e-8);
+            assert!((vacf[lag] - 25.).abs() < 1e-8);
+        }
+        assert!(
+            (diffusion.get_diffusion_coefficient().unwrap() - 37.5 / NDIMS as f64).abs() < 1e-8
+        );
+        // the samples are discarded
+        simulator.reset_diffusion();
+        assert_eq!(simulator.get_diffusion().unwrap().get_msd(), vec![0.; 4]);
+        let pos = simulator
+            .get_particles()
+            .get_unwrapped_pos(&simulator.domain, 0);
+        assert!((pos[0] - 8. - 3. * 20.5).abs() < 1e-8);
+        assert!((pos[1] - 8. + 4. * 20.5).abs() < 1e-8);
+    }
+
+    #[test]
+    fn case2() {
+        // a particle at rest in the flow direction keeps its unwrapped position
+        //   while passing through the sliding images
+        let mut vel = [0.; NDIMS];
+        vel[1] = 5.;
+        let mut simulator = init_simulator(vel, Some(0.3));
+        simulator.advance_to(20.5);
+        let particles = simulator.get_particles();
+        assert_eq!(particles.images[0].counts[1], 6);
+        let pos = particles.get_unwrapped_pos(&simulator.domain, 0);
+        assert!((pos[0] - 8.).abs() < 1e-8);
+        assert!((pos[1] - 8. - 5. * 20.5).abs() < 1e-8);
+    }
+
+    #[test]
+    fn case3() {
+        // invalid lags are rejected
+        assert_eq!(
+            Diffusion::new(0, 1),
+            Err(AnalysisError::InvalidNumberOfBins)
+        );
+        assert_eq!(
+            Diffusion::new(4, 0),
+            Err(AnalysisError::InvalidNumberOfBins)
+        );
+    }
+}
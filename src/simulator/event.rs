@@ -1,29 +1,39 @@
 mod inter_particle_collision;
+mod lees_edwards_shift;
 mod move_out_of_cell;
 mod move_to_neighbour;
+mod outflow;
 mod synchronisation;
 mod util;
 mod wall_reflection;
 
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
+use crate::random::Random;
 use crate::simulator::cell::Cell;
-use crate::simulator::particle::Particle;
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::observables::Accumulators;
+use crate::simulator::particle::Particles;
 use crate::simulator::scheduler::Scheduler;
 use crate::simulator::{Domain, NDIMS};
 
 use inter_particle_collision::InterParticleCollision;
+use lees_edwards_shift::LeesEdwardsShift;
 use move_out_of_cell::MoveOutOfCell;
 use move_to_neighbour::MoveToNeighbour;
+use outflow::Outflow;
+pub use synchronisation::synchronise;
 use synchronisation::Synchronisation;
 use wall_reflection::WallReflection;
 
+/// Number of the events below which a queue is never swept for the invalidated events.
+const MIN_NKEPT: usize = 32;
+
 pub enum EventType {
     InterParticleCollision(InterParticleCollision),
     MoveToNeighbour(MoveToNeighbour),
     MoveOutOfCell(MoveOutOfCell),
     WallReflection(WallReflection),
+    Outflow(Outflow),
+    LeesEdwardsShift(LeesEdwardsShift),
     Synchronisation(Synchronisation),
 }
 
@@ -32,227 +42,535 @@ pub struct Event {
     pub eventtype: EventType,
 }
 
-fn get_head_event_time(events: &Rc<RefCell<Vec<Event>>>) -> f64 {
-    let events: Ref<Vec<Event>> = events.borrow();
-    if events.is_empty() {
-        f64::MAX
-    } else {
-        events[0].time
+/// Events are ordered by their times only, where the earlier one is greater
+///   so that it comes first in the maximum heap.
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.time.total_cmp(&self.time)
+    }
+}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        std::cmp::Ordering::Equal == self.cmp(other)
+    }
+}
+
+impl Eq for Event {}
+
+/// Numbers of the processed events of each kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventCounts {
+    /// Collisions between two particles.
+    pub inter_particle_collision: usize,
+    /// Particles entering the neighbouring cells.
+    pub move_to_neighbour: usize,
+    /// Particles leaving the cells.
+    pub move_out_of_cell: usize,
+    /// Particles hitting the walls.
+    pub wall_reflection: usize,
+    pub outflow: usize,
+    pub lees_edwards_shift: usize,
+    pub synchronisation: usize,
+}
+
+impl EventCounts {
+    /// Number of the processed events of all kinds.
+    pub fn total(&self) -> usize {
+        self.inter_particle_collision
+            + self.move_to_neighbour
+            + self.move_out_of_cell
+            + self.wall_reflection
+            + self.outflow
+            + self.lees_edwards_shift
+            + self.synchronisation
+    }
+
+    fn count(&mut self, eventtype: &EventType) {
+        let counter: &mut usize = match eventtype {
+            EventType::InterParticleCollision(_) => &mut self.inter_particle_collision,
+            EventType::MoveToNeighbour(_) => &mut self.move_to_neighbour,
+            EventType::MoveOutOfCell(_) => &mut self.move_out_of_cell,
+            EventType::WallReflection(_) => &mut self.wall_reflection,
+            EventType::Outflow(_) => &mut self.outflow,
+            EventType::LeesEdwardsShift(_) => &mut self.lees_edwards_shift,
+            EventType::Synchronisation(_) => &mut self.synchronisation,
+        };
+        *counter += 1;
+    }
+}
+
+impl std::ops::AddAssign for EventCounts {
+    fn add_assign(&mut self, other: EventCounts) {
+        self.inter_particle_collision += other.inter_particle_collision;
+        self.move_to_neighbour += other.move_to_neighbour;
+        self.move_out_of_cell += other.move_out_of_cell;
+        self.wall_reflection += other.wall_reflection;
+        self.outflow += other.outflow;
+        self.lees_edwards_shift += other.lees_edwards_shift;
+        self.synchronisation += other.synchronisation;
+    }
+}
+
+impl Event {
+    /// Writes the time, the kind and the payload of this event.
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.time)?;
+        match &self.eventtype {
+            EventType::InterParticleCollision(event) => {
+                encoder.put(&0u8)?;
+                event.encode(encoder)
+            }
+            EventType::MoveToNeighbour(event) => {
+                encoder.put(&1u8)?;
+                event.encode(encoder)
+            }
+            EventType::MoveOutOfCell(event) => {
+                encoder.put(&2u8)?;
+                event.encode(encoder)
+            }
+            EventType::WallReflection(event) => {
+                encoder.put(&3u8)?;
+                event.encode(encoder)
+            }
+            EventType::Outflow(event) => {
+                encoder.put(&4u8)?;
+                event.encode(encoder)
+            }
+            EventType::LeesEdwardsShift(event) => {
+                encoder.put(&5u8)?;
+                event.encode(encoder)
+            }
+            EventType::Synchronisation(event) => {
+                encoder.put(&6u8)?;
+                event.encode(encoder)
+            }
+        }
+    }
+
+    /// Reads an event written by [`Event::encode`].
+    pub fn decode(decoder: &mut Decoder) -> Result<Event, CheckpointError> {
+        let time: f64 = decoder.get()?;
+        let eventtype: EventType = match decoder.get::<u8>()? {
+            0 => EventType::InterParticleCollision(InterParticleCollision::decode(decoder)?),
+            1 => EventType::MoveToNeighbour(MoveToNeighbour::decode(decoder)?),
+            2 => EventType::MoveOutOfCell(MoveOutOfCell::decode(decoder)?),
+            3 => EventType::WallReflection(WallReflection::decode(decoder)?),
+            4 => EventType::Outflow(Outflow::decode(decoder)?),
+            5 => EventType::LeesEdwardsShift(LeesEdwardsShift::decode(decoder)?),
+            6 => EventType::Synchronisation(Synchronisation::decode(decoder)?),
+            _ => return Err(CheckpointError::Corrupted("invalid event type")),
+        };
+        Ok(Event { time, eventtype })
+    }
+
+    /// Checks if none of the involved particles has changed since this event was predicted.
+    fn is_valid(&self, particles: &Particles) -> bool {
+        match &self.eventtype {
+            EventType::InterParticleCollision(event) => {
+                particles.is_current(event.p_old, event.p_stamp)
+                    && particles.is_current(event.q_old, event.q_stamp)
+            }
+            EventType::MoveToNeighbour(event) => particles.is_current(event.p_old, event.p_stamp),
+            EventType::MoveOutOfCell(event) => particles.is_current(event.p_old, event.p_stamp),
+            EventType::WallReflection(event) => particles.is_current(event.p_old, event.p_stamp),
+            EventType::Outflow(event) => particles.is_current(event.p_old, event.p_stamp),
+            EventType::LeesEdwardsShift(event) => particles.is_current(event.p_old, event.p_stamp),
+            EventType::Synchronisation(_) => true,
+        }
     }
 }
 
-/// Inserts a new event to the given cell in a sorted order.
+/// Pushes a new event to the queue of the given cell.
 ///
 /// # Arguments
 /// * `new_event` - a new event to be appended.
 /// * `cell`      - the cell which is of interest.
-/// * `scheduler` - a minimum heap to find the latest event.
-fn insert_event(new_event: Event, cell: &Rc<RefCell<Cell>>, scheduler: &mut Scheduler) {
-    let cell: Ref<Cell> = cell.borrow();
+/// * `scheduler` - a global queue to find the latest event.
+fn insert_event(new_event: Event, cell: &mut Cell, scheduler: &mut dyn Scheduler) {
     // store head event before inisertion
-    let time_bef: f64 = get_head_event_time(&cell.events);
+    let time_bef: f64 = cell.get_head_event_time();
     // insert
-    {
-        let mut events: RefMut<Vec<Event>> = cell.events.borrow_mut();
-        match events.binary_search_by(|event| event.time.partial_cmp(&new_event.time).unwrap()) {
-            Ok(position) | Err(position) => events.insert(position, new_event),
-        };
-    }
+    cell.events.push(new_event);
     // store head event after inisertion
-    let time_aft: f64 = get_head_event_time(&cell.events);
+    let time_aft: f64 = cell.get_head_event_time();
     // update heap
     scheduler.update(cell.index, time_bef, time_aft);
 }
 
+/// Pushes new events to the queue of the given cell,
+///   discarding the invalidated events on the top on the way.
+///
+/// # Arguments
+/// * `particles`  - all particles, to judge the validities of the events.
+/// * `new_events` - new events to be appended.
+/// * `cell`       - the cell which is of interest.
+/// * `scheduler`  - a global queue to find the latest event.
+fn insert_events(
+    particles: &Particles,
+    new_events: Vec<Event>,
+    cell: &mut Cell,
+    scheduler: &mut dyn Scheduler,
+) {
+    // store head event before inisertion
+    let time_bef: f64 = cell.get_head_event_time();
+    // insert
+    pop_stale_heads(particles, cell);
+    cell.events.extend(new_events);
+    discard_stale_events(particles, cell);
+    // store head event after inisertion
+    let time_aft: f64 = cell.get_head_event_time();
+    // update heap once for all
+    scheduler.update(cell.index, time_bef, time_aft);
+}
+
+/// Appends the events of "p" with the faces of the given cell.
+fn schedule_boundary_events(
+    domain: &Domain,
+    time: f64,
+    cell: &Cell,
+    particles: &Particles,
+    p: usize,
+    events: &mut Vec<Event>,
+) {
+    for dim in 0..NDIMS {
+        if let Some(event) = MoveToNeighbour::schedule(domain, time, cell, dim, particles, p) {
+            events.push(event);
+        }
+    }
+    for dim in 0..NDIMS {
+        if let Some(event) = MoveOutOfCell::schedule(domain, time, cell, dim, particles, p) {
+            events.push(event);
+        }
+    }
+    for dim in 0..NDIMS {
+        if let Some(event) = WallReflection::schedule(domain, time, cell, dim, particles, p) {
+            events.push(event);
+        }
+    }
+    for dim in 0..NDIMS {
+        if let Some(event) = Outflow::schedule(domain, time, cell, dim, particles, p) {
+            events.push(event);
+        }
+    }
+    if let Some(event) = LeesEdwardsShift::schedule(domain, time, cell, particles, p) {
+        events.push(event);
+    }
+}
+
 /// For each cell, checks events and inserts them if applicable.
-pub fn init_events(domain: &Domain, cells: &[Rc<RefCell<Cell>>], scheduler: &mut Scheduler) {
+pub fn init_events(
+    domain: &Domain,
+    particles: &Particles,
+    cells: &mut [Cell],
+    scheduler: &mut dyn Scheduler,
+) {
     let time: f64 = 0.;
-    for cell in cells.iter() {
-        let cell_borrowed: Ref<Cell> = cell.borrow();
+    for cell in cells.iter_mut() {
+        let mut events = Vec::<Event>::new();
         // main cell handles the synchronisation
-        if 0 == cell_borrowed.index {
-            insert_event(Synchronisation::schedule(time, cell), cell, scheduler);
+        if 0 == cell.index {
+            events.push(Synchronisation::schedule(time, cell.index));
         }
         // append inter-particle events
-        let particles: Ref<Vec<Rc<RefCell<Particle>>>> = cell_borrowed.particles.borrow();
-        for (n, p) in particles.iter().enumerate() {
-            for q in particles[n + 1..].iter() {
-                if let Some(event) = InterParticleCollision::schedule(domain, time, cell, p, q) {
-                    insert_event(event, cell, scheduler);
+        let ps: &[usize] = &cell.particles;
+        for (n, &p) in ps.iter().enumerate() {
+            for &q in ps[n + 1..].iter() {
+                if let Some(event) =
+                    InterParticleCollision::schedule(domain, time, cell, particles, p, q)
+                {
+                    events.push(event);
                 }
             }
         }
         // append boundary events
-        for p in particles.iter() {
-            for dim in 0..NDIMS {
-                if let Some(event) = MoveToNeighbour::schedule(domain, time, cell, dim, p) {
-                    insert_event(event, cell, scheduler);
-                }
-            }
-            for dim in 0..NDIMS {
-                if let Some(event) = MoveOutOfCell::schedule(domain, time, cell, dim, p) {
-                    insert_event(event, cell, scheduler);
-                }
-            }
-            for dim in 0..NDIMS {
-                if let Some(event) = WallReflection::schedule(domain, time, cell, dim, p) {
-                    insert_event(event, cell, scheduler);
-                }
-            }
+        for &p in ps.iter() {
+            schedule_boundary_events(domain, time, cell, particles, p, &mut events);
         }
+        insert_events(particles, events, cell, scheduler);
     }
 }
 
 /// Checks and inserts new events related to "p" into the series of events
 fn schedule_events(
     domain: &Domain,
-    p: &Rc<RefCell<Particle>>,
-    cell: &Rc<RefCell<Cell>>,
-    scheduler: &mut Scheduler,
+    particles: &mut Particles,
+    cell: &mut Cell,
+    p: usize,
+    scheduler: &mut dyn Scheduler,
 ) {
     if cfg!(debug_assertions) {
-        crate::simulator::debug::check_recognition(p, cell);
+        crate::simulator::debug::check_recognition(particles, p, cell);
     }
-    let cell_borrowed: Ref<Cell> = cell.borrow();
     // get local time of the given particle
-    let time: f64 = p.borrow().time;
-    // all particles in this cell
-    let qs: Ref<Vec<Rc<RefCell<Particle>>>> = cell_borrowed.particles.borrow();
+    let time: f64 = particles.times[p];
     // update all particles in the cell to the local time of the given particle,
     //   so that the interactions can be correctly handled
-    for q in qs.iter() {
-        if Rc::ptr_eq(p, q) {
+    for &q in cell.particles.iter() {
+        if p == q {
             continue;
         }
-        let mut q: RefMut<Particle> = q.borrow_mut();
-        q.pos = Particle::get_new_pos(domain, q.pos, q.vel, time - q.time);
-        q.time = time;
+        particles.advance(domain, q, time);
     }
-    for q in qs.iter() {
-        if Rc::ptr_eq(p, q) {
+    let mut events = Vec::<Event>::new();
+    for &q in cell.particles.iter() {
+        if p == q {
             continue;
         }
-        if let Some(event) = InterParticleCollision::schedule(domain, time, cell, p, q) {
-            insert_event(event, cell, scheduler);
+        if let Some(event) = InterParticleCollision::schedule(domain, time, cell, particles, p, q) {
+            events.push(event);
         }
     }
-    for dim in 0..NDIMS {
-        if let Some(event) = MoveToNeighbour::schedule(domain, time, cell, dim, p) {
-            insert_event(event, cell, scheduler);
-        }
+    schedule_boundary_events(domain, time, cell, particles, p, &mut events);
+    insert_events(particles, events, cell, scheduler);
+}
+
+/// Invalidates all events of "p", and reschedules them in the cells to which it belongs.
+///
+/// N.B.: The invalidated events are left in the queues, and discarded when they come.
+fn reschedule_events(
+    domain: &Domain,
+    particles: &mut Particles,
+    cells: &mut [Cell],
+    p: usize,
+    scheduler: &mut dyn Scheduler,
+) {
+    particles.invalidate(p);
+    let cell_indices: Vec<usize> = particles.cells[p].clone();
+    for &cell_index in cell_indices.iter() {
+        schedule_events(domain, particles, &mut cells[cell_index], p, scheduler);
     }
-    for dim in 0..NDIMS {
-        if let Some(event) = MoveOutOfCell::schedule(domain, time, cell, dim, p) {
-            insert_event(event, cell, scheduler);
-        }
+}
+
+/// Removes the particle detached from all cells,
+///   whose slot is taken over by the last particle.
+///
+/// N.B.: The events of the moved particle refer to the previous slot,
+///   and thus are invalidated and predicted again.
+fn remove_particle(
+    domain: &Domain,
+    time: f64,
+    particles: &mut Particles,
+    cells: &mut [Cell],
+    p: usize,
+    scheduler: &mut dyn Scheduler,
+) {
+    let last: usize = particles.len() - 1;
+    particles.swap_remove(p);
+    if p == last {
+        return;
     }
-    for dim in 0..NDIMS {
-        if let Some(event) = WallReflection::schedule(domain, time, cell, dim, p) {
-            insert_event(event, cell, scheduler);
+    for &cell_index in particles.cells[p].iter() {
+        for q in cells[cell_index].particles.iter_mut() {
+            if last == *q {
+                *q = p;
+            }
         }
     }
+    particles.advance(domain, p, time);
+    reschedule_events(domain, particles, cells, p, scheduler);
 }
 
-/// Cancels all events which involve the specified particle.
+/// Discards all invalidated events in the queue of the given cell,
+///   once it has doubled since the previous discard.
 ///
-/// # Arguments
-/// * `p`         - a particle whose events are to be removed.
-/// * `cell`      - a cell whose events which involve the given particle are to be removed.
-/// * `scheduler` - a minimum heap to find the next event.
-fn cancel_events(p: &Rc<RefCell<Particle>>, cell: &Rc<RefCell<Cell>>, scheduler: &mut Scheduler) {
-    if cfg!(debug_assertions) {
-        crate::simulator::debug::check_recognition(p, cell);
+/// N.B.: The events invalidated far before they come would otherwise pile up,
+///   whose cost is amortised over the insertions.
+/// N.B.: The head event is unchanged, provided that it is valid.
+fn discard_stale_events(particles: &Particles, cell: &mut Cell) {
+    if cell.events.len() <= 2 * cell.nkept.max(MIN_NKEPT) {
+        return;
+    }
+    cell.events.retain(|event| event.is_valid(particles));
+    cell.nkept = cell.events.len();
+}
+
+/// Discards the invalidated events on the top of the queue of the given cell.
+///
+/// N.B.: The caller is responsible for updating the scheduler.
+fn pop_stale_heads(particles: &Particles, cell: &mut Cell) {
+    while let Some(event) = cell.events.peek() {
+        if event.is_valid(particles) {
+            break;
+        }
+        cell.events.pop();
     }
-    let cell: Ref<Cell> = cell.borrow();
-    // get the head event before the event list is modified
-    let time_bef: f64 = get_head_event_time(&cell.events);
-    // only keep events which do not involve p
+}
+
+/// Discards the invalidated events coming first,
+///   so that the next one in the scheduler is valid.
+fn discard_stale_heads(particles: &Particles, cells: &mut [Cell], scheduler: &mut dyn Scheduler) {
+    loop {
+        let cell: &mut Cell = &mut cells[scheduler.get()];
+        let nevents: usize = cell.events.len();
+        let time_bef: f64 = cell.get_head_event_time();
+        pop_stale_heads(particles, cell);
+        if nevents == cell.events.len() {
+            break;
+        }
+        let time_aft: f64 = cell.get_head_event_time();
+        scheduler.update(cell.index, time_bef, time_aft);
+    }
+}
+
+/// Returns the time of the coming event.
+pub fn get_next_event_time(
+    particles: &Particles,
+    cells: &mut [Cell],
+    scheduler: &mut dyn Scheduler,
+) -> f64 {
+    discard_stale_heads(particles, cells, scheduler);
+    cells[scheduler.get()].get_head_event_time()
+}
+
+/// Replaces the pending synchronisation, which is handled by the main cell,
+///   with the one at the given time.
+pub fn reschedule_synchronisation(cells: &mut [Cell], scheduler: &mut dyn Scheduler, time: f64) {
+    let cell: &mut Cell = &mut cells[0];
+    let time_bef: f64 = cell.get_head_event_time();
     cell.events
-        .borrow_mut()
-        .retain(|event| match &event.eventtype {
-            EventType::InterParticleCollision(event) => {
-                !Rc::ptr_eq(p, &event.p_old) && !Rc::ptr_eq(p, &event.q_old)
-            }
-            EventType::MoveToNeighbour(event) => !Rc::ptr_eq(p, &event.p_old),
-            EventType::MoveOutOfCell(event) => !Rc::ptr_eq(p, &event.p_old),
-            EventType::WallReflection(event) => !Rc::ptr_eq(p, &event.p_old),
-            EventType::Synchronisation(_) => true,
-        });
-    // get the head event after the event list is modified
-    let time_aft: f64 = get_head_event_time(&cell.events);
-    // update heap
-    // NOTE: although this process is not necessary when the head event is not altered,
-    //   I perform this anyway since the overhead is small,
-    //   i.e. it attempts a down-shift, which is turned out to be not necessary,
-    //     then immediately quit
+        .retain(|event| !matches!(event.eventtype, EventType::Synchronisation(_)));
+    let time_aft: f64 = cell.get_head_event_time();
     scheduler.update(cell.index, time_bef, time_aft);
+    insert_event(Synchronisation::schedule(time, cell.index), cell, scheduler);
 }
 
 /// Core function.
 ///
-/// This function processes events until a synchronisation (all particles are at the same time).
-/// For each event, the whole process is as follows:
+/// This function processes the next event and returns its time.
+/// The whole process is as follows:
 /// 1. Picks up the next event
 /// 2. Updates involved particles, e.g. updating velocity
-/// 3. Cancels out-dated events and reschedule new events
+/// 3. Invalidates out-dated events and reschedule new events
+///
+/// The kind of the processed event is recorded in `counts`,
+///   and the quantities of interest are accumulated in `accumulators`.
+#[allow(clippy::too_many_arguments)]
+pub fn process_event(
+    domain: &Domain,
+    particles: &mut Particles,
+    cells: &mut [Cell],
+    scheduler: &mut dyn Scheduler,
+    rng: &mut Random,
+    sync_rate: f64,
+    counts: &mut EventCounts,
+    accumulators: &mut Accumulators,
+) -> f64 {
+    // take out the next valid event from the global queue
+    discard_stale_heads(particles, cells, scheduler);
+    let event: Event = {
+        // get the cell in which the next event happens
+        let cell: &mut Cell = &mut cells[scheduler.get()];
+        // trim the first element of the event queue
+        let event_bef: Event = cell.events.pop().unwrap();
+        let time_bef: f64 = event_bef.time;
+        // now the latest event is extracted and the heap is altered as well
+        // I need to fix it so that it is balanced again
+        let time_aft: f64 = cell.get_head_event_time();
+        scheduler.update(cell.index, time_bef, time_aft);
+        // the event to be processed is this
+        event_bef
+    };
+    // process the extracted event
+    let time: f64 = event.time;
+    counts.count(&event.eventtype);
+    match &event.eventtype {
+        EventType::InterParticleCollision(event) => {
+            // inter-particle collision
+            // update particle positions / velocities,
+            //   cancel all involved events,
+            //   reschedule events in all involved cells
+            event.execute(domain, time, particles, cells, scheduler, accumulators);
+        }
+        EventType::MoveToNeighbour(event) => {
+            // one particle is almost getting out of the cell
+            // I need to tell the information of it to the neighbouring cell
+            //   which is present in the direction of the particle motion
+            event.execute(domain, time, particles, cells, scheduler);
+        }
+        EventType::MoveOutOfCell(event) => {
+            // one particle has left the cell
+            // the cell forgets the particle,
+            //   and the particle forgets the cell
+            event.execute(domain, time, particles, cells);
+        }
+        EventType::WallReflection(event) => {
+            // update particle reflecting on the wall
+            // NOTE: only when the direction is not periodic
+            event.execute(domain, time, particles, cells, scheduler, rng, accumulators);
+        }
+        EventType::Outflow(event) => {
+            // one particle has left the domain
+            // the cells and the system forget the particle
+            event.execute(domain, time, particles, cells);
+            remove_particle(domain, time, particles, cells, event.p_old, scheduler);
+        }
+        EventType::LeesEdwardsShift(event) => {
+            // one particle has crossed the sliding boundary
+            // move it to the displaced image on the other side
+            event.execute(domain, time, particles, cells, scheduler);
+        }
+        EventType::Synchronisation(event) => {
+            // update all particles to the desired time to synchronise for output
+            event.execute(domain, time, sync_rate, particles, cells, scheduler);
+            accumulators.observe(domain, time, particles);
+        }
+    }
+    time
+}
+
+/// Processes events until a synchronisation (all particles are at the same time),
+///   and returns its time.
+#[allow(clippy::too_many_arguments)]
 pub fn process_events(
     domain: &Domain,
-    particles: &[Rc<RefCell<Particle>>],
-    cells: &[Rc<RefCell<Cell>>],
-    scheduler: &mut Scheduler,
+    particles: &mut Particles,
+    cells: &mut [Cell],
+    scheduler: &mut dyn Scheduler,
+    rng: &mut Random,
     sync_rate: f64,
+    counts: &mut EventCounts,
+    accumulators: &mut Accumulators,
 ) -> f64 {
     // loop until the desired time (synchronised)
-    let time: f64 = loop {
-        // take out the next event from the minumum heap
-        let event: Event = {
-            // get the cell in which the next event happens
-            let cell: Rc<RefCell<Cell>> = scheduler.get();
-            let cell: Ref<Cell> = cell.borrow();
-            // trim the first element of the event list
-            let event_bef: Event = cell.events.borrow_mut().remove(0);
-            let time_bef: f64 = event_bef.time;
-            // now the latest event is extracted and the heap is altered as well
-            // I need to fix it so that it is balanced again
-            let time_aft: f64 = get_head_event_time(&cell.events);
-            scheduler.update(cell.index, time_bef, time_aft);
-            // the event to be processed is this
-            event_bef
-        };
-        // process the extracted event
-        let time: f64 = event.time;
-        match &event.eventtype {
-            EventType::InterParticleCollision(event) => {
-                // inter-particle collision
-                // update particle positions / velocities,
-                //   cancel all involved events,
-                //   reschedule events in all involved cells
-                event.execute(domain, time, scheduler);
-            }
-            EventType::MoveToNeighbour(event) => {
-                // one particle is almost getting out of the cell
-                // I need to tell the information of it to the neighbouring cell
-                //   which is present in the direction of the particle motion
-                event.execute(domain, time, scheduler, cells);
-            }
-            EventType::MoveOutOfCell(event) => {
-                // one particle has left the cell
-                // the cell forgets the particle,
-                //   and the particle forgets the cell
-                event.execute(time, scheduler);
-            }
-            EventType::WallReflection(event) => {
-                // update particle reflecting on the wall
-                // NOTE: only when the direction is not periodic
-                event.execute(domain, time, scheduler);
-            }
-            EventType::Synchronisation(event) => {
-                // update all particles to the desired time to synchronise for output
-                // after this event exit the loop to draw state
-                event.execute(domain, time, sync_rate, particles, scheduler);
-                break time;
-            }
+    loop {
+        let nsyncs: usize = counts.synchronisation;
+        let time: f64 = process_event(
+            domain,
+            particles,
+            cells,
+            scheduler,
+            rng,
+            sync_rate,
+            counts,
+            accumulators,
+        );
+        // after the synchronisation exit the loop to draw state
+        if nsyncs != counts.synchronisation {
+            break time;
         }
-    };
-    time
+    }
+}
+
+#[cfg(test)]
+mod test_event_queue {
+    use super::{Event, Synchronisation};
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn case1() {
+        // the earliest event comes first
+        let mut events: BinaryHeap<Event> = BinaryHeap::new();
+        for time in [3., 1., 4., 1.5, 2.] {
+            events.push(Synchronisation::schedule(time, 0));
+        }
+        let times: Vec<f64> = std::iter::from_fn(|| events.pop().map(|event| event.time)).collect();
+        assert_eq!(times, vec![1., 1.5, 2., 3., 4.]);
+    }
 }
@@ -1,23 +1,25 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
-use crate::myvec::MyVec;
-use crate::simulator::cell::Cell;
-use crate::simulator::particle::Particle;
+use crate::myvec::{cross, wedge, MyVec, PseudoVec};
+use crate::simulator::cell::{Cell, CellPosition};
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::collision_model::Contact;
+use crate::simulator::lees_edwards::{self, GRADIENT_DIM};
+use crate::simulator::observables::Accumulators;
+use crate::simulator::particle::{get_moment_of_inertia, Particle, Particles, Stamp};
 use crate::simulator::scheduler::Scheduler;
 use crate::simulator::{Domain, NDIMS};
 
 use super::util;
 use super::{Event, EventType};
 
-/// Pseudo restitution coefficient.
-const RESTCOEF: f64 = 0.99;
-
 pub struct InterParticleCollision {
-    /// One of the involved particle
-    pub p_old: Rc<RefCell<Particle>>,
-    /// One of the involved particle
-    pub q_old: Rc<RefCell<Particle>>,
+    /// Slot of one of the involved particle
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted
+    pub p_stamp: Stamp,
+    /// Slot of one of the involved particle
+    pub q_old: usize,
+    /// Stamp of the particle when this event is predicted
+    pub q_stamp: Stamp,
     /// New particle position
     p_new_pos: MyVec,
     /// New particle position
@@ -26,129 +28,206 @@ pub struct InterParticleCollision {
     p_new_vel: MyVec,
     /// New particle velocity
     q_new_vel: MyVec,
-    /// New particle value
-    p_new_val: f64,
-    /// New particle value
-    q_new_val: f64,
+    /// New angular velocity
+    p_new_angvel: PseudoVec,
+    /// New angular velocity
+    q_new_angvel: PseudoVec,
+    /// New particle values
+    p_new_vals: Vec<f64>,
+    /// New particle values
+    q_new_vals: Vec<f64>,
+    /// Separation from p to q at the contact
+    dpos: MyVec,
+    /// Momentum given to q and taken from p
+    impulse: MyVec,
 }
 
 impl InterParticleCollision {
     pub fn schedule(
         domain: &Domain,
         time: f64,
-        cell: &Rc<RefCell<Cell>>,
-        p: &Rc<RefCell<Particle>>,
-        q: &Rc<RefCell<Particle>>,
+        cell: &Cell,
+        particles: &Particles,
+        p: usize,
+        q: usize,
     ) -> Option<Event> {
         let lengths: &[f64; NDIMS] = &domain.lengths;
-        let gravity = {
-            let gravity = domain
-                .periodicities
-                .map(|periodicity: bool| if periodicity { 0f64 } else { -0.5f64 });
-            MyVec::new(gravity)
+        let (p_rad, q_rad): (f64, f64) = (particles.radii[p], particles.radii[q]);
+        let (p_mass, q_mass): (f64, f64) = (particles.masses[p], particles.masses[q]);
+        let (p_angvel, q_angvel): (PseudoVec, PseudoVec) =
+            (particles.angvels[p], particles.angvels[q]);
+        // positions and velocities seen from this cell,
+        //   which differ from the original ones for the sliding images
+        let (p_pos, p_vel, q_pos, q_vel): (MyVec, MyVec, MyVec, MyVec) = {
+            let cell_pos: &CellPosition = &cell.positions[GRADIENT_DIM];
+            let (p_pos, p_vel) = lees_edwards::get_image(
+                domain,
+                time,
+                cell_pos,
+                particles.positions[p],
+                particles.velocities[p],
+            );
+            let (q_pos, q_vel) = lees_edwards::get_image(
+                domain,
+                time,
+                cell_pos,
+                particles.positions[q],
+                particles.velocities[q],
+            );
+            (p_pos, p_vel, q_pos, q_vel)
         };
-        let p_old: Ref<Particle> = p.borrow();
-        let q_old: Ref<Particle> = q.borrow();
-        // x0' = x0 + v0 dt
-        // x1' = x1 + v1 dt
-        // I want to know dt when |x1' - x0'| = r0 + r1, leading to
-        // dv^2 dt^2 + 2 dv dx dt + dx^2 - (r0 + r1)^2 = 0
-        // or
-        // a dt^2 + 2 b dt + c = 0
-        // or
-        // dt = 1 / a * ( - b [+-] sqrt(b^2 - a c) )
-        let mut dpos: MyVec = q_old.pos - p_old.pos;
-        let dvel: MyVec = q_old.vel - p_old.vel;
+        // sliding velocities of the images
+        let p_shift: MyVec = particles.velocities[p] - p_vel;
+        let q_shift: MyVec = particles.velocities[q] - q_vel;
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
+        let q_acc: MyVec = particles.get_acceleration(domain, q);
+        // x0' = x0 + v0 dt + a0 dt^2 / 2
+        // x1' = x1 + v1 dt + a1 dt^2 / 2
+        // I want to know dt when |x1' - x0'| = r0 + r1
+        let mut dpos: MyVec = q_pos - p_pos;
+        let dvel: MyVec = q_vel - p_vel;
+        let dacc: MyVec = q_acc - p_acc;
         for dim in 0..NDIMS {
-            dpos[dim] =
-                util::correct_periodicity(dpos[dim], lengths[dim], &cell.borrow().positions[dim]);
-        }
-        let a: f64 = dvel * dvel;
-        let b: f64 = dvel * dpos;
-        let c: f64 = dpos * dpos - (p_old.rad + q_old.rad).powi(2);
-        let d: f64 = b.powi(2) - a * c;
-        // a is non-negative
-        // exclude 0
-        if a < f64::EPSILON {
-            return None;
-        }
-        // when the displacement and the velocity vectors direct to the same direction,
-        //   the two particles never collide in the future
-        if 0. <= b {
-            return None;
+            dpos[dim] = util::correct_periodicity(dpos[dim], lengths[dim], &cell.positions[dim]);
         }
+        let c: f64 = dpos * dpos - (p_rad + q_rad).powi(2);
         // two particles are (slightly) overlapped,
         //   which may happen because of the rounding errors ust after collisions
         if c < 0. {
             return None;
         }
-        // discreminant, no solution when negative
-        if d < 0. {
-            return None;
-        }
-        // there are two solutions:
-        //   (-b + sqrt(d)) / a
-        //   (-b - sqrt(d)) / a
-        // I am interested in the smaller (earlier) solution, which is the latter
-        //   provided a > 0 and b < 0
-        let dt: f64 = 1. / a * (-d.sqrt() - b);
+        let dt: f64 = if dacc * dacc < f64::EPSILON {
+            // the relative motion is uniform, leading to
+            // dv^2 dt^2 + 2 dv dx dt + dx^2 - (r0 + r1)^2 = 0
+            // or
+            // a dt^2 + 2 b dt + c = 0
+            // or
+            // dt = 1 / a * ( - b [+-] sqrt(b^2 - a c) )
+            let a: f64 = dvel * dvel;
+            let b: f64 = dvel * dpos;
+            let d: f64 = b.powi(2) - a * c;
+            // a is non-negative
+            // exclude 0
+            if a < f64::EPSILON {
+                return None;
+            }
+            // when the displacement and the velocity vectors direct to the same direction,
+            //   the two particles never collide in the future
+            if 0. <= b {
+                return None;
+            }
+            // discreminant, no solution when negative
+            if d < 0. {
+                return None;
+            }
+            // there are two solutions:
+            //   (-b + sqrt(d)) / a
+            //   (-b - sqrt(d)) / a
+            // I am interested in the smaller (earlier) solution, which is the latter
+            //   provided a > 0 and b < 0
+            1. / a * (-d.sqrt() - b)
+        } else {
+            // the relative motion is parabolic, leading to a quartic equation
+            // da^2 / 4 dt^4 + da dv dt^3 + (dv^2 + da dx) dt^2 + 2 dv dx dt + c = 0
+            // whose smallest positive root is of interest
+            util::find_first_root(&[
+                c,
+                2. * (dvel * dpos),
+                dvel * dvel + dacc * dpos,
+                dvel * dacc,
+                0.25 * (dacc * dacc),
+            ])?
+        };
         if dt < 0. {
             return None;
         }
         // get positions and velocities after collision
-        // consider a coordinate system moving with the center of mass,
-        //   interchange the velocity
-        let (p_new_pos, q_new_pos, p_new_vel, q_new_vel, p_new_val, q_new_val): (
+        //   by exchanging the impulse given by the collision model
+        let (p_new_pos, q_new_pos, p_new_vel, q_new_vel, p_new_angvel, q_new_angvel, dpos, impulse): (
+            MyVec,
+            MyVec,
             MyVec,
             MyVec,
+            PseudoVec,
+            PseudoVec,
             MyVec,
             MyVec,
-            f64,
-            f64,
         ) = {
-            let p_new_pos: MyVec = Particle::get_new_pos(domain, p_old.pos, p_old.vel, dt);
-            let q_new_pos: MyVec = Particle::get_new_pos(domain, q_old.pos, q_old.vel, dt);
-            let new_val: f64 = 0.5 * p_old.val + 0.5 * q_old.val;
-            let p_new_val: f64 = new_val;
-            let q_new_val: f64 = new_val;
+            let p_new_pos: MyVec = Particle::get_new_pos(
+                domain,
+                particles.positions[p],
+                particles.velocities[p],
+                p_acc,
+                dt,
+            );
+            let q_new_pos: MyVec = Particle::get_new_pos(
+                domain,
+                particles.positions[q],
+                particles.velocities[q],
+                q_acc,
+                dt,
+            );
             // displacement with the periodicity considered
-            let dpos: MyVec = {
-                let mut dpos: MyVec = q_new_pos - p_new_pos;
-                for dim in 0..NDIMS {
-                    dpos[dim] = util::correct_periodicity(
-                        dpos[dim],
-                        lengths[dim],
-                        &cell.borrow().positions[dim],
-                    );
-                }
-                dpos
-            };
+            let dpos: MyVec = dpos + dvel * dt + (0.5 * dt * dt) * dacc;
+            // velocities just before collision
+            let p_vel: MyVec = Particle::get_new_vel(p_vel, p_acc, dt);
+            let q_vel: MyVec = Particle::get_new_vel(q_vel, q_acc, dt);
             // normal vector connecting particle centres
-            let normal: MyVec = dpos / (p_old.rad + q_old.rad);
-            // gravity-centre velocity
-            // NOTE: pseudo gravity is added
-            let gvel: MyVec = 0.5 * p_old.vel + 0.5 * q_old.vel + (new_val - 0.5) * gravity;
-            // velocity difference after collision in the centre-of-mass coordinate
-            let dvel = dvel - (1. + RESTCOEF) * (dvel * normal) * normal;
-            // go back to the original coordinate
+            let normal: MyVec = dpos / (p_rad + q_rad);
+            // rotations resist the tangential impulse as well
+            let p_arm: f64 = p_rad.powi(2) / get_moment_of_inertia(p_rad, p_mass);
+            let q_arm: f64 = q_rad.powi(2) / get_moment_of_inertia(q_rad, q_mass);
+            let mass: f64 = p_mass * q_mass / (p_mass + q_mass);
+            let contact = Contact {
+                normal,
+                vel: q_vel
+                    - p_vel
+                    - cross(p_rad * p_angvel + q_rad * q_angvel, normal),
+                mass,
+                tangential_mass: 1. / (1. / mass + p_arm + q_arm),
+            };
+            let impulse: MyVec = domain.collision_model.get_impulse(&contact);
+            // angular momentum around the contact point is conserved,
+            //   which is not exchanged by the smooth particles even by rounding errors
+            let torque: PseudoVec = if domain.collision_model.is_smooth() {
+                PseudoVec::default()
+            } else {
+                wedge(normal, impulse)
+            };
+            // remove the sliding velocities of the images
             (
                 p_new_pos,
                 q_new_pos,
-                gvel - 0.5 * dvel,
-                gvel + 0.5 * dvel,
-                p_new_val,
-                q_new_val,
+                p_vel - impulse / p_mass + p_shift,
+                q_vel + impulse / q_mass + q_shift,
+                p_angvel - torque * (p_arm / p_rad),
+                q_angvel - torque * (q_arm / q_rad),
+                dpos,
+                impulse,
             )
         };
+        // exchange scalars
+        let (p_new_vals, q_new_vals): (Vec<f64>, Vec<f64>) = domain
+            .scalar_transports
+            .iter()
+            .zip(particles.vals[p].iter().zip(particles.vals[q].iter()))
+            .map(|(transport, (&p_val, &q_val))| transport.exchange(p_val, q_val))
+            .unzip();
         let event = InterParticleCollision {
-            p_old: p.clone(),
-            q_old: q.clone(),
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            q_old: q,
+            q_stamp: particles.get_stamp(q, cell.index),
             p_new_pos,
             q_new_pos,
             p_new_vel,
             q_new_vel,
-            p_new_val,
-            q_new_val,
+            p_new_angvel,
+            q_new_angvel,
+            p_new_vals,
+            q_new_vals,
+            dpos,
+            impulse,
         };
         let event = Event {
             time: time + dt,
@@ -157,36 +236,243 @@ impl InterParticleCollision {
         Some(event)
     }
 
-    pub fn execute(&self, domain: &Domain, time: f64, scheduler: &mut Scheduler) {
-        let p: &Rc<RefCell<Particle>> = &self.p_old;
-        let q: &Rc<RefCell<Particle>> = &self.q_old;
+    pub fn execute(
+        &self,
+        domain: &Domain,
+        time: f64,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+        scheduler: &mut dyn Scheduler,
+        accumulators: &mut Accumulators,
+    ) {
+        accumulators.add_collision(&self.dpos, &self.impulse);
+        let p: usize = self.p_old;
+        let q: usize = self.q_old;
         // update particles
-        {
-            let mut p_mut: RefMut<Particle> = p.borrow_mut();
-            let mut q_mut: RefMut<Particle> = q.borrow_mut();
-            p_mut.pos = self.p_new_pos;
-            q_mut.pos = self.q_new_pos;
-            p_mut.vel = self.p_new_vel;
-            q_mut.vel = self.q_new_vel;
-            p_mut.val = self.p_new_val;
-            q_mut.val = self.q_new_val;
-            p_mut.time = time;
-            q_mut.time = time;
+        for (r, new_pos, new_vel, new_angvel, new_vals) in [
+            (
+                p,
+                self.p_new_pos,
+                self.p_new_vel,
+                self.p_new_angvel,
+                &self.p_new_vals,
+            ),
+            (
+                q,
+                self.q_new_pos,
+                self.q_new_vel,
+                self.q_new_angvel,
+                &self.q_new_vals,
+            ),
+        ] {
+            // record the free flights ended by this collision
+            let acc: MyVec = particles.get_acceleration(domain, r);
+            let vel: MyVec =
+                Particle::get_new_vel(particles.velocities[r], acc, time - particles.times[r]);
+            particles.flights[r].collide(time, (vel * vel).sqrt(), (new_vel * new_vel).sqrt());
+            particles.set_pos(domain, r, new_pos);
+            particles.velocities[r] = new_vel;
+            particles.angvels[r] = new_angvel;
+            particles.vals[r].clone_from(new_vals);
+            particles.times[r] = time;
+        }
+        // invalidate all events related to these two particles
+        //   since their velocities are altered,
+        //   and reschedule them
+        particles.invalidate(p);
+        particles.invalidate(q);
+        let p_cells: Vec<usize> = particles.cells[p].clone();
+        let q_cells: Vec<usize> = particles.cells[q].clone();
+        for &cell_index in p_cells.iter() {
+            super::schedule_events(domain, particles, &mut cells[cell_index], p, scheduler);
         }
-        // cancel all events related to these two particles
-        //   since their velocities are altered
-        for cell in p.borrow().cells.iter() {
-            super::cancel_events(p, cell, scheduler);
+        for &cell_index in q_cells.iter() {
+            super::schedule_events(domain, particles, &mut cells[cell_index], q, scheduler);
         }
-        for cell in q.borrow().cells.iter() {
-            super::cancel_events(q, cell, scheduler);
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.q_old)?;
+        encoder.put(&self.q_stamp)?;
+        encoder.put(&self.p_new_pos)?;
+        encoder.put(&self.q_new_pos)?;
+        encoder.put(&self.p_new_vel)?;
+        encoder.put(&self.q_new_vel)?;
+        encoder.put(&self.p_new_angvel)?;
+        encoder.put(&self.q_new_angvel)?;
+        encoder.put(&self.p_new_vals)?;
+        encoder.put(&self.q_new_vals)?;
+        encoder.put(&self.dpos)?;
+        encoder.put(&self.impulse)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(InterParticleCollision {
+            p_old: decoder.get()?,
+            p_stamp: decoder.get()?,
+            q_old: decoder.get()?,
+            q_stamp: decoder.get()?,
+            p_new_pos: decoder.get()?,
+            q_new_pos: decoder.get()?,
+            p_new_vel: decoder.get()?,
+            q_new_vel: decoder.get()?,
+            p_new_angvel: decoder.get()?,
+            q_new_angvel: decoder.get()?,
+            p_new_vals: decoder.get()?,
+            q_new_vals: decoder.get()?,
+            dpos: decoder.get()?,
+            impulse: decoder.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use super::InterParticleCollision;
+    use crate::myvec::{MyVec, PseudoVec};
+    use crate::simulator::boundary::get_periodic_boundaries;
+    use crate::simulator::cell::{init_cells, Cell};
+    use crate::simulator::event::{Event, EventType};
+    use crate::simulator::particle::{get_volume, FreeFlights, Images, Particle, Particles};
+    use crate::simulator::{Buoyancy, Domain, SimulationConfig, NDIMS};
+
+    fn init_particle(index: usize, pos: MyVec, vel: MyVec) -> Particle {
+        Particle {
+            index,
+            rad: 0.5,
+            mass: get_volume(0.5),
+            pos,
+            vel,
+            angvel: PseudoVec::default(),
+            time: 0.,
+            vals: vec![0.],
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // head-on collision along the diagonal
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([32.; NDIMS])
+            .boundaries(get_periodic_boundaries())
+            .gravity([0.; NDIMS])
+            .buoyancy(None)
+            .restitution_coefficient(1.)
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        let dir: MyVec = MyVec::new([1. / (NDIMS as f64).sqrt(); NDIMS]);
+        let centre: MyVec = MyVec::new([16.; NDIMS]);
+        let mut particles = Particles::default();
+        particles.push(init_particle(0, centre, 0.5 * dir));
+        particles.push(init_particle(1, centre + 3. * dir, -0.5 * dir));
+        for p in 0..particles.len() {
+            particles.append_cell(p, cells[0].index);
         }
-        // reschedule all events related to these two particles
-        for cell in p.borrow().cells.iter() {
-            super::schedule_events(domain, p, cell, scheduler);
+        let event: Event =
+            InterParticleCollision::schedule(&domain, 0., &cells[0], &particles, 0, 1).unwrap();
+        assert!((event.time - 2.).abs() < 1e-12);
+        match event.eventtype {
+            EventType::InterParticleCollision(event) => {
+                // velocities are exchanged
+                for dim in 0..NDIMS {
+                    assert!((event.p_new_vel[dim] + 0.5 * dir[dim]).abs() < 1e-12);
+                    assert!((event.q_new_vel[dim] - 0.5 * dir[dim]).abs() < 1e-12);
+                }
+            }
+            _ => panic!("unexpected event type"),
+        }
+    }
+
+    #[test]
+    fn case2() {
+        // separating particles never collide
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([32.; NDIMS])
+            .boundaries(get_periodic_boundaries())
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        let dir: MyVec = MyVec::new([1. / (NDIMS as f64).sqrt(); NDIMS]);
+        let centre: MyVec = MyVec::new([16.; NDIMS]);
+        let mut particles = Particles::default();
+        particles.push(init_particle(0, centre, -0.5 * dir));
+        particles.push(init_particle(1, centre + 3. * dir, 0.5 * dir));
+        assert!(
+            InterParticleCollision::schedule(&domain, 0., &cells[0], &particles, 0, 1).is_none()
+        );
+    }
+
+    #[test]
+    fn case3() {
+        // a particle at rest is hit by the other one sinking due to the buoyancy
+        let mut acceleration = [0.; NDIMS];
+        acceleration[1] = -1.;
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([32.; NDIMS])
+            .boundaries(get_periodic_boundaries())
+            .gravity([0.; NDIMS])
+            .buoyancy(Some(Buoyancy {
+                acceleration,
+                reference: 0.,
+            }))
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        let mut dir = MyVec::new([0.; NDIMS]);
+        dir[1] = 1.;
+        let centre: MyVec = MyVec::new([16.; NDIMS]);
+        let mut particles = Particles::default();
+        particles.push(init_particle(0, centre, MyVec::new([0.; NDIMS])));
+        particles.push(init_particle(1, centre + 3. * dir, MyVec::new([0.; NDIMS])));
+        particles.vals[1][0] = 1.;
+        for p in 0..particles.len() {
+            particles.append_cell(p, cells[0].index);
+        }
+        let event: Event =
+            InterParticleCollision::schedule(&domain, 0., &cells[0], &particles, 0, 1).unwrap();
+        assert!((event.time - 2.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn case4() {
+        // smooth particles never start spinning, even in oblique collisions
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([32.; NDIMS])
+            .boundaries(get_periodic_boundaries())
+            .gravity([0.; NDIMS])
+            .buoyancy(None)
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        let mut vel = MyVec::new([0.; NDIMS]);
+        vel[0] = 1.;
+        let mut offset = MyVec::new([0.; NDIMS]);
+        offset[0] = 3.;
+        offset[1] = 0.37;
+        let centre: MyVec = MyVec::new([16.; NDIMS]);
+        let mut particles = Particles::default();
+        particles.push(init_particle(0, centre, vel));
+        particles.push(init_particle(1, centre + offset, MyVec::new([0.; NDIMS])));
+        for p in 0..particles.len() {
+            particles.append_cell(p, cells[0].index);
         }
-        for cell in q.borrow().cells.iter() {
-            super::schedule_events(domain, q, cell, scheduler);
+        let event: Event =
+            InterParticleCollision::schedule(&domain, 0., &cells[0], &particles, 0, 1).unwrap();
+        match event.eventtype {
+            EventType::InterParticleCollision(event) => {
+                assert_eq!(event.p_new_angvel, PseudoVec::default());
+                assert_eq!(event.q_new_angvel, PseudoVec::default());
+            }
+            _ => panic!("unexpected event type"),
         }
     }
 }
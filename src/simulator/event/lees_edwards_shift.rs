@@ -0,0 +1,108 @@
+use crate::myvec::MyVec;
+use crate::simulator::cell::{Cell, CellPosition};
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::lees_edwards::{self, GRADIENT_DIM};
+use crate::simulator::particle::{Particle, Particles, Stamp};
+use crate::simulator::scheduler::Scheduler;
+use crate::simulator::Domain;
+
+use super::util;
+use super::{Event, EventType};
+
+pub struct LeesEdwardsShift {
+    /// Slot of the involved particle.
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted.
+    pub p_stamp: Stamp,
+    /// Position of the particle after this event, before being shifted.
+    p_new_pos: MyVec,
+    /// Velocity of the particle after this event, before being shifted.
+    p_new_vel: MyVec,
+    /// 1 (-1) when the particle comes back from the negative (positive) face.
+    sign: f64,
+}
+
+impl LeesEdwardsShift {
+    pub fn schedule(
+        domain: &Domain,
+        time: f64,
+        cell: &Cell,
+        particles: &Particles,
+        p: usize,
+    ) -> Option<Event> {
+        domain.shear_rate?;
+        let length: f64 = domain.lengths[GRADIENT_DIM];
+        let p_pos: MyVec = particles.positions[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
+        let pos: f64 = p_pos[GRADIENT_DIM];
+        let vel: f64 = p_vel[GRADIENT_DIM];
+        let acc: f64 = p_acc[GRADIENT_DIM];
+        // only the cell in which the particle centre is located takes care of it,
+        //   i.e. sliding images are excluded
+        let (bound, sign): (f64, f64) = match cell.positions[GRADIENT_DIM] {
+            CellPosition::NegativeEdge if pos < 0.5 * length => (0., 1.),
+            CellPosition::PositiveEdge if 0.5 * length < pos => (length, -1.),
+            _ => return None,
+        };
+        // a particle slightly crossing the face due to rounding errors is shifted immediately
+        let dt: f64 = if sign * (bound - pos) > 0. && sign * vel < 0. {
+            0.
+        } else {
+            util::get_crossing_time(bound - pos, vel, acc, -sign)?
+        };
+        let event = LeesEdwardsShift {
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            p_new_pos: Particle::get_new_pos(domain, p_pos, p_vel, p_acc, dt),
+            p_new_vel: Particle::get_new_vel(p_vel, p_acc, dt),
+            sign,
+        };
+        let event = Event {
+            time: time + dt,
+            eventtype: EventType::LeesEdwardsShift(event),
+        };
+        Some(event)
+    }
+
+    /// Moves the particle to the image on the other side,
+    ///   which is displaced and sliding in the flow direction.
+    pub fn execute(
+        &self,
+        domain: &Domain,
+        time: f64,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+        scheduler: &mut dyn Scheduler,
+    ) {
+        let p: usize = self.p_old;
+        let (mut pos, vel): (MyVec, MyVec) =
+            lees_edwards::shift(domain, time, self.sign, self.p_new_pos, self.p_new_vel);
+        pos[GRADIENT_DIM] += self.sign * domain.lengths[GRADIENT_DIM];
+        particles.set_pos(domain, p, self.p_new_pos);
+        particles.shift_image(domain, p, time, pos, self.sign);
+        particles.velocities[p] = vel;
+        particles.times[p] = time;
+        // reschedule all events related to this particle
+        //   since its position and velocity are altered
+        super::reschedule_events(domain, particles, cells, p, scheduler);
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.p_new_pos)?;
+        encoder.put(&self.p_new_vel)?;
+        encoder.put(&self.sign)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(LeesEdwardsShift {
+            p_old: decoder.get()?,
+            p_stamp: decoder.get()?,
+            p_new_pos: decoder.get()?,
+            p_new_vel: decoder.get()?,
+            sign: decoder.get()?,
+        })
+    }
+}
@@ -1,72 +1,83 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
 use crate::myvec::MyVec;
 use crate::simulator::cell::{Cell, CellPosition};
-use crate::simulator::particle::Particle;
-use crate::simulator::scheduler::Scheduler;
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::lees_edwards::{self, GRADIENT_DIM};
+use crate::simulator::particle::{Particle, Particles, Stamp};
 use crate::simulator::{Domain, NDIMS};
 
 use super::util;
 use super::{Event, EventType};
 
 pub struct MoveOutOfCell {
-    /// The cell in which this event happens.
-    cell: Rc<RefCell<Cell>>,
-    /// Reference to the involved particle.
-    pub p_old: Rc<RefCell<Particle>>,
+    /// Index of the cell in which this event happens.
+    cell: usize,
+    /// Slot of the involved particle.
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted.
+    pub p_stamp: Stamp,
     /// Position of the particle after this event.
     p_new_pos: MyVec,
+    /// Velocity of the particle after this event.
+    p_new_vel: MyVec,
 }
 
 impl MoveOutOfCell {
     pub fn schedule(
         domain: &Domain,
         time: f64,
-        cell: &Rc<RefCell<Cell>>,
+        cell: &Cell,
         dim: usize,
-        p: &Rc<RefCell<Particle>>,
+        particles: &Particles,
+        p: usize,
     ) -> Option<Event> {
         let lengths: &[f64; NDIMS] = &domain.lengths;
-        let periodicities: &[bool; NDIMS] = &domain.periodicities;
-        let p_old: Ref<Particle> = p.borrow();
+        let p_pos: MyVec = particles.positions[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
         let dt: f64 = {
             let length: f64 = lengths[dim];
-            let cell_pos: &CellPosition = &cell.borrow().positions[dim];
-            let periodicity: bool = periodicities[dim];
-            let rad: f64 = p_old.rad;
-            let pos: f64 = p_old.pos[dim];
-            let vel: f64 = p_old.vel[dim];
-            if 0. == vel {
-                return None;
-            }
-            if vel < 0. {
-                if !periodicity {
-                    if let CellPosition::NegativeEdge = *cell_pos {
-                        return None;
-                    }
-                }
-                let bound: f64 = cell.borrow().bounds[dim].min;
+            let cell_pos: &CellPosition = &cell.positions[dim];
+            let periodicity: bool = domain.periodicities[dim];
+            let rad: f64 = particles.radii[p];
+            // a sliding image leaves the cell with its own velocity
+            let (pos, vel): (MyVec, MyVec) =
+                lees_edwards::get_image(domain, time, &cell.positions[GRADIENT_DIM], p_pos, p_vel);
+            let pos: f64 = pos[dim];
+            let vel: f64 = vel[dim];
+            let acc: f64 = p_acc[dim];
+            // the particle may turn back under the acceleration,
+            //   and thus both faces are examined to find the earlier crossing
+            let dt_neg: Option<f64> = {
+                let is_wall: bool = !periodicity && matches!(*cell_pos, CellPosition::NegativeEdge);
+                let bound: f64 = cell.bounds[dim].min;
                 let dpos: f64 = util::correct_periodicity(bound - rad - pos, length, cell_pos);
-                dpos / vel
-            } else {
-                if !periodicity {
-                    if let CellPosition::PositiveEdge = *cell_pos {
-                        return None;
-                    }
+                if is_wall {
+                    None
+                } else {
+                    util::get_crossing_time(dpos, vel, acc, -1.)
                 }
-                let bound: f64 = cell.borrow().bounds[dim].max;
+            };
+            let dt_pos: Option<f64> = {
+                let is_wall: bool = !periodicity && matches!(*cell_pos, CellPosition::PositiveEdge);
+                let bound: f64 = cell.bounds[dim].max;
                 let dpos: f64 = util::correct_periodicity(bound + rad - pos, length, cell_pos);
-                dpos / vel
+                if is_wall {
+                    None
+                } else {
+                    util::get_crossing_time(dpos, vel, acc, 1.)
+                }
+            };
+            match (dt_neg, dt_pos) {
+                (Some(dt_neg), Some(dt_pos)) => dt_neg.min(dt_pos),
+                (dt_neg, dt_pos) => dt_neg.or(dt_pos)?,
             }
         };
-        if dt <= 0. {
-            return None;
-        }
         let event = MoveOutOfCell {
-            cell: cell.clone(),
-            p_old: p.clone(),
-            p_new_pos: Particle::get_new_pos(domain, p_old.pos, p_old.vel, dt),
+            cell: cell.index,
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            p_new_pos: Particle::get_new_pos(domain, p_pos, p_vel, p_acc, dt),
+            p_new_vel: Particle::get_new_vel(p_vel, p_acc, dt),
         };
         let event = Event {
             time: time + dt,
@@ -75,20 +86,40 @@ impl MoveOutOfCell {
         Some(event)
     }
 
-    pub fn execute(&self, time: f64, scheduler: &mut Scheduler) {
-        let p: &Rc<RefCell<Particle>> = &self.p_old;
-        {
-            let mut p_mut: RefMut<Particle> = p.borrow_mut();
-            p_mut.pos = self.p_new_pos;
-            p_mut.time = time;
-        }
+    pub fn execute(
+        &self,
+        domain: &Domain,
+        time: f64,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+    ) {
+        let p: usize = self.p_old;
+        particles.set_pos(domain, p, self.p_new_pos);
+        particles.velocities[p] = self.p_new_vel;
+        particles.times[p] = time;
         // for the cell from which the particle is leaving,
-        //   1. cancel events related to this particle in the old cell
-        //   2. remove the particle from the local particle list
-        //   3. remove the cell from the cell list
-        let cell: &Rc<RefCell<Cell>> = &self.cell;
-        super::cancel_events(p, cell, scheduler);
-        cell.borrow_mut().remove(p);
-        p.borrow_mut().remove(cell);
+        //   1. remove the particle from the local particle list
+        //   2. remove the cell from the cell list,
+        //        which invalidates the events of this particle left in the old cell
+        cells[self.cell].remove(p);
+        particles.remove_cell(p, self.cell);
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.cell)?;
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.p_new_pos)?;
+        encoder.put(&self.p_new_vel)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(MoveOutOfCell {
+            cell: decoder.get_cell_index()?,
+            p_old: decoder.get()?,
+            p_stamp: decoder.get()?,
+            p_new_pos: decoder.get()?,
+            p_new_vel: decoder.get()?,
+        })
     }
 }
@@ -1,9 +1,8 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
 use crate::myvec::MyVec;
 use crate::simulator::cell::{Cell, CellPosition};
-use crate::simulator::particle::Particle;
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::lees_edwards::{self, GRADIENT_DIM};
+use crate::simulator::particle::{Particle, Particles, Stamp};
 use crate::simulator::scheduler::Scheduler;
 use crate::simulator::{Domain, NDIMS};
 
@@ -11,66 +10,128 @@ use super::util;
 use super::{Event, EventType};
 
 pub struct MoveToNeighbour {
-    /// Reference to the involved particle.
-    pub p_old: Rc<RefCell<Particle>>,
+    /// Index of the cell in which this event happens.
+    cell: usize,
+    /// Direction normal to the face.
+    dim: usize,
+    /// -1 (1) when the particle passes the negative (positive) face.
+    dir: f64,
+    /// Slot of the involved particle.
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted.
+    pub p_stamp: Stamp,
     /// Position of the particle after this event.
     p_new_pos: MyVec,
-    /// Index of the cell, to which the particle information is passed.
-    new_cell_index: usize,
+    /// Velocity of the particle after this event.
+    p_new_vel: MyVec,
+    /// Indices of the cells, to which the particle information is passed.
+    /// Usually one, while the sliding image may overlap more cells.
+    new_cell_indices: Vec<usize>,
 }
 
 impl MoveToNeighbour {
     pub fn schedule(
         domain: &Domain,
         time: f64,
-        cell: &Rc<RefCell<Cell>>,
+        cell: &Cell,
+        dim: usize,
+        particles: &Particles,
+        p: usize,
+    ) -> Option<Event> {
+        // the particle may turn back under the acceleration,
+        //   and thus both faces are examined to find the earlier crossing
+        let event_neg: Option<Event> =
+            Self::schedule_face(domain, time, cell, dim, particles, p, -1.);
+        let event_pos: Option<Event> =
+            Self::schedule_face(domain, time, cell, dim, particles, p, 1.);
+        match (event_neg, event_pos) {
+            (Some(event_neg), Some(event_pos)) => {
+                if event_neg.time < event_pos.time {
+                    Some(event_neg)
+                } else {
+                    Some(event_pos)
+                }
+            }
+            (event_neg, event_pos) => event_neg.or(event_pos),
+        }
+    }
+
+    /// Schedules the event on the negative (positive) face when `dir` is -1 (1).
+    fn schedule_face(
+        domain: &Domain,
+        time: f64,
+        cell: &Cell,
         dim: usize,
-        p: &Rc<RefCell<Particle>>,
+        particles: &Particles,
+        p: usize,
+        dir: f64,
     ) -> Option<Event> {
         let lengths: &[f64; NDIMS] = &domain.lengths;
-        let periodicities: &[bool; NDIMS] = &domain.periodicities;
-        let p_old: Ref<Particle> = p.borrow();
-        let (dt, new_cell_index): (f64, usize) = {
+        let p_pos: MyVec = particles.positions[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_rad: f64 = particles.radii[p];
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
+        let (dt, sign, neighbour): (f64, f64, usize) = {
             let length: f64 = lengths[dim];
-            let cell_pos: &CellPosition = &cell.borrow().positions[dim];
-            let periodicity: bool = periodicities[dim];
-            let rad: f64 = p_old.rad;
-            let pos: f64 = p_old.pos[dim];
-            let vel: f64 = p_old.vel[dim];
-            if 0. == vel {
-                return None;
-            }
-            if vel < 0. {
+            let cell_pos: &CellPosition = &cell.positions[dim];
+            let periodicity: bool = domain.periodicities[dim];
+            let rad: f64 = p_rad;
+            // a sliding image approaches the neighbour with its own velocity
+            let (pos, vel): (MyVec, MyVec) =
+                lees_edwards::get_image(domain, time, &cell.positions[GRADIENT_DIM], p_pos, p_vel);
+            let pos: f64 = pos[dim];
+            let vel: f64 = vel[dim];
+            let acc: f64 = p_acc[dim];
+            if dir < 0. {
                 if !periodicity {
                     if let CellPosition::NegativeEdge = *cell_pos {
                         return None;
                     }
                 }
-                let bound: f64 = cell.borrow().bounds[dim].min;
+                let bound: f64 = cell.bounds[dim].min;
                 let dpos: f64 = util::correct_periodicity(bound + rad - pos, length, cell_pos);
-                let dt: f64 = dpos / vel;
-                let neighbour: usize = cell.borrow().neighbours[dim].min;
-                (dt, neighbour)
+                let dt: f64 = util::get_crossing_time(dpos, vel, acc, dir)?;
+                let neighbour: usize = cell.neighbours[dim].min;
+                (dt, 1., neighbour)
             } else {
                 if !periodicity {
                     if let CellPosition::PositiveEdge = *cell_pos {
                         return None;
                     }
                 }
-                let bound: f64 = cell.borrow().bounds[dim].max;
+                let bound: f64 = cell.bounds[dim].max;
                 let dpos: f64 = util::correct_periodicity(bound - rad - pos, length, cell_pos);
-                let dt: f64 = dpos / vel;
-                let neighbour: usize = cell.borrow().neighbours[dim].max;
-                (dt, neighbour)
+                let dt: f64 = util::get_crossing_time(dpos, vel, acc, dir)?;
+                let neighbour: usize = cell.neighbours[dim].max;
+                (dt, -1., neighbour)
             }
         };
-        if dt <= 0. {
-            return None;
-        }
+        let p_new_pos: MyVec = Particle::get_new_pos(domain, p_pos, p_vel, p_acc, dt);
+        let p_new_vel: MyVec = Particle::get_new_vel(p_vel, p_acc, dt);
+        // the particle reaching the sliding boundary is passed to the cells
+        //   which overlap its image at that moment
+        let is_sliding: bool = domain.shear_rate.is_some()
+            && GRADIENT_DIM == dim
+            && match cell.positions[dim] {
+                CellPosition::NegativeEdge => sign > 0.,
+                CellPosition::PositiveEdge => sign < 0.,
+                CellPosition::Centre => false,
+            };
+        let new_cell_indices: Vec<usize> = if is_sliding {
+            let (image_pos, _) = lees_edwards::shift(domain, time + dt, sign, p_new_pos, p_new_vel);
+            cell.get_sheared_neighbours(domain, p_rad, image_pos[lees_edwards::FLOW_DIM])
+        } else {
+            vec![neighbour]
+        };
         let event = MoveToNeighbour {
-            p_old: p.clone(),
-            p_new_pos: Particle::get_new_pos(domain, p_old.pos, p_old.vel, dt),
-            new_cell_index,
+            cell: cell.index,
+            dim,
+            dir,
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            p_new_pos,
+            p_new_vel,
+            new_cell_indices,
         };
         let event = Event {
             time: time + dt,
@@ -83,23 +144,74 @@ impl MoveToNeighbour {
         &self,
         domain: &Domain,
         time: f64,
-        scheduler: &mut Scheduler,
-        cells: &[Rc<RefCell<Cell>>],
+        particles: &mut Particles,
+        cells: &mut [Cell],
+        scheduler: &mut dyn Scheduler,
     ) {
-        let p: &Rc<RefCell<Particle>> = &self.p_old;
+        let p: usize = self.p_old;
+        particles.set_pos(domain, p, self.p_new_pos);
+        particles.velocities[p] = self.p_new_vel;
+        particles.times[p] = time;
+        // the particle turning back may pass the opposite face later,
+        //   which is not scheduled in this cell yet
+        let cell: &mut Cell = &mut cells[self.cell];
+        if let Some(event) =
+            Self::schedule_face(domain, time, cell, self.dim, particles, p, -self.dir)
         {
-            let mut p: RefMut<Particle> = p.borrow_mut();
-            p.pos = self.p_new_pos;
-            p.time = time;
+            super::insert_event(event, cell, scheduler);
         }
-        // for the new cell,
+        // for each new cell,
         //   1. register this particle to the next cell
         //   2. register the cell index to the list
         //   3. schedule events of the particle in the new cell
-        let cell_index: usize = self.new_cell_index;
-        let cell: &Rc<RefCell<Cell>> = &cells[cell_index];
-        cell.borrow_mut().append(p);
-        p.borrow_mut().append(cell);
-        super::schedule_events(domain, p, cell, scheduler);
+        for &cell_index in self.new_cell_indices.iter() {
+            // the sliding image may have been registered
+            //   by the same event in another cell
+            if particles.cells[p].contains(&cell_index) {
+                continue;
+            }
+            let cell: &mut Cell = &mut cells[cell_index];
+            cell.append(p);
+            particles.append_cell(p, cell_index);
+            super::schedule_events(domain, particles, cell, p, scheduler);
+        }
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.cell)?;
+        encoder.put(&self.dim)?;
+        encoder.put(&self.dir)?;
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.p_new_pos)?;
+        encoder.put(&self.p_new_vel)?;
+        encoder.put(&self.new_cell_indices)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let cell: usize = decoder.get_cell_index()?;
+        let dim: usize = decoder.get()?;
+        if NDIMS <= dim {
+            return Err(CheckpointError::Corrupted("invalid direction"));
+        }
+        let dir: f64 = decoder.get()?;
+        let p_old: usize = decoder.get()?;
+        let p_stamp: Stamp = decoder.get()?;
+        let p_new_pos: MyVec = decoder.get()?;
+        let p_new_vel: MyVec = decoder.get()?;
+        let ncells: usize = decoder.get_len()?;
+        let new_cell_indices: Vec<usize> = (0..ncells)
+            .map(|_| decoder.get_cell_index())
+            .collect::<Result<_, _>>()?;
+        Ok(MoveToNeighbour {
+            cell,
+            dim,
+            dir,
+            p_old,
+            p_stamp,
+            p_new_pos,
+            p_new_vel,
+            new_cell_indices,
+        })
     }
 }
@@ -0,0 +1,102 @@
+use crate::myvec::MyVec;
+use crate::simulator::boundary::Boundary;
+use crate::simulator::cell::{Cell, CellPosition};
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::particle::{Particle, Particles, Stamp};
+use crate::simulator::{Domain, Extrema};
+
+use super::util;
+use super::{Event, EventType};
+
+pub struct Outflow {
+    /// Slot of the involved particle.
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted.
+    pub p_stamp: Stamp,
+    /// Position of the particle after this event.
+    p_new_pos: MyVec,
+}
+
+impl Outflow {
+    pub fn schedule(
+        domain: &Domain,
+        time: f64,
+        cell: &Cell,
+        dim: usize,
+        particles: &Particles,
+        p: usize,
+    ) -> Option<Event> {
+        let p_pos: MyVec = particles.positions[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
+        let dt: f64 = {
+            let rad: f64 = particles.radii[p];
+            let pos: f64 = p_pos[dim];
+            let vel: f64 = p_vel[dim];
+            let acc: f64 = p_acc[dim];
+            // the face next to this cell, with the outward unit normal
+            let boundaries: &Extrema<Boundary> = &domain.boundaries[dim];
+            let (dir, bound): (f64, f64) = match cell.positions[dim] {
+                CellPosition::NegativeEdge if Boundary::Outflow == boundaries.min => {
+                    (-1., cell.bounds[dim].min + rad)
+                }
+                CellPosition::PositiveEdge if Boundary::Outflow == boundaries.max => {
+                    (1., cell.bounds[dim].max - rad)
+                }
+                _ => return None,
+            };
+            // a particle touching (or slightly crossing due to rounding errors) the face
+            //   and moving toward it is removed immediately
+            if 0. <= dir * (pos - bound) && 0. < dir * vel {
+                0.
+            } else {
+                util::get_crossing_time(bound - pos, vel, acc, dir)?
+            }
+        };
+        let event = Outflow {
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            p_new_pos: Particle::get_new_pos(domain, p_pos, p_vel, p_acc, dt),
+        };
+        let event = Event {
+            time: time + dt,
+            eventtype: EventType::Outflow(event),
+        };
+        Some(event)
+    }
+
+    /// Detaches the particle from all cells,
+    ///   whose events left in the cells are invalidated.
+    ///
+    /// N.B.: The caller is responsible for removing the particle from the arena.
+    pub fn execute(
+        &self,
+        domain: &Domain,
+        time: f64,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+    ) {
+        let p: usize = self.p_old;
+        particles.set_pos(domain, p, self.p_new_pos);
+        particles.times[p] = time;
+        let cell_indices: Vec<usize> = particles.cells[p].clone();
+        for &cell_index in cell_indices.iter() {
+            cells[cell_index].remove(p);
+            particles.remove_cell(p, cell_index);
+        }
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.p_new_pos)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Outflow {
+            p_old: decoder.get()?,
+            p_stamp: decoder.get()?,
+            p_new_pos: decoder.get()?,
+        })
+    }
+}
@@ -1,21 +1,19 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
 use crate::simulator::cell::Cell;
-use crate::simulator::particle::{find_minimum_distance, Particle};
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::particle::Particles;
 use crate::simulator::scheduler::Scheduler;
 use crate::simulator::Domain;
 
 use super::{Event, EventType};
 
 pub struct Synchronisation {
-    /// The cell in which this event happens.
-    cell: Rc<RefCell<Cell>>,
+    /// Index of the cell in which this event happens.
+    cell: usize,
 }
 
 impl Synchronisation {
-    pub fn schedule(time: f64, cell: &Rc<RefCell<Cell>>) -> Event {
-        let event = Synchronisation { cell: cell.clone() };
+    pub fn schedule(time: f64, cell: usize) -> Event {
+        let event = Synchronisation { cell };
         Event {
             time,
             eventtype: EventType::Synchronisation(event),
@@ -27,77 +25,35 @@ impl Synchronisation {
         domain: &Domain,
         time: f64,
         sync_rate: f64,
-        particles: &[Rc<RefCell<Particle>>],
-        scheduler: &mut Scheduler,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+        scheduler: &mut dyn Scheduler,
     ) {
         // update all particles
-        for particle in particles.iter() {
-            let mut p: RefMut<Particle> = particle.borrow_mut();
-            p.pos = Particle::get_new_pos(domain, p.pos, p.vel, time - p.time);
-            p.time = time;
-        }
+        synchronise(domain, time, particles);
         // schedule next synchronisation
         super::insert_event(
-            Synchronisation::schedule(time + sync_rate, &self.cell),
-            &self.cell,
+            Synchronisation::schedule(time + sync_rate, self.cell),
+            &mut cells[self.cell],
             scheduler,
         );
-        // check stats, only for binary crate without optimisation
-        if cfg!(debug_assertions) {
-            check_energy(time, particles);
-            check_distance(domain, time, particles);
-        }
     }
-}
 
-#[allow(dead_code)]
-fn check_energy(time: f64, particles: &[Rc<RefCell<Particle>>]) {
-    // compute total energy
-    let mut energy: f64 = 0.;
-    for particle in particles.iter() {
-        let p: Ref<Particle> = particle.borrow();
-        energy += 0.5 * p.vel * p.vel;
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.cell)
     }
-    let mut content = String::new();
-    content += format!("{:+22.15e} {:+22.15e}", time, energy).as_str();
-    save("energy.dat", &content);
-}
 
-#[allow(dead_code)]
-fn check_distance(domain: &Domain, time: f64, particles: &[Rc<RefCell<Particle>>]) {
-    let mut min: f64 = f64::MAX;
-    for (n, p) in particles.iter().enumerate() {
-        let p: Ref<Particle> = p.borrow();
-        for q in particles[n + 1..].iter() {
-            let q: Ref<Particle> = q.borrow();
-            let mut dist: f64 = find_minimum_distance(domain, p.pos, q.pos);
-            dist -= p.rad + q.rad;
-            min = min.min(dist);
-        }
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        Ok(Synchronisation {
+            cell: decoder.get_cell_index()?,
+        })
     }
-    let mut content = String::new();
-    content += format!("{:+22.15e} {:+22.15e}", time, min).as_str();
-    save("distance.dat", &content);
 }
 
-#[allow(dead_code)]
-fn save(filename: &str, content: &str) {
-    let mut file: std::fs::File = match std::fs::OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(filename)
-    {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("\"{}\": {}", filename, e);
-            return;
-        }
-    };
-    use std::io::Write;
-    match writeln!(file, "{}", content) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("\"{}\": {}", filename, e)
-        }
-    };
+/// Updates all particles to the given time,
+///   which is valid as long as no event happens in between.
+pub fn synchronise(domain: &Domain, time: f64, particles: &mut Particles) {
+    for p in 0..particles.len() {
+        particles.advance(domain, p, time);
+    }
 }
@@ -19,6 +19,112 @@ pub fn correct_periodicity(dpos: f64, length: f64, cell_position: &CellPosition)
     }
 }
 
+/// Upper limit of the bisection iterations.
+const MAX_ITERATIONS: usize = 256;
+
+/// Finds the real roots of `a x^2 + b x + c = 0` in ascending order.
+///
+/// A double root, or the single root when `a` is zero, is returned twice.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if 0. == a {
+        if 0. == b {
+            return None;
+        }
+        let x: f64 = -c / b;
+        return Some((x, x));
+    }
+    let d: f64 = b * b - 4. * a * c;
+    if d < 0. {
+        return None;
+    }
+    // avoid the loss of significance
+    let q: f64 = -0.5 * (b + b.signum() * d.sqrt());
+    if 0. == q {
+        return Some((0., 0.));
+    }
+    let x0: f64 = q / a;
+    let x1: f64 = c / q;
+    Some((x0.min(x1), x0.max(x1)))
+}
+
+/// Finds the earliest time at which a particle under a constant acceleration
+///   travels `dpos`, passing the point in the direction given by `sign`.
+pub fn get_crossing_time(dpos: f64, vel: f64, acc: f64, sign: f64) -> Option<f64> {
+    // dpos = vel * t + acc * t^2 / 2
+    let (t0, t1): (f64, f64) = solve_quadratic(0.5 * acc, vel, -dpos)?;
+    [t0, t1]
+        .into_iter()
+        .find(|&t: &f64| 0. < t && 0. < sign * (vel + acc * t))
+}
+
+/// Evaluates a polynomial whose coefficients are given in ascending order.
+fn evaluate(coefs: &[f64], x: f64) -> f64 {
+    coefs
+        .iter()
+        .rev()
+        .fold(0., |val: f64, &coef: &f64| val * x + coef)
+}
+
+/// Finds the roots of a polynomial in (lo, hi) where its sign changes, in ascending order.
+///
+/// The polynomial is monotonic between its extrema, which are the roots of the derivative,
+///   so that each interval contains one root at most, which is found by the bisection.
+fn find_roots(coefs: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    // vanishing leading coefficients are dropped
+    let mut n: usize = coefs.len();
+    while 0 < n && 0. == coefs[n - 1] {
+        n -= 1;
+    }
+    let coefs: &[f64] = &coefs[..n];
+    if n < 2 {
+        return Vec::new();
+    }
+    let derivative: Vec<f64> = coefs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(order, &coef)| order as f64 * coef)
+        .collect();
+    let mut bounds: Vec<f64> = vec![lo];
+    bounds.extend(find_roots(&derivative, lo, hi));
+    bounds.push(hi);
+    let mut roots = Vec::<f64>::new();
+    for bound in bounds.windows(2) {
+        let (mut a, mut b): (f64, f64) = (bound[0], bound[1]);
+        let is_positive: bool = 0. < evaluate(coefs, a);
+        let fb: f64 = evaluate(coefs, b);
+        if 0. != fb && is_positive == (0. < fb) {
+            continue;
+        }
+        for _ in 0..MAX_ITERATIONS {
+            let c: f64 = 0.5 * (a + b);
+            if c <= a || b <= c {
+                break;
+            }
+            if is_positive == (0. < evaluate(coefs, c)) {
+                a = c;
+            } else {
+                b = c;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
+
+/// Finds the smallest positive root of a polynomial whose coefficients are given
+///   in ascending order, and the constant term is positive.
+pub fn find_first_root(coefs: &[f64]) -> Option<f64> {
+    let n: usize = coefs.iter().rposition(|&coef: &f64| 0. != coef)?;
+    // Cauchy's bound of the roots
+    let hi: f64 = 1.
+        + coefs[..n]
+            .iter()
+            .map(|&coef: &f64| (coef / coefs[n]).abs())
+            .fold(0., f64::max);
+    find_roots(coefs, 0., hi).first().copied()
+}
+
 #[cfg(test)]
 mod test_get_index_of_absmin {
     use super::get_index_of_absmin as func;
@@ -38,3 +144,47 @@ mod test_get_index_of_absmin {
         assert_eq!(func(&data), 1);
     }
 }
+
+#[cfg(test)]
+mod test_get_crossing_time {
+    use super::get_crossing_time as func;
+    #[test]
+    fn case1() {
+        // uniform motion
+        assert_eq!(func(2., 0.5, 0., 1.), Some(4.));
+        assert_eq!(func(2., -0.5, 0., 1.), None);
+    }
+    #[test]
+    fn case2() {
+        // thrown up and falling down, passing the point twice
+        assert_eq!(func(1.5, 2., -1., 1.), Some(1.));
+        assert_eq!(func(1.5, 2., -1., -1.), Some(3.));
+    }
+    #[test]
+    fn case3() {
+        // never reaching the point
+        assert_eq!(func(3., 2., -1., 1.), None);
+    }
+}
+
+#[cfg(test)]
+mod test_find_first_root {
+    use super::find_first_root as func;
+    #[test]
+    fn case1() {
+        // (x - 1) (x - 2) (x - 3) (x - 4)
+        let root: f64 = func(&[24., -50., 35., -10., 1.]).unwrap();
+        assert!((root - 1.).abs() < 1e-12);
+    }
+    #[test]
+    fn case2() {
+        // (x - 1) (x - 2), where the leading coefficients vanish
+        let root: f64 = func(&[2., -3., 1., 0., 0.]).unwrap();
+        assert!((root - 1.).abs() < 1e-12);
+    }
+    #[test]
+    fn case3() {
+        // x^4 + 1 has no real roots
+        assert_eq!(func(&[1., 0., 0., 0., 1.]), None);
+    }
+}
@@ -1,107 +1,186 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
 use crate::myvec::MyVec;
+use crate::random::Random;
+use crate::simulator::boundary::{Boundary, ScalarCondition, WallMotion};
 use crate::simulator::cell::{Cell, CellPosition};
-use crate::simulator::extrema::Extrema;
-use crate::simulator::particle::Particle;
+use crate::simulator::checkpoint::{CheckpointError, Decoder, Encoder};
+use crate::simulator::observables::Accumulators;
+use crate::simulator::particle::{Particle, Particles, Stamp};
 use crate::simulator::Scheduler;
 use crate::simulator::{Domain, NDIMS};
 
+use super::util;
 use super::{Event, EventType};
 
-enum BoundaryCondition {
-    Dirichlet(f64),
-    Neumann,
-}
+/// Gap below which the particle is regarded as touching an oscillating wall.
+const TOLERANCE: f64 = 1e-10;
+/// Upper limit of the iterations to find the contact with an oscillating wall.
+const MAX_ITERATIONS: usize = 1 << 16;
 
 pub struct WallReflection {
-    /// Reference to the involved particle.
-    pub p_old: Rc<RefCell<Particle>>,
+    /// Slot of the involved particle.
+    pub p_old: usize,
+    /// Stamp of the particle when this event is predicted.
+    pub p_stamp: Stamp,
     /// Position of the particle after this event.
     p_new_pos: MyVec,
-    /// Velocity of the particle after this event.
+    /// Velocity of the particle when this event happens, before being reflected.
     p_new_vel: MyVec,
-    /// Scalar value after this event.
-    p_new_val: f64,
+    /// Scalar values after this event.
+    p_new_vals: Vec<f64>,
+    /// Wall-normal direction.
+    dim: usize,
+    /// Wall-normal unit vector pointing toward the domain.
+    sign: f64,
+    /// Condition of the wall.
+    boundary: Boundary,
+    /// Velocity of the wall when this event happens.
+    wall_vel: MyVec,
+}
+
+/// Finds the time until the particle surface touches the wall, both approaching each other.
+///
+/// # Arguments
+/// * `time`   - current time, which decides the phase of the oscillating wall.
+/// * `sign`   - wall-normal unit vector pointing toward the domain.
+/// * `bound`  - rest position of the wall.
+/// * `motion` - motion of the wall.
+/// * `rad`    - radius of the particle.
+/// * `pos`    - wall-normal position of the particle.
+/// * `vel`    - wall-normal velocity of the particle.
+/// * `acc`    - wall-normal acceleration of the particle.
+#[allow(clippy::too_many_arguments)]
+fn get_contact_time(
+    time: f64,
+    sign: f64,
+    bound: f64,
+    motion: &WallMotion,
+    rad: f64,
+    pos: f64,
+    vel: f64,
+    acc: f64,
+) -> Option<f64> {
+    // distance between the particle surface and the wall at rest
+    let gap: f64 = sign * (pos - bound) - rad;
+    // speed and acceleration of the particle approaching the wall at rest
+    let speed: f64 = -sign * vel;
+    let accel: f64 = -sign * acc;
+    if !motion.is_oscillating() {
+        // a particle touching (or slightly penetrating due to rounding errors) the wall
+        //   and moving toward it is reflected immediately
+        if gap <= 0. && 0. < speed {
+            return Some(0.);
+        }
+        return util::get_crossing_time(gap, speed, accel, 1.);
+    }
+    // gap between the particle and the wall at rest after dt
+    let get_gap = |dt: f64| -> f64 { gap - speed * dt - 0.5 * accel * dt * dt };
+    // the wall departs from the rest position at most by the amplitude,
+    //   until which the particle travels freely
+    let amplitude: f64 = motion.amplitude;
+    let mut dt: f64 = 0.;
+    if amplitude + TOLERANCE < gap {
+        dt = util::get_crossing_time(gap - amplitude, speed, accel, 1.)?;
+    }
+    // the wall sweeps its whole reach within one period,
+    //   so that a particle staying within the reach is hit by then unless only grazed
+    let period: f64 = 2. * std::f64::consts::PI / motion.angular_frequency.abs();
+    let mut deadline: f64 = dt + period;
+    // upper bound of the growth of the closing speed
+    let max_accel: f64 = (accel + motion.get_max_acceleration()).max(0.);
+    // conservative advancement:
+    //   the gap never closes faster than the current closing speed grown by the bound,
+    //   so that the first contact is not skipped
+    for _ in 0..MAX_ITERATIONS {
+        if deadline < dt {
+            return None;
+        }
+        let g: f64 = get_gap(dt) - sign * motion.get_displacement(time + dt);
+        let s: f64 = speed + accel * dt;
+        let approach: f64 = s + sign * motion.get_normal_velocity(time + dt);
+        if g < TOLERANCE {
+            if 0. < approach {
+                return Some(dt);
+            }
+            // separating just after a reflection
+            dt += util::get_crossing_time(2. * TOLERANCE - g, approach, max_accel, 1.)?;
+            continue;
+        }
+        if amplitude < get_gap(dt) {
+            // leaving the reach of the wall for good
+            if s <= 0. && accel <= 0. {
+                return None;
+            }
+            // or coming back later, from when the wall sweeps its reach again
+            deadline = dt + period;
+        }
+        // the largest step which the gap cannot close within
+        dt += util::get_crossing_time(g, approach, max_accel, 1.)?;
+    }
+    // the contact is not resolved
+    None
 }
 
 impl WallReflection {
     pub fn schedule(
         domain: &Domain,
         time: f64,
-        cell: &Rc<RefCell<Cell>>,
+        cell: &Cell,
         dim: usize,
-        p: &Rc<RefCell<Particle>>,
+        particles: &Particles,
+        p: usize,
     ) -> Option<Event> {
-        let periodicities: &[bool; NDIMS] = &domain.periodicities;
-        // boundary conditions, only used when the direction is non-periodic
-        let boundary_conditions: [Extrema<BoundaryCondition>; NDIMS] =
-            periodicities.map(|periodicity: bool| {
-                if periodicity {
-                    Extrema::<BoundaryCondition> {
-                        min: BoundaryCondition::Neumann,
-                        max: BoundaryCondition::Neumann,
-                    }
-                } else {
-                    Extrema::<BoundaryCondition> {
-                        min: BoundaryCondition::Dirichlet(0.),
-                        max: BoundaryCondition::Dirichlet(1.),
-                    }
-                }
-            });
-        let p_old: Ref<Particle> = p.borrow();
-        let (dt, p_new_vel, p_new_val): (f64, MyVec, f64) = {
-            // schedule only if the direction is wall-bounded (non-periodic)
-            let periodicity: bool = periodicities[dim];
-            if periodicity {
-                return None;
-            }
-            let cell_pos: &CellPosition = &cell.borrow().positions[dim];
-            let rad: f64 = p_old.rad;
-            let pos: f64 = p_old.pos[dim];
-            let vel: f64 = p_old.vel[dim];
-            if 0. == vel {
-                return None;
-            }
-            if vel < 0. {
-                match *cell_pos {
-                    CellPosition::NegativeEdge => {}
-                    _ => return None,
-                }
-                let bound: f64 = cell.borrow().bounds[dim].min;
-                let dpos: f64 = bound + rad - pos;
-                let mut p_new_vel: MyVec = p_old.vel;
-                p_new_vel[dim] = -1. * vel;
-                let p_new_val: f64 = match boundary_conditions[dim].min {
-                    BoundaryCondition::Dirichlet(val) => 0.5 * (val + p_old.val),
-                    BoundaryCondition::Neumann => p_old.val,
-                };
-                (dpos / vel, p_new_vel, p_new_val)
-            } else {
-                match *cell_pos {
-                    CellPosition::PositiveEdge => {}
-                    _ => return None,
-                }
-                let bound: f64 = cell.borrow().bounds[dim].max;
-                let dpos: f64 = bound - rad - pos;
-                let mut p_new_vel: MyVec = p_old.vel;
-                p_new_vel[dim] = -1. * vel;
-                let p_new_val: f64 = match boundary_conditions[dim].max {
-                    BoundaryCondition::Dirichlet(val) => 0.5 * (val + p_old.val),
-                    BoundaryCondition::Neumann => p_old.val,
-                };
-                (dpos / vel, p_new_vel, p_new_val)
-            }
+        // the face next to this cell
+        let (sign, boundary, motion, bound): (f64, &Boundary, WallMotion, f64) =
+            match cell.positions[dim] {
+                CellPosition::NegativeEdge => (
+                    1.,
+                    &domain.boundaries[dim].min,
+                    domain.wall_motions[dim].min,
+                    cell.bounds[dim].min,
+                ),
+                CellPosition::PositiveEdge => (
+                    -1.,
+                    &domain.boundaries[dim].max,
+                    domain.wall_motions[dim].max,
+                    cell.bounds[dim].max,
+                ),
+                CellPosition::Centre => return None,
+            };
+        // schedule only if the face is a wall
+        let p_new_vals: Vec<f64> = match boundary.scalar_condition() {
+            Some(ScalarCondition::Dirichlet(wall_vals)) => domain
+                .scalar_transports
+                .iter()
+                .zip(particles.vals[p].iter())
+                .zip(wall_vals.iter())
+                .map(|((transport, &val), &wall_val)| transport.exchange_with_wall(val, wall_val))
+                .collect(),
+            Some(ScalarCondition::Neumann) => particles.vals[p].clone(),
+            None => return None,
         };
-        if dt <= 0. {
-            return None;
-        }
+        let p_pos: MyVec = particles.positions[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_acc: MyVec = particles.get_acceleration(domain, p);
+        let dt: f64 = get_contact_time(
+            time,
+            sign,
+            bound,
+            &motion,
+            particles.radii[p],
+            p_pos[dim],
+            p_vel[dim],
+            p_acc[dim],
+        )?;
         let event = WallReflection {
-            p_old: p.clone(),
-            p_new_pos: Particle::get_new_pos(domain, p_old.pos, p_old.vel, dt),
-            p_new_vel,
-            p_new_val,
+            p_old: p,
+            p_stamp: particles.get_stamp(p, cell.index),
+            p_new_pos: Particle::get_new_pos(domain, p_pos, p_vel, p_acc, dt),
+            p_new_vel: Particle::get_new_vel(p_vel, p_acc, dt),
+            p_new_vals,
+            dim,
+            sign,
+            boundary: boundary.clone(),
+            wall_vel: motion.get_velocity(dim, time + dt),
         };
         let event = Event {
             time: time + dt,
@@ -110,23 +189,264 @@ impl WallReflection {
         Some(event)
     }
 
-    pub fn execute(&self, domain: &Domain, time: f64, scheduler: &mut Scheduler) {
-        let p: &Rc<RefCell<Particle>> = &self.p_old;
-        {
-            let mut p: RefMut<Particle> = p.borrow_mut();
-            p.pos = self.p_new_pos;
-            p.vel = self.p_new_vel;
-            p.val = self.p_new_val;
-            p.time = time;
+    /// Computes the velocity after reflection, which is considered in the frame moving with the wall.
+    fn get_new_vel(&self, mass: f64, vel: MyVec, rng: &mut Random) -> MyVec {
+        let dim: usize = self.dim;
+        let wall_vel: MyVec = self.wall_vel;
+        let mut new_vel: MyVec = vel;
+        match self.boundary {
+            Boundary::Diffuse { temperature, .. } => {
+                // tangential components follow the Maxwellian,
+                //   while the normal component follows the flux-weighted (Rayleigh) distribution
+                let stddev: f64 = (temperature / mass).sqrt();
+                for d in 0..NDIMS {
+                    new_vel[d] = wall_vel[d] + rng.gen_normal(0., stddev);
+                }
+                let u: f64 = loop {
+                    let u: f64 = rng.gen_range(0., 1.);
+                    if 0. < u {
+                        break u;
+                    }
+                };
+                new_vel[dim] = wall_vel[dim] + self.sign * stddev * (-2. * u.ln()).sqrt();
+            }
+            _ => {
+                // frictionless, only the normal component is affected
+                new_vel[dim] = 2. * wall_vel[dim] - vel[dim];
+            }
         }
-        // cancel all events related to these two particles
-        //   since their velocities are altered
-        for cell in p.borrow().cells.iter() {
-            super::cancel_events(p, cell, scheduler);
+        new_vel
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &self,
+        domain: &Domain,
+        time: f64,
+        particles: &mut Particles,
+        cells: &mut [Cell],
+        scheduler: &mut dyn Scheduler,
+        rng: &mut Random,
+        accumulators: &mut Accumulators,
+    ) {
+        let p: usize = self.p_old;
+        let mass: f64 = particles.masses[p];
+        let new_vel: MyVec = self.get_new_vel(mass, self.p_new_vel, rng);
+        let impulse: MyVec = mass * (new_vel - self.p_new_vel);
+        let dvals: Vec<f64> = self
+            .p_new_vals
+            .iter()
+            .zip(particles.vals[p].iter())
+            .map(|(&new_val, &val)| new_val - val)
+            .collect();
+        accumulators.add_reflection(self.dim, self.sign, &dvals, &impulse);
+        particles.set_pos(domain, p, self.p_new_pos);
+        particles.velocities[p] = new_vel;
+        particles.vals[p].clone_from(&self.p_new_vals);
+        particles.times[p] = time;
+        // reschedule all events related to this particle
+        //   since its velocity is altered
+        super::reschedule_events(domain, particles, cells, p, scheduler);
+    }
+
+    pub fn encode(&self, encoder: &mut Encoder) -> Result<(), CheckpointError> {
+        encoder.put(&self.p_old)?;
+        encoder.put(&self.p_stamp)?;
+        encoder.put(&self.p_new_pos)?;
+        encoder.put(&self.p_new_vel)?;
+        encoder.put(&self.p_new_vals)?;
+        encoder.put(&self.dim)?;
+        encoder.put(&self.sign)?;
+        encoder.put(&self.boundary)?;
+        encoder.put(&self.wall_vel)
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CheckpointError> {
+        let p_old: usize = decoder.get()?;
+        let p_stamp: Stamp = decoder.get()?;
+        let p_new_pos: MyVec = decoder.get()?;
+        let p_new_vel: MyVec = decoder.get()?;
+        let p_new_vals: Vec<f64> = decoder.get()?;
+        let dim: usize = decoder.get()?;
+        if NDIMS <= dim {
+            return Err(CheckpointError::Corrupted("invalid direction"));
+        }
+        Ok(WallReflection {
+            p_old,
+            p_stamp,
+            p_new_pos,
+            p_new_vel,
+            p_new_vals,
+            dim,
+            sign: decoder.get()?,
+            boundary: decoder.get()?,
+            wall_vel: decoder.get()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_get_new_vel {
+    use super::{get_contact_time, WallReflection};
+    use crate::myvec::MyVec;
+    use crate::random::Random;
+    use crate::simulator::boundary::{Boundary, ScalarCondition, WallMotion};
+    use crate::simulator::particle::Stamp;
+    use crate::simulator::NDIMS;
+
+    fn init_event(boundary: Boundary, sign: f64) -> WallReflection {
+        WallReflection {
+            p_old: 0,
+            p_stamp: Stamp::default(),
+            p_new_pos: MyVec::new([0.5; NDIMS]),
+            p_new_vel: MyVec::new([-1.; NDIMS]),
+            p_new_vals: vec![0.],
+            dim: 1,
+            sign,
+            boundary,
+            wall_vel: MyVec::new([0.; NDIMS]),
         }
-        // reschedule all events related to these two particles
-        for cell in p.borrow().cells.iter() {
-            super::schedule_events(domain, p, cell, scheduler);
+    }
+
+    #[test]
+    fn case1() {
+        // specular reflection only flips the normal component
+        let mut rng = Random::new(0);
+        let event = init_event(Boundary::Specular(ScalarCondition::Neumann), 1.);
+        let vel: MyVec = event.get_new_vel(1., MyVec::new([-1.; NDIMS]), &mut rng);
+        for dim in 0..NDIMS {
+            assert_eq!(vel[dim], if 1 == dim { 1. } else { -1. });
+        }
+    }
+
+    #[test]
+    fn case2() {
+        // diffuse reflection always directs toward the domain
+        let mut rng = Random::new(0);
+        let boundary = Boundary::Diffuse {
+            temperature: 1.,
+            scalar: ScalarCondition::Neumann,
+        };
+        for sign in [1., -1.] {
+            let event = init_event(boundary.clone(), sign);
+            for _ in 0..128 {
+                let vel: MyVec = event.get_new_vel(1., MyVec::new([-1.; NDIMS]), &mut rng);
+                assert!(0. < sign * vel[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn case3() {
+        // specular reflection on a wall moving toward the particle
+        let mut rng = Random::new(0);
+        let mut event = init_event(Boundary::Specular(ScalarCondition::Neumann), 1.);
+        event.wall_vel[1] = 0.5;
+        let vel: MyVec = event.get_new_vel(1., MyVec::new([-1.; NDIMS]), &mut rng);
+        assert_eq!(vel[1], 2.);
+    }
+
+    #[test]
+    fn case4() {
+        // a particle at rest is hit by the oscillating floor
+        let motion = WallMotion {
+            velocity: [0.; NDIMS],
+            amplitude: 0.5,
+            angular_frequency: 1.,
+        };
+        let dt: f64 = get_contact_time(0., 1., 0., &motion, 0.5, 0.75, 0., 0.).unwrap();
+        assert!((dt - std::f64::consts::PI / 6.).abs() < 1e-8);
+        // but not when it is out of reach and moving away
+        assert!(get_contact_time(0., 1., 0., &motion, 0.5, 1.5, 1., 0.).is_none());
+        // stationary walls
+        let motion = WallMotion::default();
+        assert_eq!(
+            get_contact_time(0., -1., 8., &motion, 0.5, 6.5, 0.5, 0.),
+            Some(2.)
+        );
+        assert!(get_contact_time(0., 1., 0., &motion, 0.5, 0.75, 0., 0.).is_none());
+    }
+
+    #[test]
+    fn case5() {
+        // a particle falling freely under the gravity
+        let motion = WallMotion::default();
+        assert_eq!(
+            get_contact_time(0., 1., 0., &motion, 0.5, 2.5, 0., -1.),
+            Some(2.)
+        );
+        // a particle thrown upward reaches the ceiling only when fast enough
+        assert_eq!(
+            get_contact_time(0., -1., 8., &motion, 0.5, 6., 2., -1.),
+            Some(1.)
+        );
+        assert!(get_contact_time(0., -1., 8., &motion, 0.5, 6., 1., -1.).is_none());
+    }
+
+    #[test]
+    fn case6() {
+        // a slow particle hovering on the oscillating floor at the top of its stroke
+        let motion = WallMotion {
+            velocity: [0.; NDIMS],
+            amplitude: 0.5,
+            angular_frequency: 1.,
+        };
+        let time: f64 = 0.5 * std::f64::consts::PI;
+        // rises slightly under a weak gravity, and is hit by the floor coming back
+        let (vel, acc): (f64, f64) = (1e-6, -1e-3);
+        let dt: f64 = get_contact_time(time, 1., 0., &motion, 0.5, 1., vel, acc).unwrap();
+        assert!(5. < dt && dt < 2. * std::f64::consts::PI);
+        let pos: f64 = 1. + vel * dt + 0.5 * acc * dt * dt;
+        assert!((pos - 0.5 - motion.get_displacement(time + dt)).abs() < 1e-8);
+        assert!(motion.get_normal_velocity(time + dt) > vel + acc * dt);
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use super::WallReflection;
+    use crate::myvec::{MyVec, PseudoVec};
+    use crate::simulator::boundary::{Boundary, ScalarCondition};
+    use crate::simulator::cell::{init_cells, Cell};
+    use crate::simulator::event::{Event, EventType};
+    use crate::simulator::particle::{get_volume, FreeFlights, Images, Particle, Particles};
+    use crate::simulator::{Conductive, Domain, SimulationConfig, NDIMS};
+
+    #[test]
+    fn case1() {
+        // each scalar is exchanged with its own wall value
+        let wall = |vals: Vec<f64>| Boundary::Specular(ScalarCondition::Dirichlet(vals));
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .add_scalar(Conductive { coefficient: 1. })
+            .boundary(1, wall(vec![0., 1.]), wall(vec![1., 0.]))
+            .buoyancy(None)
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let (_, cells): (_, Vec<Cell>) = init_cells(&domain, config.cell_size());
+        let mut vel: MyVec = MyVec::new([0.; NDIMS]);
+        vel[1] = -1.;
+        let mut particles = Particles::default();
+        particles.push(Particle {
+            index: 0,
+            rad: 0.5,
+            mass: get_volume(0.5),
+            pos: MyVec::new([1.; NDIMS]),
+            vel,
+            angvel: PseudoVec::default(),
+            time: 0.,
+            vals: vec![0.5, 0.5],
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        });
+        particles.append_cell(0, cells[0].index);
+        let event: Event =
+            WallReflection::schedule(&domain, 0., &cells[0], 1, &particles, 0).unwrap();
+        assert_eq!(event.time, 0.5);
+        match event.eventtype {
+            EventType::WallReflection(event) => assert_eq!(event.p_new_vals, vec![0.25, 0.75]),
+            _ => panic!("unexpected event type"),
         }
     }
 }
@@ -1,6 +1,16 @@
 /// Stores minimum and maximum data.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Extrema<T> {
     pub min: T,
     pub max: T,
 }
+
+impl<T: Clone> Extrema<T> {
+    /// Gives the same value to both.
+    pub fn both(value: T) -> Self {
+        Extrema {
+            min: value.clone(),
+            max: value,
+        }
+    }
+}
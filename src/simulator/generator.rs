@@ -0,0 +1,701 @@
+use crate::myvec::MyVec;
+use crate::random::Random;
+use crate::simulator::util::vec_to_array;
+use crate::simulator::NDIMS;
+
+/// Strategy to decide the initial particle positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placement {
+    /// Random sequential addition, where each particle is placed randomly
+    ///   without overlapping the ones placed before.
+    /// The volume fraction is capped, since it hardly finds a room in dense systems.
+    RandomSequential,
+    /// Square (simple cubic) lattice.
+    SquareLattice,
+    /// Hexagonal lattice, or the face-centred cubic lattice in three dimensions,
+    ///   realising the densest packing.
+    HexagonalLattice,
+    /// Random sequential addition in an expanded domain,
+    ///   followed by a gradual compression to the actual domain,
+    ///   where the overlaps are resolved at each stage.
+    ///
+    /// N.B.: Particles which still overlap after the compression are discarded,
+    ///   which happens close to the jamming (e.g. above 0.8 in two dimensions),
+    ///   where the lattices should be used instead.
+    Compression,
+}
+
+/// Distribution from which the initial particle velocities are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VelocityDistribution {
+    /// Each component is drawn uniformly from [-max, max].
+    Uniform { max: f64 },
+    /// Maxwell-Boltzmann distribution at the given temperature,
+    ///   where the velocity variance is inversely proportional to the particle mass.
+    Maxwellian { temperature: f64 },
+}
+
+/// Initial distribution of the scalars, which is shared by all scalars.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stratification {
+    /// Unity above the diagonal of the first two directions and zero below.
+    Diagonal,
+    /// Linear profile in `dim`, from `min` at the negative face to `max` at the positive face.
+    Linear { dim: usize, min: f64, max: f64 },
+    /// `min` below and `max` above the plane at `fraction` of the domain length in `dim`.
+    Step {
+        dim: usize,
+        fraction: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl VelocityDistribution {
+    /// Checks if the parameters are meaningful.
+    pub fn is_valid(&self) -> bool {
+        let is_non_negative = |val: f64| val.is_finite() && 0. <= val;
+        match *self {
+            VelocityDistribution::Uniform { max } => is_non_negative(max),
+            VelocityDistribution::Maxwellian { temperature } => is_non_negative(temperature),
+        }
+    }
+
+    /// Draws a velocity of a particle with the given mass.
+    pub fn sample(&self, mass: f64, rng: &mut Random) -> MyVec {
+        let vel: Vec<f64> = match *self {
+            VelocityDistribution::Uniform { max } => {
+                (0..NDIMS).map(|_| rng.gen_range(-max, max)).collect()
+            }
+            VelocityDistribution::Maxwellian { temperature } => {
+                let stddev: f64 = (temperature / mass).sqrt();
+                (0..NDIMS).map(|_| rng.gen_normal(0., stddev)).collect()
+            }
+        };
+        MyVec::new(vec_to_array::<f64>(vel))
+    }
+}
+
+impl Stratification {
+    /// Checks if the parameters are meaningful.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Stratification::Diagonal => true,
+            Stratification::Linear { dim, min, max } => {
+                dim < NDIMS && min.is_finite() && max.is_finite()
+            }
+            Stratification::Step {
+                dim,
+                fraction,
+                min,
+                max,
+            } => {
+                dim < NDIMS && (0. ..=1.).contains(&fraction) && min.is_finite() && max.is_finite()
+            }
+        }
+    }
+
+    /// Value of a particle at the given position.
+    pub fn get_value(&self, lengths: &[f64; NDIMS], pos: &MyVec) -> f64 {
+        match *self {
+            Stratification::Diagonal => {
+                if pos[0] / lengths[0] < pos[1] / lengths[1] {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            Stratification::Linear { dim, min, max } => min + (max - min) * pos[dim] / lengths[dim],
+            Stratification::Step {
+                dim,
+                fraction,
+                min,
+                max,
+            } => {
+                if pos[dim] < fraction * lengths[dim] {
+                    min
+                } else {
+                    max
+                }
+            }
+        }
+    }
+}
+
+/// Margin to keep the lattice sites slightly apart,
+///   so that the particles touching each other are not regarded as overlapping.
+const LATTICE_MARGIN: f64 = 1e-9;
+
+/// Rectangular unit cell relative to the nearest-neighbour distance,
+///   and the fractional coordinates of the sites in it.
+#[cfg(not(feature = "3d"))]
+fn get_unit_cell(placement: &Placement) -> ([f64; NDIMS], Vec<[f64; NDIMS]>) {
+    match placement {
+        Placement::HexagonalLattice => ([1., 3f64.sqrt()], vec![[0., 0.], [0.5, 0.5]]),
+        _ => ([1.; NDIMS], vec![[0.; NDIMS]]),
+    }
+}
+
+/// Rectangular unit cell relative to the nearest-neighbour distance,
+///   and the fractional coordinates of the sites in it.
+#[cfg(feature = "3d")]
+fn get_unit_cell(placement: &Placement) -> ([f64; NDIMS], Vec<[f64; NDIMS]>) {
+    match placement {
+        Placement::HexagonalLattice => (
+            [2f64.sqrt(); NDIMS],
+            vec![[0., 0., 0.], [0.5, 0.5, 0.], [0.5, 0., 0.5], [0., 0.5, 0.5]],
+        ),
+        _ => ([1.; NDIMS], vec![[0.; NDIMS]]),
+    }
+}
+
+/// Numbers of unit cells in each direction for the given nearest-neighbour distance.
+///
+/// In the wall-bounded directions, the available length is reduced by the particle radius,
+///   and the last unit cell is counted as long as its sites fit.
+fn get_lattice_size(
+    lengths: &[f64; NDIMS],
+    periodicities: &[bool; NDIMS],
+    max_rad: f64,
+    unit_cell: &([f64; NDIMS], Vec<[f64; NDIMS]>),
+    spacing: f64,
+) -> [usize; NDIMS] {
+    let (sizes, sites) = unit_cell;
+    std::array::from_fn(|dim: usize| {
+        let size: f64 = sizes[dim] * spacing;
+        if periodicities[dim] {
+            (lengths[dim] / size) as usize
+        } else {
+            let extent: f64 = sites.iter().map(|site| site[dim]).fold(0., f64::max);
+            let n: f64 = (lengths[dim] - 2. * max_rad) / size - extent;
+            if n < 0. {
+                0
+            } else {
+                n as usize + 1
+            }
+        }
+    })
+}
+
+/// Number of lattice sites in the domain, with the particles being in contact.
+pub fn get_lattice_capacity(
+    lengths: &[f64; NDIMS],
+    periodicities: &[bool; NDIMS],
+    max_rad: f64,
+    placement: &Placement,
+) -> usize {
+    let unit_cell = get_unit_cell(placement);
+    let spacing: f64 = 2. * max_rad * (1. + LATTICE_MARGIN);
+    let ncells = get_lattice_size(lengths, periodicities, max_rad, &unit_cell, spacing);
+    unit_cell.1.len() * ncells.iter().product::<usize>()
+}
+
+/// Places `nitems` particles on the lattice,
+///   whose spacing is as large as possible to accommodate them.
+///
+/// The vacant sites are chosen randomly.
+pub fn place_on_lattice(
+    lengths: &[f64; NDIMS],
+    periodicities: &[bool; NDIMS],
+    max_rad: f64,
+    placement: &Placement,
+    nitems: usize,
+    rng: &mut Random,
+) -> Vec<MyVec> {
+    let unit_cell = get_unit_cell(placement);
+    let (sizes, sites) = &unit_cell;
+    let count = |spacing: f64| -> usize {
+        let ncells = get_lattice_size(lengths, periodicities, max_rad, &unit_cell, spacing);
+        sites.len() * ncells.iter().product::<usize>()
+    };
+    // the number of sites only changes when a unit-cell boundary touches the domain boundary,
+    //   and thus the largest spacing is among such candidates
+    let min_spacing: f64 = 2. * max_rad * (1. + LATTICE_MARGIN);
+    let mut spacing: f64 = min_spacing;
+    for dim in 0..NDIMS {
+        let extent: f64 = sites.iter().map(|site| site[dim]).fold(0., f64::max);
+        let length: f64 = if periodicities[dim] {
+            lengths[dim]
+        } else {
+            lengths[dim] - 2. * max_rad
+        };
+        for n in 1.. {
+            let n: f64 = if periodicities[dim] {
+                n as f64
+            } else {
+                n as f64 - 1. + extent
+            };
+            if 0. == n {
+                continue;
+            }
+            // slightly shrunk to be free from the rounding errors
+            let candidate: f64 = length / (n * sizes[dim]) * (1. - LATTICE_MARGIN);
+            if candidate < min_spacing {
+                break;
+            }
+            if spacing < candidate && nitems <= count(candidate) {
+                spacing = candidate;
+            }
+        }
+    }
+    let ncells = get_lattice_size(lengths, periodicities, max_rad, &unit_cell, spacing);
+    // centre the lattice in the domain
+    let offsets: [f64; NDIMS] = std::array::from_fn(|dim: usize| {
+        let extent: f64 = sites.iter().map(|site| site[dim]).fold(0., f64::max);
+        let occupied: f64 = (ncells[dim] as f64 - 1. + extent) * sizes[dim] * spacing;
+        0.5 * (lengths[dim] - occupied)
+    });
+    let mut positions = Vec::<MyVec>::new();
+    for index in 0..ncells.iter().product::<usize>() {
+        let mut indices = [0usize; NDIMS];
+        let mut n: usize = index;
+        for (dim, i) in indices.iter_mut().enumerate() {
+            *i = n % ncells[dim];
+            n /= ncells[dim];
+        }
+        for site in sites.iter() {
+            let pos: [f64; NDIMS] = std::array::from_fn(|dim: usize| {
+                offsets[dim] + (indices[dim] as f64 + site[dim]) * sizes[dim] * spacing
+            });
+            positions.push(MyVec::new(pos));
+        }
+    }
+    // choose the occupied sites by the partial Fisher-Yates shuffle,
+    //   which are sorted to keep the spatial locality
+    let nsites: usize = positions.len();
+    let mut indices: Vec<usize> = (0..nsites).collect();
+    for n in 0..nitems.min(nsites) {
+        let m: usize = n + (rng.gen_range(0., (nsites - n) as f64) as usize).min(nsites - n - 1);
+        indices.swap(n, m);
+    }
+    indices.truncate(nitems);
+    indices.sort_unstable();
+    indices.into_iter().map(|index| positions[index]).collect()
+}
+
+/// Uniform grid to find particles close to each other,
+///   independent of the cells used by the simulator.
+struct Grid {
+    lengths: [f64; NDIMS],
+    periodicities: [bool; NDIMS],
+    nbins: [usize; NDIMS],
+    /// Particles in each bin.
+    bins: Vec<Vec<usize>>,
+    /// Bins adjacent to each bin including itself, without duplication.
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    fn new(lengths: [f64; NDIMS], periodicities: [bool; NDIMS], bin_size: f64) -> Grid {
+        let nbins: [usize; NDIMS] =
+            lengths.map(|length: f64| ((length / bin_size) as usize).max(1));
+        let nitems: usize = nbins.iter().product();
+        let mut grid = Grid {
+            lengths,
+            periodicities,
+            nbins,
+            bins: vec![Vec::<usize>::new(); nitems],
+            neighbours: Vec::with_capacity(nitems),
+        };
+        for index in 0..nitems {
+            let neighbours: Vec<usize> = grid.find_neighbours(index);
+            grid.neighbours.push(neighbours);
+        }
+        grid
+    }
+
+    fn find_neighbours(&self, index: usize) -> Vec<usize> {
+        let mut bin = [0usize; NDIMS];
+        let mut n: usize = index;
+        for (dim, b) in bin.iter_mut().enumerate() {
+            *b = n % self.nbins[dim];
+            n /= self.nbins[dim];
+        }
+        let mut neighbours = Vec::<usize>::new();
+        'outer: for n in 0..3usize.pow(NDIMS as u32) {
+            let mut neighbour: usize = 0;
+            let mut stride: usize = 1;
+            let mut n: usize = n;
+            for (dim, &b) in bin.iter().enumerate() {
+                let nbins: isize = self.nbins[dim] as isize;
+                let mut b: isize = b as isize + (n % 3) as isize - 1;
+                n /= 3;
+                if b < 0 || nbins <= b {
+                    if !self.periodicities[dim] {
+                        continue 'outer;
+                    }
+                    b = b.rem_euclid(nbins);
+                }
+                neighbour += stride * b as usize;
+                stride *= self.nbins[dim];
+            }
+            neighbours.push(neighbour);
+        }
+        // a few bins in one direction may be visited more than once
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn get_bin(&self, pos: &MyVec) -> usize {
+        let mut index: usize = 0;
+        for dim in (0..NDIMS).rev() {
+            let n: f64 = pos[dim] / self.lengths[dim] * self.nbins[dim] as f64;
+            let n: usize = (n.max(0.) as usize).min(self.nbins[dim] - 1);
+            index = index * self.nbins[dim] + n;
+        }
+        index
+    }
+
+    fn insert(&mut self, index: usize, pos: &MyVec) {
+        let bin: usize = self.get_bin(pos);
+        self.bins[bin].push(index);
+    }
+
+    /// Indices of the particles in the bins around the given position.
+    fn get_neighbours<'a>(&'a self, pos: &MyVec) -> impl Iterator<Item = usize> + 'a {
+        self.neighbours[self.get_bin(pos)]
+            .iter()
+            .flat_map(|&bin| self.bins[bin].iter().copied())
+    }
+
+    /// Pairs of the particles in the same or adjacent bins, each of which appears once.
+    fn get_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::<(usize, usize)>::new();
+        for (bin, neighbours) in self.neighbours.iter().enumerate() {
+            let ns: &[usize] = &self.bins[bin];
+            for &neighbour in neighbours.iter() {
+                if neighbour < bin {
+                    continue;
+                }
+                for (i, &n) in ns.iter().enumerate() {
+                    if neighbour == bin {
+                        pairs.extend(ns[i + 1..].iter().map(|&m| (n, m)));
+                    } else {
+                        pairs.extend(self.bins[neighbour].iter().map(|&m| (n, m)));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Separation from the first to the second position, taking the periodicity into account.
+    fn get_separation(&self, pos0: &MyVec, pos1: &MyVec) -> MyVec {
+        let mut dpos: MyVec = *pos1 - *pos0;
+        for dim in 0..NDIMS {
+            if self.periodicities[dim] {
+                let length: f64 = self.lengths[dim];
+                dpos[dim] -= length * (dpos[dim] / length).round();
+            }
+        }
+        dpos
+    }
+}
+
+/// Random sequential addition followed by the compression.
+///
+/// N.B.: Some particles may still overlap when the compression does not converge.
+pub fn place_by_compression(
+    lengths: &[f64; NDIMS],
+    periodicities: &[bool; NDIMS],
+    radii: &[f64],
+    rng: &mut Random,
+) -> Vec<MyVec> {
+    // volume fraction where the random sequential addition easily finds rooms
+    const DILUTE_VOLUME_FRACTION: f64 = 0.25;
+    // largest and smallest relative reductions of the domain length at each stage
+    const MAX_STEP: f64 = 1e-2;
+    const MIN_STEP: f64 = 1e-4;
+    // maximum number of sweeps to resolve the overlaps at each stage
+    const MAX_SWEEPS: usize = 256;
+    // gap relative to the contact distance left after the overlaps are resolved,
+    //   which accelerates the convergence
+    const GAP: f64 = 1e-3;
+    let max_rad: f64 = radii.iter().copied().fold(0., f64::max);
+    let volume: f64 = radii
+        .iter()
+        .map(|&rad| crate::simulator::particle::get_volume(rad))
+        .sum();
+    let volume_fraction: f64 = volume / lengths.iter().product::<f64>();
+    let expansion: f64 = (volume_fraction / DILUTE_VOLUME_FRACTION)
+        .powf(1. / NDIMS as f64)
+        .max(1.);
+    // the domain with the walls is expanded so that the particles touching them stay inside
+    let get_lengths = |scale: f64| -> [f64; NDIMS] {
+        std::array::from_fn(|dim: usize| {
+            if periodicities[dim] {
+                lengths[dim] * scale
+            } else {
+                (lengths[dim] - 2. * max_rad) * scale + 2. * max_rad
+            }
+        })
+    };
+    let clamp = |lengths: &[f64; NDIMS], rad: f64, pos: &mut MyVec| {
+        for dim in 0..NDIMS {
+            if periodicities[dim] {
+                pos[dim] = pos[dim].rem_euclid(lengths[dim]);
+            } else {
+                pos[dim] = pos[dim].clamp(rad, lengths[dim] - rad);
+            }
+        }
+    };
+    let is_overlapping = |grid: &Grid, positions: &[MyVec], n: usize, m: usize| -> bool {
+        let dpos: MyVec = grid.get_separation(&positions[n], &positions[m]);
+        let contact: f64 = (radii[n] + radii[m]) * (1. + LATTICE_MARGIN);
+        dpos * dpos < contact * contact
+    };
+    // random sequential addition in the expanded domain
+    let mut current: [f64; NDIMS] = get_lengths(expansion);
+    let mut positions = Vec::<MyVec>::with_capacity(radii.len());
+    let mut grid = Grid::new(current, *periodicities, 2. * max_rad);
+    for (n, &rad) in radii.iter().enumerate() {
+        loop {
+            let pos = MyVec::new(std::array::from_fn(|dim: usize| {
+                if periodicities[dim] {
+                    rng.gen_range(0., current[dim])
+                } else {
+                    rng.gen_range(rad, current[dim] - rad)
+                }
+            }));
+            positions.push(pos);
+            if grid
+                .get_neighbours(&pos)
+                .any(|m: usize| is_overlapping(&grid, &positions, n, m))
+            {
+                positions.pop();
+                continue;
+            }
+            grid.insert(n, &pos);
+            break;
+        }
+    }
+    // pushes each overlapping pair apart until no overlap is found
+    let relax = |lengths: &[f64; NDIMS], positions: &mut [MyVec]| -> bool {
+        let mut grid = Grid::new(*lengths, *periodicities, 2. * max_rad);
+        for _ in 0..MAX_SWEEPS {
+            for bin in grid.bins.iter_mut() {
+                bin.clear();
+            }
+            for (n, pos) in positions.iter().enumerate() {
+                grid.insert(n, pos);
+            }
+            let mut is_resolved: bool = true;
+            for (n, m) in grid.get_pairs() {
+                if !is_overlapping(&grid, positions, n, m) {
+                    continue;
+                }
+                is_resolved = false;
+                let dpos: MyVec = grid.get_separation(&positions[n], &positions[m]);
+                let dist: f64 = (dpos * dpos).sqrt();
+                let normal: MyVec = if 0. < dist {
+                    dpos / dist
+                } else {
+                    let mut normal = MyVec::new([0.; NDIMS]);
+                    normal[0] = 1.;
+                    normal
+                };
+                let contact: f64 = (radii[n] + radii[m]) * (1. + GAP);
+                let shift: MyVec = (0.5 * (contact - dist)) * normal;
+                positions[n] = positions[n] - shift;
+                positions[m] = positions[m] + shift;
+                clamp(lengths, radii[n], &mut positions[n]);
+                clamp(lengths, radii[m], &mut positions[m]);
+            }
+            if is_resolved {
+                return true;
+            }
+        }
+        false
+    };
+    // compress gradually, where the step is reduced when the overlaps are not resolved,
+    //   and the domain is compressed to the actual one at once when it jams
+    let mut scale: f64 = expansion;
+    let mut step: f64 = MAX_STEP;
+    let mut is_jammed: bool = false;
+    while 1. < scale {
+        let new_scale: f64 = if is_jammed {
+            1.
+        } else {
+            (scale * (1. - step)).max(1.)
+        };
+        let new: [f64; NDIMS] = get_lengths(new_scale);
+        let mut new_positions: Vec<MyVec> = positions.clone();
+        for (n, pos) in new_positions.iter_mut().enumerate() {
+            for dim in 0..NDIMS {
+                // the wall-bounded directions shrink about the particle centres touching the walls
+                let origin: f64 = if periodicities[dim] { 0. } else { max_rad };
+                pos[dim] = origin
+                    + (pos[dim] - origin) * (new[dim] - 2. * origin) / (current[dim] - 2. * origin);
+            }
+            clamp(&new, radii[n], pos);
+        }
+        if relax(&new, &mut new_positions) || is_jammed {
+            positions = new_positions;
+            current = new;
+            scale = new_scale;
+            step = (2. * step).min(MAX_STEP);
+        } else if step < MIN_STEP {
+            is_jammed = true;
+        } else {
+            step *= 0.5;
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+mod test_generator {
+    use super::{
+        get_lattice_capacity, place_by_compression, place_on_lattice, Placement, Stratification,
+        VelocityDistribution,
+    };
+    use crate::myvec::MyVec;
+    use crate::random::Random;
+    use crate::simulator::NDIMS;
+
+    /// Periodic in the first direction and wall-bounded in the others.
+    fn init_periodicities() -> [bool; NDIMS] {
+        std::array::from_fn(|dim: usize| 0 == dim)
+    }
+
+    /// Checks that no pair overlaps and that each particle is inside the domain.
+    fn check(lengths: &[f64; NDIMS], periodicities: &[bool; NDIMS], rad: f64, positions: &[MyVec]) {
+        for (n, p) in positions.iter().enumerate() {
+            for dim in 0..NDIMS {
+                if periodicities[dim] {
+                    assert!(0. <= p[dim] && p[dim] <= lengths[dim]);
+                } else {
+                    assert!(rad <= p[dim] && p[dim] <= lengths[dim] - rad);
+                }
+            }
+            for q in positions.iter().skip(n + 1) {
+                let mut dist2: f64 = 0.;
+                for dim in 0..NDIMS {
+                    let mut dpos: f64 = q[dim] - p[dim];
+                    if periodicities[dim] {
+                        dpos -= lengths[dim] * (dpos / lengths[dim]).round();
+                    }
+                    dist2 += dpos * dpos;
+                }
+                assert!(4. * rad * rad <= dist2);
+            }
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // square lattice filling the domain, which is slightly larger
+        //   since the particles are kept apart by the margin
+        let lengths = [16.5; NDIMS];
+        let periodicities = init_periodicities();
+        let placement = Placement::SquareLattice;
+        let capacity: usize = get_lattice_capacity(&lengths, &periodicities, 0.5, &placement);
+        assert_eq!(capacity, 16usize.pow(NDIMS as u32));
+        let mut rng = Random::new(0);
+        let positions = place_on_lattice(
+            &lengths,
+            &periodicities,
+            0.5,
+            &placement,
+            capacity,
+            &mut rng,
+        );
+        assert_eq!(positions.len(), capacity);
+        check(&lengths, &periodicities, 0.5, &positions);
+    }
+
+    #[test]
+    fn case2() {
+        // hexagonal lattice exceeding the volume fraction of the square lattice
+        let lengths = [16.5; NDIMS];
+        let periodicities = init_periodicities();
+        let placement = Placement::HexagonalLattice;
+        let capacity: usize = get_lattice_capacity(&lengths, &periodicities, 0.5, &placement);
+        assert!(16usize.pow(NDIMS as u32) < capacity);
+        let mut rng = Random::new(0);
+        let positions = place_on_lattice(
+            &lengths,
+            &periodicities,
+            0.5,
+            &placement,
+            capacity,
+            &mut rng,
+        );
+        assert_eq!(positions.len(), capacity);
+        check(&lengths, &periodicities, 0.5, &positions);
+        // the spacing is widened when fewer particles are requested
+        let positions = place_on_lattice(
+            &lengths,
+            &periodicities,
+            0.5,
+            &placement,
+            capacity / 2,
+            &mut rng,
+        );
+        assert_eq!(positions.len(), capacity / 2);
+        check(&lengths, &periodicities, 0.5, &positions);
+    }
+
+    #[test]
+    fn case3() {
+        // compression beyond the volume fraction reachable by the random sequential addition
+        let lengths = [12.; NDIMS];
+        let periodicities = init_periodicities();
+        let volume_fraction: f64 = if 2 == NDIMS { 0.6 } else { 0.45 };
+        let volume: f64 = crate::simulator::particle::get_volume(0.5);
+        let nitems: usize = (volume_fraction * lengths.iter().product::<f64>() / volume) as usize;
+        let radii: Vec<f64> = vec![0.5; nitems];
+        let mut rng = Random::new(0);
+        let positions = place_by_compression(&lengths, &periodicities, &radii, &mut rng);
+        assert_eq!(positions.len(), nitems);
+        check(&lengths, &periodicities, 0.5, &positions);
+    }
+
+    #[test]
+    fn case4() {
+        // Maxwellian velocities whose variance is the temperature divided by the mass
+        let distribution = VelocityDistribution::Maxwellian { temperature: 2. };
+        let mass: f64 = 4.;
+        let nitems: usize = 10000;
+        let mut rng = Random::new(0);
+        let mut sum2: f64 = 0.;
+        for _ in 0..nitems {
+            let vel: MyVec = distribution.sample(mass, &mut rng);
+            sum2 += vel * vel;
+        }
+        let variance: f64 = sum2 / (nitems * NDIMS) as f64;
+        assert!((variance - 0.5).abs() < 0.02);
+        assert!(!VelocityDistribution::Maxwellian { temperature: -1. }.is_valid());
+    }
+
+    #[test]
+    fn case5() {
+        // stratified scalars
+        let lengths = [4.; NDIMS];
+        let mut pos = MyVec::new([1.; NDIMS]);
+        pos[1] = 3.;
+        let linear = Stratification::Linear {
+            dim: 1,
+            min: 1.,
+            max: 3.,
+        };
+        assert_eq!(linear.get_value(&lengths, &pos), 2.5);
+        let step = Stratification::Step {
+            dim: 1,
+            fraction: 0.5,
+            min: -1.,
+            max: 1.,
+        };
+        assert_eq!(step.get_value(&lengths, &pos), 1.);
+        pos[1] = 1.;
+        assert_eq!(step.get_value(&lengths, &pos), -1.);
+        assert!(!Stratification::Step {
+            dim: 1,
+            fraction: 1.5,
+            min: -1.,
+            max: 1.,
+        }
+        .is_valid());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::simulator::{Domain, NDIMS};
+
+/// Particles given explicitly instead of being placed randomly,
+///   e.g. lattice packings, data read from files, or the output of previous runs.
+///
+/// The masses are given by the particle volumes, and the angular velocities are zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InitialCondition {
+    pub positions: Vec<[f64; NDIMS]>,
+    pub velocities: Vec<[f64; NDIMS]>,
+    pub radii: Vec<f64>,
+    /// Scalars of each particle, one for each scalar transport model.
+    pub vals: Vec<Vec<f64>>,
+}
+
+/// Reasons why an initial condition is rejected, or cannot be generated.
+#[derive(Debug, PartialEq)]
+pub enum InitialConditionError {
+    /// Numbers of positions, velocities, radii and scalars differ.
+    InconsistentLength,
+    /// Radius of the given particle is not positive or not finite.
+    InvalidRadius(usize),
+    /// Cells are not larger than twice the diameter of the given particle.
+    TooLargeRadius(usize),
+    /// Velocity of the given particle is not finite.
+    InvalidVelocity(usize),
+    /// Number of scalars of the given particle differs from the number of the models,
+    ///   or one of them is not finite.
+    InvalidScalars(usize),
+    /// The given particle is not inside the domain or penetrates a wall.
+    OutOfDomain(usize),
+    /// The given two particles overlap.
+    Overlap(usize, usize),
+    /// Generator stops after placing the latter number of particles out of the former,
+    ///   due to the cap on the volume fraction or the overlaps left after the placement.
+    Shortfall(usize, usize),
+}
+
+impl std::fmt::Display for InitialConditionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitialConditionError::InconsistentLength => write!(
+                f,
+                "numbers of positions, velocities, radii and scalars should be identical"
+            ),
+            InitialConditionError::InvalidRadius(index) => {
+                write!(f, "radius of particle {} should be positive", index)
+            }
+            InitialConditionError::TooLargeRadius(index) => write!(
+                f,
+                "cell size should be larger than twice the diameter of particle {}",
+                index
+            ),
+            InitialConditionError::InvalidVelocity(index) => {
+                write!(f, "velocity of particle {} should be finite", index)
+            }
+            InitialConditionError::InvalidScalars(index) => write!(
+                f,
+                "particle {} should carry one finite value for each scalar",
+                index
+            ),
+            InitialConditionError::OutOfDomain(index) => {
+                write!(f, "particle {} should be inside the domain", index)
+            }
+            InitialConditionError::Overlap(p, q) => {
+                write!(f, "particles {} and {} overlap", p, q)
+            }
+            InitialConditionError::Shortfall(nparticles, nplaced) => write!(
+                f,
+                "only {} out of {} particles are placed",
+                nplaced, nparticles
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InitialConditionError {}
+
+impl InitialCondition {
+    /// Number of particles.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Checks each particle against the domain,
+    ///   while the overlaps are examined when the particles are registered to the cells.
+    pub fn check(&self, domain: &Domain) -> Result<(), InitialConditionError> {
+        let nitems: usize = self.len();
+        if nitems != self.velocities.len()
+            || nitems != self.radii.len()
+            || nitems != self.vals.len()
+        {
+            return Err(InitialConditionError::InconsistentLength);
+        }
+        let lengths: &[f64; NDIMS] = &domain.lengths;
+        let cell_size: f64 = (0..NDIMS)
+            .map(|dim: usize| lengths[dim] / domain.ncells[dim] as f64)
+            .fold(f64::MAX, f64::min);
+        for index in 0..nitems {
+            let rad: f64 = self.radii[index];
+            if !(rad.is_finite() && 0. < rad) {
+                return Err(InitialConditionError::InvalidRadius(index));
+            }
+            if cell_size <= 4. * rad {
+                return Err(InitialConditionError::TooLargeRadius(index));
+            }
+            if self.velocities[index]
+                .iter()
+                .any(|vel: &f64| !vel.is_finite())
+            {
+                return Err(InitialConditionError::InvalidVelocity(index));
+            }
+            let vals: &[f64] = &self.vals[index];
+            if domain.scalar_transports.len() != vals.len()
+                || vals.iter().any(|val: &f64| !val.is_finite())
+            {
+                return Err(InitialConditionError::InvalidScalars(index));
+            }
+            // particles are allowed to touch the walls
+            for (dim, &length) in lengths.iter().enumerate() {
+                let pos: f64 = self.positions[index][dim];
+                let (min, max): (f64, f64) = if domain.periodicities[dim] {
+                    (0., length)
+                } else {
+                    (rad, length - rad)
+                };
+                if !(min <= pos && pos <= max) {
+                    return Err(InitialConditionError::OutOfDomain(index));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_from_initial_condition {
+    use super::{InitialCondition, InitialConditionError};
+    use crate::simulator::{SimulationConfig, Simulator, NDIMS};
+
+    /// Simple cubic lattice with the given spacing, filling the domain of the given length.
+    fn init_lattice(length: f64, spacing: f64) -> InitialCondition {
+        let nitems_per_dim: usize = (length / spacing) as usize;
+        let nitems: usize = nitems_per_dim.pow(NDIMS as u32);
+        let mut initial_condition = InitialCondition::default();
+        for index in 0..nitems {
+            let mut pos = [0.; NDIMS];
+            let mut vel = [0.; NDIMS];
+            let mut n: usize = index;
+            for x in pos.iter_mut() {
+                *x = spacing * ((n % nitems_per_dim) as f64 + 0.5);
+                n /= nitems_per_dim;
+            }
+            vel[0] = if 0 == index % 2 { 1. } else { -1. };
+            initial_condition.positions.push(pos);
+            initial_condition.velocities.push(vel);
+            initial_condition.radii.push(0.5);
+            initial_condition.vals.push(vec![index as f64]);
+        }
+        initial_condition
+    }
+
+    fn init_config() -> SimulationConfig {
+        SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn case1() {
+        // the given particles are kept as they are
+        let initial_condition: InitialCondition = init_lattice(16., 2.);
+        let mut simulator =
+            Simulator::from_initial_condition(init_config(), &initial_condition).unwrap();
+        let particles = simulator.get_particles();
+        assert_eq!(particles.len(), initial_condition.len());
+        for index in 0..particles.len() {
+            assert_eq!(particles.indices[index], index);
+            for dim in 0..NDIMS {
+                assert_eq!(
+                    particles.positions[index][dim],
+                    initial_condition.positions[index][dim]
+                );
+                assert_eq!(
+                    particles.velocities[index][dim],
+                    initial_condition.velocities[index][dim]
+                );
+            }
+            assert_eq!(particles.vals[index], initial_condition.vals[index]);
+        }
+        simulator.integrate();
+    }
+
+    #[test]
+    fn case2() {
+        // overlapping particles are rejected
+        let mut initial_condition: InitialCondition = init_lattice(16., 2.);
+        initial_condition.positions[1] = initial_condition.positions[0];
+        initial_condition.positions[1][0] += 0.5;
+        let result = Simulator::from_initial_condition(init_config(), &initial_condition);
+        assert!(matches!(result, Err(InitialConditionError::Overlap(0, 1))));
+    }
+
+    #[test]
+    fn case3() {
+        // particles penetrating the walls in the vertical direction are rejected
+        let mut initial_condition: InitialCondition = init_lattice(16., 2.);
+        initial_condition.positions[0][1] = 0.25;
+        let result = Simulator::from_initial_condition(init_config(), &initial_condition);
+        assert!(matches!(result, Err(InitialConditionError::OutOfDomain(0))));
+        // while they can cross the periodic boundaries
+        let mut initial_condition: InitialCondition = init_lattice(16., 2.);
+        initial_condition.positions[0][0] = 0.25;
+        let result = Simulator::from_initial_condition(init_config(), &initial_condition);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn case4() {
+        // inconsistent inputs are rejected
+        let mut initial_condition: InitialCondition = init_lattice(16., 2.);
+        initial_condition.radii.pop();
+        let result = Simulator::from_initial_condition(init_config(), &initial_condition);
+        assert!(matches!(
+            result,
+            Err(InitialConditionError::InconsistentLength)
+        ));
+        let mut initial_condition: InitialCondition = init_lattice(16., 2.);
+        initial_condition.vals[2].push(0.);
+        let result = Simulator::from_initial_condition(init_config(), &initial_condition);
+        assert!(matches!(
+            result,
+            Err(InitialConditionError::InvalidScalars(2))
+        ));
+    }
+}
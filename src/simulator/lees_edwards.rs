@@ -0,0 +1,112 @@
+use crate::myvec::MyVec;
+use crate::simulator::cell::CellPosition;
+use crate::simulator::Domain;
+
+/// Direction of the mean flow.
+pub const FLOW_DIM: usize = 0usize;
+/// Direction of the velocity gradient, whose faces slide relative to each other.
+pub const GRADIENT_DIM: usize = 1usize;
+
+/// Velocity difference between the positive and the negative faces in the gradient direction.
+pub fn get_velocity(domain: &Domain) -> f64 {
+    match domain.shear_rate {
+        Some(shear_rate) => shear_rate * domain.lengths[GRADIENT_DIM],
+        None => 0.,
+    }
+}
+
+/// Displacement of the periodic image above the domain at the given time,
+///   which is wrapped into the domain length in the flow direction.
+pub fn get_offset(domain: &Domain, time: f64) -> f64 {
+    (get_velocity(domain) * time).rem_euclid(domain.lengths[FLOW_DIM])
+}
+
+/// Moves a particle to the image above (`sign` = 1) or below (`sign` = -1) the domain
+///   in the flow direction, i.e. the offset and the sliding velocity are added.
+///
+/// N.B.: The position in the gradient direction is unchanged.
+pub fn shift(domain: &Domain, time: f64, sign: f64, pos: MyVec, vel: MyVec) -> (MyVec, MyVec) {
+    let length: f64 = domain.lengths[FLOW_DIM];
+    let mut pos: MyVec = pos;
+    let mut vel: MyVec = vel;
+    pos[FLOW_DIM] = (pos[FLOW_DIM] + sign * get_offset(domain, time)).rem_euclid(length);
+    vel[FLOW_DIM] += sign * get_velocity(domain);
+    (pos, vel)
+}
+
+/// Returns the position and the velocity of a particle seen from a cell.
+///
+/// A particle belonging to an edge cell in the gradient direction but located on the other side
+///   of the domain is an image, which is displaced and moving in the flow direction.
+///
+/// # Arguments
+/// * `domain`   - the domain, which may not have the Lees-Edwards boundaries.
+/// * `time`     - the time at which `pos` is given.
+/// * `cell_pos` - position of the cell in the gradient direction.
+/// * `pos`      - position of the particle.
+/// * `vel`      - velocity of the particle.
+pub fn get_image(
+    domain: &Domain,
+    time: f64,
+    cell_pos: &CellPosition,
+    pos: MyVec,
+    vel: MyVec,
+) -> (MyVec, MyVec) {
+    if domain.shear_rate.is_none() {
+        return (pos, vel);
+    }
+    let half: f64 = 0.5 * domain.lengths[GRADIENT_DIM];
+    let sign: f64 = match *cell_pos {
+        CellPosition::NegativeEdge if half < pos[GRADIENT_DIM] => -1.,
+        CellPosition::PositiveEdge if pos[GRADIENT_DIM] < half => 1.,
+        _ => return (pos, vel),
+    };
+    shift(domain, time, sign, pos, vel)
+}
+
+#[cfg(test)]
+mod test_get_image {
+    use super::{get_image, get_offset, FLOW_DIM, GRADIENT_DIM};
+    use crate::myvec::MyVec;
+    use crate::simulator::cell::CellPosition;
+    use crate::simulator::{Domain, NDIMS};
+
+    #[test]
+    fn case1() {
+        // the offset is wrapped into the domain
+        let domain: Domain = Domain::periodic([32.; NDIMS], Some(0.25));
+        assert_eq!(get_offset(&domain, 1.), 8.);
+        assert_eq!(get_offset(&domain, 5.), 8.);
+    }
+
+    #[test]
+    fn case2() {
+        // particle near the top face seen from the bottom cells
+        let domain: Domain = Domain::periodic([32.; NDIMS], Some(0.25));
+        let mut pos: MyVec = MyVec::new([1.; NDIMS]);
+        pos[GRADIENT_DIM] = 31.;
+        let vel: MyVec = MyVec::new([0.; NDIMS]);
+        let (img_pos, img_vel) = get_image(&domain, 1., &CellPosition::NegativeEdge, pos, vel);
+        assert_eq!(img_pos[FLOW_DIM], 25.);
+        assert_eq!(img_pos[GRADIENT_DIM], 31.);
+        assert_eq!(img_vel[FLOW_DIM], -8.);
+    }
+
+    #[test]
+    fn case3() {
+        // particle near the bottom face seen from the top cells
+        let domain: Domain = Domain::periodic([32.; NDIMS], Some(0.25));
+        let mut pos: MyVec = MyVec::new([1.; NDIMS]);
+        pos[GRADIENT_DIM] = 1.;
+        let vel: MyVec = MyVec::new([0.; NDIMS]);
+        let (img_pos, img_vel) = get_image(&domain, 1., &CellPosition::PositiveEdge, pos, vel);
+        assert_eq!(img_pos[FLOW_DIM], 9.);
+        assert_eq!(img_vel[FLOW_DIM], 8.);
+        // but not from the bottom cells
+        let (img_pos, img_vel) = get_image(&domain, 1., &CellPosition::NegativeEdge, pos, vel);
+        for dim in 0..NDIMS {
+            assert_eq!(img_pos[dim], pos[dim]);
+            assert_eq!(img_vel[dim], vel[dim]);
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::myvec::{MyVec, PseudoVec};
+use crate::simulator::binning::Binning;
+use crate::simulator::diffusion::Diffusion;
+use crate::simulator::extrema::Extrema;
+use crate::simulator::particle::{get_moment_of_inertia, Particles};
+use crate::simulator::{Domain, NDIMS};
+
+/// Macroscopic quantities evaluated at a synchronisation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Observables {
+    /// Time of the synchronisation.
+    pub time: f64,
+    /// Total translational kinetic energy.
+    pub kinetic_energy: f64,
+    /// Total rotational kinetic energy, which is only given by the rough collisions.
+    pub rotational_energy: f64,
+    /// Granular temperature, i.e. the mean kinetic energy per degree of freedom
+    ///   relative to the mean velocity.
+    pub temperature: f64,
+    /// Net momentum.
+    pub momentum: [f64; NDIMS],
+    /// Means of the scalars, one for each scalar transport model.
+    pub val_means: Vec<f64>,
+    /// Variances of the scalars, one for each scalar transport model.
+    pub val_variances: Vec<f64>,
+    /// Pressure tensor due to the particle motions relative to the mean velocity.
+    pub kinetic_pressure: [[f64; NDIMS]; NDIMS],
+    /// Pressure tensor due to the momentum exchanged by the collisions
+    ///   since the previous synchronisation.
+    pub collisional_pressure: [[f64; NDIMS]; NDIMS],
+}
+
+impl Observables {
+    /// Scalar pressure, i.e. the mean of the diagonal components of the total pressure tensor.
+    pub fn get_pressure(&self) -> f64 {
+        (0..NDIMS)
+            .map(|dim: usize| self.kinetic_pressure[dim][dim] + self.collisional_pressure[dim][dim])
+            .sum::<f64>()
+            / NDIMS as f64
+    }
+}
+
+/// Exchanges between a wall and the particles accumulated since the latest reset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WallFlux {
+    /// Number of reflections.
+    pub nreflections: usize,
+    /// Scalars given to the particles, one for each scalar transport model.
+    pub scalars: Vec<f64>,
+    /// Momentum given to the particles.
+    pub momentum: [f64; NDIMS],
+}
+
+/// Exchanges on a wall per unit area per unit time, averaged since the latest reset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WallStatistics {
+    /// Number of reflections.
+    pub nreflections: usize,
+    /// Scalar fluxes from the wall into the domain, one for each scalar transport model.
+    pub scalar_fluxes: Vec<f64>,
+    /// Wall-normal force exerted by the particles, which is positive when pushing the wall outward.
+    pub pressure: f64,
+    /// Tangential force exerted by the particles, whose wall-normal component is zero.
+    pub shear_stress: [f64; NDIMS],
+}
+
+/// Quantities accumulated over the events between two synchronisations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Accumulators {
+    /// Time of the latest synchronisation, from which the quantities are accumulated.
+    pub since: f64,
+    /// Sum of the outer products of the contact separations and the exchanged impulses.
+    pub virial: [[f64; NDIMS]; NDIMS],
+    /// Observables evaluated at the latest synchronisation.
+    pub latest: Option<Observables>,
+    /// Eulerian averages sampled at each synchronisation, if requested.
+    pub binning: Option<Binning>,
+    /// Mean-squared displacement and velocity autocorrelation sampled at each synchronisation,
+    ///   if requested.
+    pub diffusion: Option<Diffusion>,
+    /// Time from which the wall fluxes are accumulated.
+    pub walls_since: f64,
+    /// Exchanges on the negative and positive walls in each direction.
+    pub wall_fluxes: [Extrema<WallFlux>; NDIMS],
+}
+
+impl Accumulators {
+    /// Adds the momentum exchange of a collision,
+    ///   where `impulse` is given to the particle at `dpos` relative to the other one.
+    pub fn add_collision(&mut self, dpos: &MyVec, impulse: &MyVec) {
+        for (m, row) in self.virial.iter_mut().enumerate() {
+            for (n, item) in row.iter_mut().enumerate() {
+                *item += dpos[m] * impulse[n];
+            }
+        }
+    }
+
+    /// Adds the exchanges of a reflection on the wall normal to `dim`,
+    ///   where `sign` is the wall-normal unit vector pointing toward the domain.
+    pub fn add_reflection(&mut self, dim: usize, sign: f64, dvals: &[f64], impulse: &MyVec) {
+        let flux: &mut WallFlux = if 0. < sign {
+            &mut self.wall_fluxes[dim].min
+        } else {
+            &mut self.wall_fluxes[dim].max
+        };
+        if flux.scalars.len() < dvals.len() {
+            flux.scalars.resize(dvals.len(), 0.);
+        }
+        flux.nreflections += 1;
+        for (scalar, &dval) in flux.scalars.iter_mut().zip(dvals.iter()) {
+            *scalar += dval;
+        }
+        for d in 0..NDIMS {
+            flux.momentum[d] += impulse[d];
+        }
+    }
+
+    /// Restarts the accumulation of the wall fluxes.
+    pub fn reset_wall_fluxes(&mut self, time: f64) {
+        self.walls_since = time;
+        self.wall_fluxes = Default::default();
+    }
+
+    /// Averages the exchanges on the walls normal to `dim` over the time until `time`,
+    ///   which are not available for the periodic directions or before any time has passed.
+    pub fn get_wall_statistics(
+        &self,
+        domain: &Domain,
+        time: f64,
+        dim: usize,
+    ) -> Option<Extrema<WallStatistics>> {
+        let duration: f64 = time - self.walls_since;
+        if domain.periodicities[dim] || duration <= 0. {
+            return None;
+        }
+        let area: f64 = (0..NDIMS)
+            .filter(|&d: &usize| d != dim)
+            .map(|d: usize| domain.lengths[d])
+            .product();
+        let factor: f64 = 1. / (area * duration);
+        // the force on the wall is opposite to the momentum given to the particles
+        let average = |flux: &WallFlux, sign: f64| -> WallStatistics {
+            let mut shear_stress: [f64; NDIMS] =
+                flux.momentum.map(|momentum: f64| -momentum * factor);
+            shear_stress[dim] = 0.;
+            WallStatistics {
+                nreflections: flux.nreflections,
+                scalar_fluxes: flux.scalars.iter().map(|s: &f64| s * factor).collect(),
+                pressure: sign * flux.momentum[dim] * factor,
+                shear_stress,
+            }
+        };
+        Some(Extrema::<WallStatistics> {
+            min: average(&self.wall_fluxes[dim].min, 1.),
+            max: average(&self.wall_fluxes[dim].max, -1.),
+        })
+    }
+
+    /// Evaluates the observables of the synchronised particles,
+    ///   and restarts the accumulation.
+    pub fn observe(&mut self, domain: &Domain, time: f64, particles: &Particles) {
+        let volume: f64 = domain.lengths.iter().product();
+        let duration: f64 = time - self.since;
+        let mut observables: Observables = measure(domain, time, particles);
+        if 0. < duration {
+            for (m, row) in observables.collisional_pressure.iter_mut().enumerate() {
+                for (n, item) in row.iter_mut().enumerate() {
+                    *item = self.virial[m][n] / (volume * duration);
+                }
+            }
+        }
+        self.since = time;
+        self.virial = [[0.; NDIMS]; NDIMS];
+        self.latest = Some(observables);
+        if let Some(binning) = self.binning.as_mut() {
+            binning.sample(domain, particles);
+        }
+        if let Some(diffusion) = self.diffusion.as_mut() {
+            diffusion.sample(domain, time, particles);
+        }
+    }
+}
+
+/// Computes the observables which only depend on the current particle states.
+fn measure(domain: &Domain, time: f64, particles: &Particles) -> Observables {
+    let nscalars: usize = domain.scalar_transports.len();
+    let mut observables = Observables {
+        time,
+        val_means: vec![0.; nscalars],
+        val_variances: vec![0.; nscalars],
+        ..Default::default()
+    };
+    let nitems: usize = particles.len();
+    if 0 == nitems {
+        return observables;
+    }
+    let volume: f64 = domain.lengths.iter().product();
+    let mut mass: f64 = 0.;
+    let mut momentum = MyVec::new([0.; NDIMS]);
+    for p in 0..nitems {
+        let p_mass: f64 = particles.masses[p];
+        let p_vel: MyVec = particles.velocities[p];
+        let p_angvel: PseudoVec = particles.angvels[p];
+        mass += p_mass;
+        momentum = momentum + p_mass * p_vel;
+        observables.kinetic_energy += 0.5 * p_mass * (p_vel * p_vel);
+        observables.rotational_energy +=
+            0.5 * get_moment_of_inertia(particles.radii[p], p_mass) * (p_angvel * p_angvel);
+        for (mean, &val) in observables
+            .val_means
+            .iter_mut()
+            .zip(particles.vals[p].iter())
+        {
+            *mean += val;
+        }
+    }
+    for mean in observables.val_means.iter_mut() {
+        *mean /= nitems as f64;
+    }
+    // fluctuations relative to the mean values
+    let vel: MyVec = momentum / mass;
+    let mut energy: f64 = 0.;
+    for p in 0..nitems {
+        let p_mass: f64 = particles.masses[p];
+        let dvel: MyVec = particles.velocities[p] - vel;
+        energy += p_mass * (dvel * dvel);
+        for (m, row) in observables.kinetic_pressure.iter_mut().enumerate() {
+            for (n, item) in row.iter_mut().enumerate() {
+                *item += p_mass * dvel[m] * dvel[n] / volume;
+            }
+        }
+        for (variance, (&val, &mean)) in observables
+            .val_variances
+            .iter_mut()
+            .zip(particles.vals[p].iter().zip(observables.val_means.iter()))
+        {
+            *variance += (val - mean).powi(2);
+        }
+    }
+    for variance in observables.val_variances.iter_mut() {
+        *variance /= nitems as f64;
+    }
+    observables.temperature = energy / (NDIMS * nitems) as f64;
+    for dim in 0..NDIMS {
+        observables.momentum[dim] = momentum[dim];
+    }
+    observables
+}
+
+#[cfg(test)]
+mod test_observe {
+    use super::Accumulators;
+    use crate::myvec::{MyVec, PseudoVec};
+    use crate::simulator::particle::{FreeFlights, Images, Particle, Particles};
+    use crate::simulator::{Domain, SimulationConfig, NDIMS};
+
+    fn init_particle(vel: f64, val: f64) -> Particle {
+        let mut v = MyVec::new([0.; NDIMS]);
+        v[0] = vel;
+        Particle {
+            index: 0,
+            rad: 0.5,
+            mass: 2.,
+            pos: MyVec::new([1.; NDIMS]),
+            vel: v,
+            angvel: PseudoVec::default(),
+            time: 0.,
+            vals: vec![val],
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        }
+    }
+
+    #[test]
+    fn case1() {
+        // two particles moving in the opposite directions
+        let config = SimulationConfig::builder()
+            .lengths([8.; NDIMS])
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let volume: f64 = 8f64.powi(NDIMS as i32);
+        let particles: Particles = [init_particle(1., 0.), init_particle(-1., 1.)]
+            .into_iter()
+            .collect();
+        let mut accumulators = Accumulators::default();
+        let mut impulse = MyVec::new([0.; NDIMS]);
+        impulse[0] = 3.;
+        let mut dpos = MyVec::new([0.; NDIMS]);
+        dpos[0] = 1.;
+        accumulators.add_collision(&dpos, &impulse);
+        accumulators.observe(&domain, 2., &particles);
+        let observables = accumulators.latest.unwrap();
+        assert_eq!(observables.kinetic_energy, 2.);
+        assert_eq!(observables.temperature, 2. / NDIMS as f64);
+        assert_eq!(observables.momentum, [0.; NDIMS]);
+        assert_eq!(observables.val_means, vec![0.5]);
+        assert_eq!(observables.val_variances, vec![0.25]);
+        assert_eq!(observables.kinetic_pressure[0][0], 4. / volume);
+        assert_eq!(observables.kinetic_pressure[1][1], 0.);
+        assert_eq!(observables.collisional_pressure[0][0], 1.5 / volume);
+        assert_eq!(observables.collisional_pressure[0][1], 0.);
+        // the accumulation is restarted
+        assert_eq!(accumulators.since, 2.);
+        assert_eq!(accumulators.virial, [[0.; NDIMS]; NDIMS]);
+    }
+
+    #[test]
+    fn case2() {
+        // reflections on the floor and the ceiling, sliding in the first direction
+        let config = SimulationConfig::builder()
+            .lengths([8.; NDIMS])
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let area: f64 = 8f64.powi(NDIMS as i32 - 1);
+        let mut accumulators = Accumulators::default();
+        let mut impulse = MyVec::new([0.; NDIMS]);
+        impulse[0] = 1.;
+        impulse[1] = 4.;
+        accumulators.add_reflection(1, 1., &[-0.5], &impulse);
+        impulse[1] = -2.;
+        accumulators.add_reflection(1, -1., &[0.25], &impulse);
+        accumulators.add_reflection(1, -1., &[0.25], &impulse);
+        let statistics = accumulators.get_wall_statistics(&domain, 2., 1).unwrap();
+        assert_eq!(statistics.min.nreflections, 1);
+        assert_eq!(statistics.max.nreflections, 2);
+        assert_eq!(statistics.min.scalar_fluxes, vec![-0.25 / area]);
+        assert_eq!(statistics.max.scalar_fluxes, vec![0.25 / area]);
+        assert_eq!(statistics.min.pressure, 2. / area);
+        assert_eq!(statistics.max.pressure, 2. / area);
+        assert_eq!(statistics.min.shear_stress[0], -0.5 / area);
+        assert_eq!(statistics.min.shear_stress[1], 0.);
+        assert_eq!(statistics.max.shear_stress[0], -1. / area);
+        // periodic directions have no walls
+        assert_eq!(accumulators.get_wall_statistics(&domain, 2., 0), None);
+        // the averages restart
+        accumulators.reset_wall_fluxes(2.);
+        assert_eq!(accumulators.get_wall_statistics(&domain, 2., 1), None);
+        let statistics = accumulators.get_wall_statistics(&domain, 3., 1).unwrap();
+        assert_eq!(statistics.min.nreflections, 0);
+    }
+}
@@ -1,67 +1,165 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
-use crate::myvec::MyVec;
+use crate::myvec::{MyVec, PseudoVec};
 use crate::random::Random;
 use crate::simulator::cell::Cell;
+use crate::simulator::config::SimulationConfig;
 use crate::simulator::extrema::Extrema;
+use crate::simulator::generator::{self, Placement, Stratification, VelocityDistribution};
+use crate::simulator::initial_condition::{InitialCondition, InitialConditionError};
+use crate::simulator::lees_edwards::{self, FLOW_DIM, GRADIENT_DIM};
+use crate::simulator::size_distribution::SizeDistribution;
 use crate::simulator::util::vec_to_array;
 use crate::simulator::{Domain, NDIMS};
 
-/// Particle radius, to be consistent with the cell size.
-pub const RADIUS: f64 = 0.5f64;
-
+/// State of a single particle, which is stored in [`Particles`] field by field.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Particle {
     pub index: usize,
     pub rad: f64,
+    pub mass: f64,
     pub pos: MyVec,
     pub vel: MyVec,
+    /// Angular velocity, which is only altered by the rough collisions.
+    pub angvel: PseudoVec,
+    pub time: f64,
+    /// Scalars carried by the particle, whose transport models are given by the configuration.
+    pub vals: Vec<f64>,
+    /// Collisions with the other particles experienced so far.
+    pub flights: FreeFlights,
+    /// Periodic images visited so far.
+    pub images: Images,
+}
+
+/// All particles in the struct-of-arrays form,
+///   where a particle is referred to by its slot, i.e. the position in the arrays.
+///
+/// N.B.: A removed particle is replaced by the last one, and thus the slots are not persistent;
+///   `indices` identify the particles throughout the run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Particles {
+    pub indices: Vec<usize>,
+    pub radii: Vec<f64>,
+    pub masses: Vec<f64>,
+    pub positions: Vec<MyVec>,
+    pub velocities: Vec<MyVec>,
+    pub angvels: Vec<PseudoVec>,
+    /// Local times, at which the positions and the velocities are given.
+    pub times: Vec<f64>,
+    /// Indices of the cells to which each particle belongs.
+    pub cells: Vec<Vec<usize>>,
+    /// Incremented whenever the trajectory of a particle changes,
+    ///   which invalidates the events predicted with the previous value.
+    pub counters: Vec<u64>,
+    /// Epochs of the memberships, in the same order as `cells`,
+    ///   which invalidate the events predicted in a cell once the particle leaves it.
+    pub epochs: Vec<Vec<u64>>,
+    /// Number of the epochs issued so far, which makes each membership unique.
+    pub nepochs: u64,
+    pub vals: Vec<Vec<f64>>,
+    pub flights: Vec<FreeFlights>,
+    pub images: Vec<Images>,
+}
+
+/// Trajectory and cell membership of a particle at which an event is predicted,
+///   whose event is valid as long as both are unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stamp {
+    pub counter: u64,
+    pub epoch: u64,
+}
+
+/// Periodic image in which a particle is located, from which the unwrapped position is recovered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Images {
+    /// Net number of crossings of the positive face in each periodic direction.
+    pub counts: [i64; NDIMS],
+}
+
+/// Collision history of a particle, from which the mean free time and path are measured.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FreeFlights {
+    /// Number of collisions with the other particles.
+    pub ncollisions: usize,
+    /// Time just after the latest collision.
+    pub last_time: f64,
+    /// Speed just after the latest collision.
+    pub last_speed: f64,
+    /// Total duration of the flights between two successive collisions.
     pub time: f64,
-    pub cells: Vec<Rc<RefCell<Cell>>>,
-    pub val: f64,
+    /// Total length of the flights between two successive collisions.
+    pub path: f64,
+}
+
+impl FreeFlights {
+    /// Number of the flights between two successive collisions,
+    ///   excluding the one from the initial position.
+    pub fn nflights(&self) -> usize {
+        self.ncollisions.saturating_sub(1)
+    }
+
+    /// Records a collision at the given time with the speeds just before and after it.
+    ///
+    /// N.B.: The speed is interpolated linearly between both ends of the flight,
+    ///   which is exact unless the particle is accelerated or hits diffuse walls.
+    pub fn collide(&mut self, time: f64, speed_bef: f64, speed_aft: f64) {
+        if 0 < self.ncollisions {
+            let dt: f64 = time - self.last_time;
+            self.time += dt;
+            self.path += 0.5 * (self.last_speed + speed_bef) * dt;
+        }
+        self.ncollisions += 1;
+        self.last_time = time;
+        self.last_speed = speed_aft;
+    }
 }
 
 #[allow(dead_code)]
-fn check_duplication(p: &Particle, cells: &[Rc<RefCell<Cell>>]) {
+fn check_duplication(p: usize, cells: &[usize]) {
     // check no duplication
     for (n, c0) in cells.iter().enumerate() {
-        for c1 in cells[n + 1..].iter() {
-            if Rc::ptr_eq(c0, c1) {
-                panic!(
-                    "cell {} is duplicated for particle {} at ({}, {})",
-                    c0.borrow().index,
-                    p.index,
-                    p.pos[0],
-                    p.pos[1]
-                );
-            }
+        if cells[n + 1..].contains(c0) {
+            panic!("cell {} is duplicated for particle {}", c0, p);
         }
     }
 }
 
-impl Particle {
-    pub fn append(&mut self, cell: &Rc<RefCell<Cell>>) {
-        self.cells.push(cell.clone());
-        if cfg!(debug_assertions) {
-            check_duplication(self, &self.cells);
-        }
+/// Computes the volume of a particle, which is used as its mass.
+pub fn get_volume(rad: f64) -> f64 {
+    const PI: f64 = std::f64::consts::PI;
+    if 2 == NDIMS {
+        PI * rad.powi(2)
+    } else {
+        4. / 3. * PI * rad.powi(3)
     }
+}
 
-    pub fn remove(&mut self, cell: &Rc<RefCell<Cell>>) {
-        let cells: &mut Vec<Rc<RefCell<Cell>>> = &mut self.cells;
-        let position: usize = cells.iter().position(|c| Rc::ptr_eq(c, cell)).unwrap();
-        cells.remove(position);
-        if cfg!(debug_assertions) {
-            check_duplication(self, &self.cells);
-        }
+/// Computes the moment of inertia of a uniform disk (sphere) in two (three) dimensions.
+pub fn get_moment_of_inertia(rad: f64, mass: f64) -> f64 {
+    if 2 == NDIMS {
+        0.5 * mass * rad.powi(2)
+    } else {
+        0.4 * mass * rad.powi(2)
     }
+}
 
-    pub fn get_new_pos(domain: &Domain, pos: MyVec, vel: MyVec, dt: f64) -> MyVec {
+impl Particle {
+    /// Advances the position along the parabolic trajectory,
+    ///   which is wrapped in the periodic directions.
+    ///
+    /// N.B.: Under the Lees-Edwards boundaries, the position in the gradient direction is
+    ///   not wrapped here, since the velocity should also be altered on crossing.
+    /// N.B.: Particles may be slightly outside the domain next to oscillating walls.
+    pub fn get_new_pos(domain: &Domain, pos: MyVec, vel: MyVec, acc: MyVec, dt: f64) -> MyVec {
         let lengths: &[f64; NDIMS] = &domain.lengths;
-        // x^{n+1} = x^n + v * dt
-        let mut new_pos: MyVec = pos + vel * dt;
+        // x^{n+1} = x^n + v * dt + a * dt^2 / 2
+        let mut new_pos: MyVec = pos + vel * dt + (0.5 * dt * dt) * acc;
         // correct periodicity
         for dim in 0..NDIMS {
+            if !domain.periodicities[dim] {
+                continue;
+            }
+            if domain.shear_rate.is_some() && GRADIENT_DIM == dim {
+                continue;
+            }
             if new_pos[dim] < 0. {
                 new_pos[dim] += lengths[dim];
             } else if lengths[dim] < new_pos[dim] {
@@ -70,6 +168,200 @@ impl Particle {
         }
         new_pos
     }
+
+    /// Advances the velocity under the constant acceleration.
+    pub fn get_new_vel(vel: MyVec, acc: MyVec, dt: f64) -> MyVec {
+        vel + acc * dt
+    }
+}
+
+impl FromIterator<Particle> for Particles {
+    /// Collects the particles belonging to no cell.
+    fn from_iter<I: IntoIterator<Item = Particle>>(iter: I) -> Self {
+        let mut particles = Particles::default();
+        for particle in iter {
+            particles.push(particle);
+        }
+        particles
+    }
+}
+
+impl Particles {
+    /// Number of the particles.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Appends a particle belonging to no cell, and returns its slot.
+    pub fn push(&mut self, particle: Particle) -> usize {
+        self.indices.push(particle.index);
+        self.radii.push(particle.rad);
+        self.masses.push(particle.mass);
+        self.positions.push(particle.pos);
+        self.velocities.push(particle.vel);
+        self.angvels.push(particle.angvel);
+        self.times.push(particle.time);
+        self.cells.push(Vec::new());
+        self.counters.push(0);
+        self.epochs.push(Vec::new());
+        self.vals.push(particle.vals);
+        self.flights.push(particle.flights);
+        self.images.push(particle.images);
+        self.len() - 1
+    }
+
+    /// Returns a copy of the particle in the given slot.
+    pub fn get(&self, p: usize) -> Particle {
+        Particle {
+            index: self.indices[p],
+            rad: self.radii[p],
+            mass: self.masses[p],
+            pos: self.positions[p],
+            vel: self.velocities[p],
+            angvel: self.angvels[p],
+            time: self.times[p],
+            vals: self.vals[p].clone(),
+            flights: self.flights[p],
+            images: self.images[p],
+        }
+    }
+
+    /// Removes the particle in the given slot, which is filled by the last particle.
+    ///
+    /// N.B.: The caller is responsible for redirecting the references to the moved particle.
+    pub fn swap_remove(&mut self, p: usize) -> Particle {
+        self.cells.swap_remove(p);
+        self.counters.swap_remove(p);
+        self.epochs.swap_remove(p);
+        Particle {
+            index: self.indices.swap_remove(p),
+            rad: self.radii.swap_remove(p),
+            mass: self.masses.swap_remove(p),
+            pos: self.positions.swap_remove(p),
+            vel: self.velocities.swap_remove(p),
+            angvel: self.angvels.swap_remove(p),
+            time: self.times.swap_remove(p),
+            vals: self.vals.swap_remove(p),
+            flights: self.flights.swap_remove(p),
+            images: self.images.swap_remove(p),
+        }
+    }
+
+    /// Invalidates all pending events of the particle.
+    pub fn invalidate(&mut self, p: usize) {
+        self.counters[p] += 1;
+    }
+
+    /// Returns the stamp of the particle to predict an event in the given cell.
+    pub fn get_stamp(&self, p: usize, cell_index: usize) -> Stamp {
+        let position: usize = self.cells[p].iter().position(|&c| c == cell_index).unwrap();
+        Stamp {
+            counter: self.counters[p],
+            epoch: self.epochs[p][position],
+        }
+    }
+
+    /// Checks if an event predicted for the particle with the given stamp is still valid.
+    ///
+    /// N.B.: The slot may be taken over by another particle or even vacant,
+    ///   whose memberships have different epochs anyway.
+    pub fn is_current(&self, p: usize, stamp: Stamp) -> bool {
+        p < self.len() && stamp.counter == self.counters[p] && self.epochs[p].contains(&stamp.epoch)
+    }
+
+    pub fn append_cell(&mut self, p: usize, cell_index: usize) {
+        self.cells[p].push(cell_index);
+        self.epochs[p].push(self.nepochs);
+        self.nepochs += 1;
+        if cfg!(debug_assertions) {
+            check_duplication(p, &self.cells[p]);
+        }
+    }
+
+    pub fn remove_cell(&mut self, p: usize, cell_index: usize) {
+        let cells: &mut Vec<usize> = &mut self.cells[p];
+        let position: usize = cells.iter().position(|&c| c == cell_index).unwrap();
+        cells.remove(position);
+        self.epochs[p].remove(position);
+    }
+
+    /// Updates the position, counting the crossings of the periodic faces.
+    ///
+    /// N.B.: The displacement since the previous update should be shorter than
+    ///   half of the domain, which is guaranteed by the cell transfers.
+    /// N.B.: The Lees-Edwards shifts are counted by [`Particles::shift_image`].
+    pub fn set_pos(&mut self, domain: &Domain, p: usize, pos: MyVec) {
+        for dim in 0..NDIMS {
+            if !domain.periodicities[dim] {
+                continue;
+            }
+            if domain.shear_rate.is_some() && GRADIENT_DIM == dim {
+                continue;
+            }
+            let half: f64 = 0.5 * domain.lengths[dim];
+            let dpos: f64 = pos[dim] - self.positions[p][dim];
+            if dpos < -half {
+                self.images[p].counts[dim] += 1;
+            } else if half < dpos {
+                self.images[p].counts[dim] -= 1;
+            }
+        }
+        self.positions[p] = pos;
+    }
+
+    /// Moves the particle at `time` to the image on the other side of the sliding faces,
+    ///   where `sign` is 1 (-1) when it comes back from the negative (positive) face.
+    ///
+    /// The wrapping of the displaced position in the flow direction is also counted.
+    pub fn shift_image(&mut self, domain: &Domain, p: usize, time: f64, pos: MyVec, sign: f64) {
+        let length: f64 = domain.lengths[FLOW_DIM];
+        let displacement: f64 = sign * lees_edwards::get_velocity(domain) * time;
+        let nwraps: f64 = (self.positions[p][FLOW_DIM] + displacement - pos[FLOW_DIM]) / length;
+        let images: &mut Images = &mut self.images[p];
+        images.counts[FLOW_DIM] += nwraps.round() as i64;
+        images.counts[GRADIENT_DIM] -= sign as i64;
+        self.positions[p] = pos;
+    }
+
+    /// Position in the unbounded space obtained by undoing the periodic wrapping,
+    ///   which is continuous along the trajectory.
+    ///
+    /// N.B.: Under the Lees-Edwards boundaries, the images above and below are displaced
+    ///   in the flow direction as time goes on.
+    pub fn get_unwrapped_pos(&self, domain: &Domain, p: usize) -> MyVec {
+        let counts: &[i64; NDIMS] = &self.images[p].counts;
+        let mut pos: MyVec = self.positions[p];
+        for dim in 0..NDIMS {
+            pos[dim] += counts[dim] as f64 * domain.lengths[dim];
+        }
+        let displacement: f64 = lees_edwards::get_velocity(domain) * self.times[p];
+        pos[FLOW_DIM] += counts[GRADIENT_DIM] as f64 * displacement;
+        pos
+    }
+
+    /// Acceleration due to the gravity and the buoyancy, which is constant between events.
+    pub fn get_acceleration(&self, domain: &Domain, p: usize) -> MyVec {
+        match domain.buoyancy {
+            Some(buoyancy) => domain.gravity + buoyancy.get_acceleration(self.vals[p][0]),
+            None => domain.gravity,
+        }
+    }
+
+    /// Brings the particle to the given time,
+    ///   which is valid as long as no event happens in between.
+    pub fn advance(&mut self, domain: &Domain, p: usize, time: f64) {
+        let dt: f64 = time - self.times[p];
+        let acc: MyVec = self.get_acceleration(domain, p);
+        let pos: MyVec =
+            Particle::get_new_pos(domain, self.positions[p], self.velocities[p], acc, dt);
+        self.set_pos(domain, p, pos);
+        self.velocities[p] = Particle::get_new_vel(self.velocities[p], acc, dt);
+        self.times[p] = time;
+    }
 }
 
 fn from_p_to_c(
@@ -111,17 +403,25 @@ fn from_p_to_c(
             kernel(length, n, min, max, index);
         }
     }
-    // TODO: generalise
-    let mut cell_indices = Vec::<usize>::new();
-    for index1 in indices[1].iter() {
-        for index0 in indices[0].iter() {
-            cell_indices.push(index0 + ncells[0] * index1);
+    // take the Cartesian product of the indices in all directions,
+    //   where the first direction varies the fastest
+    let mut cell_indices: Vec<usize> = vec![0];
+    let mut stride: usize = 1;
+    for dim in 0..NDIMS {
+        let mut new_cell_indices =
+            Vec::<usize>::with_capacity(cell_indices.len() * indices[dim].len());
+        for &index in indices[dim].iter() {
+            for &cell_index in cell_indices.iter() {
+                new_cell_indices.push(cell_index + stride * index);
+            }
         }
+        cell_indices = new_cell_indices;
+        stride *= ncells[dim];
     }
     cell_indices
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "3d")))]
 mod test_from_p_to_c {
     use super::from_p_to_c as func;
     use crate::myvec::MyVec;
@@ -192,179 +492,366 @@ mod test_from_p_to_c {
     }
 }
 
-/// Finds minimum distance between two points, taking periodicity into account.
-pub fn find_minimum_distance(domain: &Domain, pos0: MyVec, pos1: MyVec) -> f64 {
+#[cfg(all(test, feature = "3d"))]
+mod test_from_p_to_c_3d {
+    use super::from_p_to_c as func;
+    use crate::myvec::MyVec;
+    use crate::simulator::NDIMS;
+    const LENGTHS: [f64; NDIMS] = [8., 8., 8.];
+    const NCELLS: [usize; NDIMS] = [4, 4, 4];
+    #[test]
+    fn case1() {
+        let rad: f64 = 0.5;
+        let pos: MyVec = MyVec::new([4., 3., 5.]);
+        assert_eq!(
+            func(&LENGTHS, &NCELLS, rad, &pos),
+            vec![1 + 4 + 16 * 2, 2 + 4 + 16 * 2]
+        );
+    }
+    #[test]
+    fn case2() {
+        // close to the corner, touching eight cells
+        let rad: f64 = 0.5;
+        let pos: MyVec = MyVec::new([0., 0., 0.]);
+        assert_eq!(
+            func(&LENGTHS, &NCELLS, rad, &pos),
+            vec![0, 3, 12, 15, 48, 51, 60, 63]
+        );
+    }
+    #[test]
+    fn case3() {
+        let rad: f64 = 0.49 * LENGTHS[0];
+        let pos: MyVec = MyVec::new([0.5 * LENGTHS[0], 0.5 * LENGTHS[1], 0.5 * LENGTHS[2]]);
+        assert_eq!(
+            func(&LENGTHS, &NCELLS, rad, &pos).len(),
+            NCELLS[0] * NCELLS[1] * NCELLS[2]
+        );
+    }
+}
+
+/// Finds minimum distance between two points at the given time, taking periodicity into account.
+pub fn find_minimum_distance(domain: &Domain, time: f64, pos0: MyVec, pos1: MyVec) -> f64 {
     let lengths: &[f64; NDIMS] = &domain.lengths;
-    let dpos: MyVec = pos1 - pos0;
-    let mut dist: f64 = 0.;
-    for dim in 0..NDIMS {
-        let d: f64 = dpos[dim].abs();
-        let d: f64 = d.min((dpos[dim] - lengths[dim]).abs());
-        let d: f64 = d.min((dpos[dim] + lengths[dim]).abs());
-        dist += d.powi(2);
+    // images above and below the domain are displaced under the Lees-Edwards boundaries
+    let signs: &[f64] = if domain.shear_rate.is_some() {
+        &[-1., 0., 1.]
+    } else {
+        &[0.]
+    };
+    let offset: f64 = lees_edwards::get_offset(domain, time);
+    let mut min: f64 = f64::MAX;
+    for &sign in signs.iter() {
+        let mut dpos: MyVec = pos1 - pos0;
+        if 0. != sign {
+            dpos[GRADIENT_DIM] += sign * lengths[GRADIENT_DIM];
+            dpos[FLOW_DIM] += sign * offset;
+        }
+        let mut dist: f64 = 0.;
+        for dim in 0..NDIMS {
+            let d: f64 = dpos[dim].abs();
+            // images are only considered in periodic directions,
+            //   while the sliding ones are already taken into account
+            let is_sliding: bool = domain.shear_rate.is_some() && GRADIENT_DIM == dim;
+            let d: f64 = if domain.periodicities[dim] && !is_sliding {
+                let d: f64 = d.min((dpos[dim] - lengths[dim]).abs());
+                d.min((dpos[dim] + lengths[dim]).abs())
+            } else {
+                d
+            };
+            dist += d.powi(2);
+        }
+        min = min.min(dist);
     }
-    dist.sqrt()
+    min.sqrt()
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "3d")))]
 mod test_find_minimum_distance {
     use super::find_minimum_distance;
     use crate::myvec::MyVec;
-    use crate::simulator::{Domain, NDIMS};
-    const PERIODICITIES: [bool; NDIMS] = [true, true];
+    use crate::simulator::{Domain, SimulationConfig};
 
     #[test]
     fn case1() {
         // normal case, 3:4:5
-        let domain = Domain {
-            lengths: [32., 32.],
-            periodicities: PERIODICITIES,
-        };
+        let domain: Domain = Domain::periodic([32., 32.], None);
         let pos0: MyVec = MyVec::new([1., 2.]);
         let pos1: MyVec = MyVec::new([4., 6.]);
-        assert_eq!(find_minimum_distance(&domain, pos0, pos1), 5.);
+        assert_eq!(find_minimum_distance(&domain, 0., pos0, pos1), 5.);
     }
 
     #[test]
     fn case2() {
         // case with periodicity
-        let domain = Domain {
-            lengths: [32., 32.],
-            periodicities: PERIODICITIES,
-        };
+        let domain: Domain = Domain::periodic([32., 32.], None);
         let pos0: MyVec = MyVec::new([0., 0.]);
         let pos1: MyVec = MyVec::new([0., domain.lengths[1]]);
-        assert_eq!(find_minimum_distance(&domain, pos0, pos1), 0.);
+        assert_eq!(find_minimum_distance(&domain, 0., pos0, pos1), 0.);
     }
 
     #[test]
     fn case3() {
         // case with periodicity
-        let domain = Domain {
-            lengths: [32., 32.],
-            periodicities: PERIODICITIES,
-        };
+        let domain: Domain = Domain::periodic([32., 32.], None);
         let pos0: MyVec = MyVec::new([0., 0.]);
         let pos1: MyVec = MyVec::new([domain.lengths[0], domain.lengths[1]]);
-        assert_eq!(find_minimum_distance(&domain, pos0, pos1), 0.);
+        assert_eq!(find_minimum_distance(&domain, 0., pos0, pos1), 0.);
+    }
+
+    #[test]
+    fn case4() {
+        // images are not considered in the wall-bounded direction
+        let config: SimulationConfig = SimulationConfig::builder()
+            .lengths([32., 32.])
+            .build()
+            .unwrap();
+        let domain = Domain::new(&config);
+        let pos0: MyVec = MyVec::new([0., 0.]);
+        let pos1: MyVec = MyVec::new([0., domain.lengths[1]]);
+        assert_eq!(
+            find_minimum_distance(&domain, 0., pos0, pos1),
+            domain.lengths[1]
+        );
     }
+
+    #[test]
+    fn case5() {
+        // the image above the domain slides under the Lees-Edwards boundaries
+        let domain = Domain::periodic([32., 32.], Some(0.125));
+        let pos0: MyVec = MyVec::new([29., 0.]);
+        let pos1: MyVec = MyVec::new([1., domain.lengths[1]]);
+        assert_eq!(find_minimum_distance(&domain, 0., pos0, pos1), 4.);
+        // the offset is 4 at time 1
+        assert_eq!(find_minimum_distance(&domain, 1., pos0, pos1), 0.);
+    }
+}
+
+/// Finds the index of a particle overlapping with the given one,
+///   among the particles registered to the given cells.
+fn find_overlap(
+    domain: &Domain,
+    particles: &Particles,
+    cells: &[Cell],
+    cell_indices: &[usize],
+    time: f64,
+    rad: f64,
+    pos: MyVec,
+) -> Option<usize> {
+    for &index in cell_indices.iter() {
+        // check overlap for all particles which share the same cell
+        for &q in cells[index].particles.iter() {
+            let dist: f64 = find_minimum_distance(domain, time, pos, particles.positions[q]);
+            if dist < particles.radii[q] + rad {
+                return Some(particles.indices[q]);
+            }
+        }
+    }
+    None
 }
 
+/// Registers a new particle to the given cells, and vice versa.
+fn register(
+    particles: &mut Particles,
+    cells: &mut [Cell],
+    cell_indices: &[usize],
+    particle: Particle,
+) {
+    let p: usize = particles.push(particle);
+    // add particle to the local list for each cell
+    for &cell_index in cell_indices.iter() {
+        particles.append_cell(p, cell_index);
+        cells[cell_index].append(p);
+    }
+}
+
+/// Places `nparticles` following the generators given by the configuration.
+///
+/// N.B.: It fails when some of them cannot be placed,
+///   instead of starting with fewer particles than requested.
 pub fn init_particles(
     domain: &Domain,
     ncells: &[usize; NDIMS],
-    cells: &[Rc<RefCell<Cell>>],
-    mut nitems: usize,
+    cells: &mut [Cell],
+    config: &SimulationConfig,
     time: f64,
-    seed: f64,
-) -> Vec<Rc<RefCell<Particle>>> {
+    rng: &mut Random,
+) -> Result<Particles, InitialConditionError> {
     let lengths: &[f64; NDIMS] = &domain.lengths;
     let periodicities: &[bool; NDIMS] = &domain.periodicities;
-    let rad: f64 = RADIUS;
-    nitems = {
-        let max_vfrac: f64 = 0.4;
-        let max_nitems: f64 =
-            (lengths[0] * lengths[1] * max_vfrac) / (std::f64::consts::PI * rad.powi(2));
-        let max_nitems: usize = max_nitems as usize;
-        max_nitems.min(nitems)
-    };
+    let size_distribution: SizeDistribution = config.size_distribution();
     // request the cell sizes are larger than twice the particle diameters
+    let max_rad: f64 = size_distribution.max_radius();
     for cell in cells.iter() {
         for dim in 0..NDIMS {
-            let bounds: &Extrema<f64> = &cell.borrow().bounds[dim];
+            let bounds: &Extrema<f64> = &cell.bounds[dim];
             let d: f64 = bounds.max - bounds.min;
-            if d <= 4. * rad {
+            if d <= 4. * max_rad {
                 panic!(
                     "cell size {:+.2e} should be larger than the particle diameter {:+.2e}",
                     d,
-                    2. * rad
+                    2. * max_rad
                 );
             }
         }
     }
-    let mut rng = Random::new((seed * f64::MAX) as u64);
-    let mut particles = Vec::<Rc<RefCell<Particle>>>::new();
-    for index in 0..nitems {
-        // find a proper position for a particle without overlapping
-        //   with the other particles already defined
-        let (pos, cell_indices): (MyVec, Vec<usize>) = 'find_no_overlap: loop {
-            // choose position randomly
-            let pos0: MyVec = {
-                let pos: Vec<f64> = (0..NDIMS)
-                    .map(|dim: usize| {
-                        let min: f64 = if periodicities[dim] { 0. } else { rad };
-                        let max: f64 = if periodicities[dim] {
-                            lengths[dim]
-                        } else {
-                            lengths[dim] - rad
-                        };
-                        rng.gen_range(min, max)
-                    })
-                    .collect::<Vec<f64>>();
-                MyVec::new(vec_to_array::<f64>(pos))
+    let placement: Placement = config.placement();
+    let velocity_distribution: VelocityDistribution = config.velocity_distribution();
+    let stratification: Stratification = config.stratification();
+    // the generators other than the random sequential addition decide all positions in advance
+    let (radii, positions): (Vec<f64>, Vec<MyVec>) = match placement {
+        Placement::RandomSequential => (Vec::new(), Vec::new()),
+        Placement::SquareLattice | Placement::HexagonalLattice | Placement::Compression => {
+            let radii: Vec<f64> = (0..config.nparticles())
+                .map(|_| size_distribution.sample(rng))
+                .collect();
+            let positions: Vec<MyVec> = if let Placement::Compression = placement {
+                generator::place_by_compression(lengths, periodicities, &radii, rng)
+            } else {
+                generator::place_on_lattice(
+                    lengths,
+                    periodicities,
+                    max_rad,
+                    &placement,
+                    radii.len(),
+                    rng,
+                )
             };
-            // get all cells to which this particle will belong
-            let cell_indices: Vec<usize> = from_p_to_c(lengths, ncells, rad, &pos0);
-            for &index in cell_indices.iter() {
-                // check overlap for all particles which share the same cell
-                let cell: Ref<Cell> = cells[index].borrow();
-                let ps: Ref<Vec<Rc<RefCell<Particle>>>> = cell.particles.borrow();
-                for p in ps.iter() {
-                    let p: Ref<Particle> = p.borrow();
-                    let pos1: MyVec = p.pos;
-                    let dist: f64 = find_minimum_distance(domain, pos0, pos1);
-                    if dist < 2. * rad {
+            (radii, positions)
+        }
+    };
+    // the total volume of particles is clamped for the random sequential addition,
+    //   since the random placement hardly finds a room otherwise
+    let max_volume: f64 = lengths.iter().product::<f64>() * config.max_volume_fraction();
+    let mut volume: f64 = 0.;
+    let mut particles = Particles::default();
+    for index in 0..config.nparticles() {
+        let (rad, pos, cell_indices): (f64, MyVec, Vec<usize>) =
+            if let Placement::RandomSequential = placement {
+                let rad: f64 = size_distribution.sample(rng);
+                volume += get_volume(rad);
+                if max_volume < volume {
+                    return Err(InitialConditionError::Shortfall(
+                        config.nparticles(),
+                        particles.len(),
+                    ));
+                }
+                // find a proper position for a particle without overlapping
+                //   with the other particles already defined
+                'find_no_overlap: loop {
+                    // choose position randomly
+                    let pos0: MyVec = {
+                        let pos: Vec<f64> = (0..NDIMS)
+                            .map(|dim: usize| {
+                                let min: f64 = if periodicities[dim] { 0. } else { rad };
+                                let max: f64 = if periodicities[dim] {
+                                    lengths[dim]
+                                } else {
+                                    lengths[dim] - rad
+                                };
+                                rng.gen_range(min, max)
+                            })
+                            .collect::<Vec<f64>>();
+                        MyVec::new(vec_to_array::<f64>(pos))
+                    };
+                    // get all cells to which this particle will belong
+                    let cell_indices: Vec<usize> = from_p_to_c(lengths, ncells, rad, &pos0);
+                    if find_overlap(domain, &particles, cells, &cell_indices, time, rad, pos0)
+                        .is_some()
+                    {
                         continue 'find_no_overlap;
                     }
+                    break (rad, pos0, cell_indices);
                 }
-            }
-            break (pos0, cell_indices);
-        };
-        let vel = MyVec::new(vec_to_array::<f64>(
-            (0..NDIMS).map(|_| rng.gen_range(-1., 1.)).collect(),
-        ));
-        let val: f64 = if pos[0] / lengths[0] < pos[1] / lengths[1] {
-            1.
-        } else {
-            0.
-        };
-        let particle = Rc::new(RefCell::new(Particle {
-            index,
+            } else {
+                let rad: f64 = radii[index];
+                let pos: MyVec = positions[index];
+                let cell_indices: Vec<usize> = from_p_to_c(lengths, ncells, rad, &pos);
+                // particles still overlapping after the compression cannot be placed
+                if find_overlap(domain, &particles, cells, &cell_indices, time, rad, pos).is_some()
+                {
+                    return Err(InitialConditionError::Shortfall(
+                        config.nparticles(),
+                        particles.len(),
+                    ));
+                }
+                (rad, pos, cell_indices)
+            };
+        let mass: f64 = get_volume(rad);
+        let vel: MyVec = velocity_distribution.sample(mass, rng);
+        let val: f64 = stratification.get_value(lengths, &pos);
+        let particle = Particle {
+            index: particles.len(),
             rad,
+            mass,
             pos,
             vel,
+            angvel: PseudoVec::default(),
             time,
-            cells: {
-                let mut cs = Vec::<Rc<RefCell<Cell>>>::new();
-                for &cell_index in cell_indices.iter() {
-                    let cell: Rc<RefCell<Cell>> = cells[cell_index].clone();
-                    cs.push(cell);
-                }
-                cs
-            },
-            val,
-        }));
-        // add particle to the local list for each cell
-        for &cell_index in cell_indices.iter() {
-            let cell: Ref<Cell> = cells[cell_index].borrow();
-            let mut ps: RefMut<Vec<Rc<RefCell<Particle>>>> = cell.particles.borrow_mut();
-            ps.push(particle.clone());
-        }
-        // append to the main vector including all particles
-        particles.push(particle);
+            // all scalars share the same initial distribution
+            vals: vec![val; config.scalar_transports().len()],
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        };
+        // append to the arena including all particles
+        register(&mut particles, cells, &cell_indices, particle);
     }
     // enforce zero net momentum
     {
         let mut mean = MyVec::new([0.; NDIMS]);
-        for p in particles.iter() {
-            let p: Ref<Particle> = p.borrow();
-            mean = mean + p.vel;
+        let mut mass: f64 = 0.;
+        for (&m, &vel) in particles.masses.iter().zip(particles.velocities.iter()) {
+            mean = mean + m * vel;
+            mass += m;
+        }
+        mean = mean / mass;
+        for vel in particles.velocities.iter_mut() {
+            *vel = *vel - mean;
         }
-        mean = mean / particles.len() as f64;
-        for p in particles.iter_mut() {
-            let mut p: RefMut<Particle> = p.borrow_mut();
-            p.vel = p.vel - mean;
+    }
+    // give the linear velocity profile of the simple shear flow
+    if let Some(shear_rate) = domain.shear_rate {
+        for (vel, pos) in particles
+            .velocities
+            .iter_mut()
+            .zip(particles.positions.iter())
+        {
+            vel[FLOW_DIM] += shear_rate * (pos[GRADIENT_DIM] - 0.5 * lengths[GRADIENT_DIM]);
         }
     }
-    particles
+    Ok(particles)
+}
+
+/// Places the particles given explicitly, which are checked in advance.
+pub fn init_particles_from(
+    domain: &Domain,
+    ncells: &[usize; NDIMS],
+    cells: &mut [Cell],
+    initial_condition: &InitialCondition,
+    time: f64,
+) -> Result<Particles, InitialConditionError> {
+    initial_condition.check(domain)?;
+    let mut particles = Particles::default();
+    for index in 0..initial_condition.len() {
+        let rad: f64 = initial_condition.radii[index];
+        let pos = MyVec::new(initial_condition.positions[index]);
+        let cell_indices: Vec<usize> = from_p_to_c(&domain.lengths, ncells, rad, &pos);
+        if let Some(other) = find_overlap(domain, &particles, cells, &cell_indices, time, rad, pos)
+        {
+            return Err(InitialConditionError::Overlap(other, index));
+        }
+        let particle = Particle {
+            index,
+            rad,
+            mass: get_volume(rad),
+            pos,
+            vel: MyVec::new(initial_condition.velocities[index]),
+            angvel: PseudoVec::default(),
+            time,
+            vals: initial_condition.vals[index].clone(),
+            flights: FreeFlights::default(),
+            images: Images::default(),
+        };
+        register(&mut particles, cells, &cell_indices, particle);
+    }
+    Ok(particles)
 }
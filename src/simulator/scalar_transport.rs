@@ -0,0 +1,93 @@
+/// Law deciding how a scalar carried by particles is exchanged on events.
+pub trait ScalarTransport: std::fmt::Debug + Send + Sync {
+    /// Checks if the parameters are meaningful.
+    fn is_valid(&self) -> bool;
+
+    /// New values of a colliding pair.
+    fn exchange(&self, p_val: f64, q_val: f64) -> (f64, f64);
+
+    /// New value of a particle reflected by a wall imposing the Dirichlet condition.
+    fn exchange_with_wall(&self, val: f64, wall_val: f64) -> f64;
+
+    /// Name and parameters to save the model in checkpoints,
+    ///   which are only available for the built-in models.
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        None
+    }
+}
+
+/// Scalar diffusing by contacts, e.g. the temperature.
+///
+/// The difference between the two is reduced by the exchange coefficient in [0, 1],
+///   where the two values are equalised when unity.
+/// The sum of the values is conserved on collisions,
+///   while a wall drives the value toward its own value as if it were a particle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conductive {
+    pub coefficient: f64,
+}
+
+impl ScalarTransport for Conductive {
+    fn is_valid(&self) -> bool {
+        (0. ..=1.).contains(&self.coefficient)
+    }
+
+    fn exchange(&self, p_val: f64, q_val: f64) -> (f64, f64) {
+        let flux: f64 = 0.5 * self.coefficient * (q_val - p_val);
+        (p_val + flux, q_val - flux)
+    }
+
+    fn exchange_with_wall(&self, val: f64, wall_val: f64) -> f64 {
+        val + 0.5 * self.coefficient * (wall_val - val)
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("conductive", vec![self.coefficient]))
+    }
+}
+
+/// Scalar which is never exchanged, e.g. a species label or a tracer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Passive;
+
+impl ScalarTransport for Passive {
+    fn is_valid(&self) -> bool {
+        true
+    }
+
+    fn exchange(&self, p_val: f64, q_val: f64) -> (f64, f64) {
+        (p_val, q_val)
+    }
+
+    fn exchange_with_wall(&self, val: f64, _wall_val: f64) -> f64 {
+        val
+    }
+
+    fn get_parameters(&self) -> Option<(&'static str, Vec<f64>)> {
+        Some(("passive", Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod test_exchange {
+    use super::{Conductive, Passive, ScalarTransport};
+
+    #[test]
+    fn case1() {
+        // fully conductive particles share the mean value
+        let model = Conductive { coefficient: 1. };
+        assert_eq!(model.exchange(0., 1.), (0.5, 0.5));
+        assert_eq!(model.exchange_with_wall(0., 1.), 0.5);
+        // partially conductive particles conserve the sum
+        let model = Conductive { coefficient: 0.5 };
+        let (p_val, q_val): (f64, f64) = model.exchange(0., 1.);
+        assert_eq!((p_val, q_val), (0.25, 0.75));
+    }
+
+    #[test]
+    fn case2() {
+        // passive scalars are kept
+        assert_eq!(Passive.exchange(0., 1.), (0., 1.));
+        assert_eq!(Passive.exchange_with_wall(0., 1.), 0.);
+    }
+}
@@ -1,166 +1,131 @@
-use std::cell::{Ref, RefCell};
-use std::rc::Rc;
+mod binary_heap;
+mod calendar_queue;
+mod pairing_heap;
+mod tournament_tree;
 
 use crate::simulator::cell::Cell;
-use crate::simulator::event::Event;
 
-pub struct Scheduler {
-    /// Number of items
-    nitems: usize,
-    /// Main minimum heap
-    heap: Vec<Rc<RefCell<Cell>>>,
-    /// Contravariant look-up table to find heap from cell index
-    lookups: Vec<usize>,
+use binary_heap::BinaryHeap;
+use calendar_queue::CalendarQueue;
+use pairing_heap::PairingHeap;
+use tournament_tree::TournamentTree;
+
+/// Data structure to find the cell containing the coming event among all cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SchedulerKind {
+    /// Minimum binary heap, costing `O(log N_c)` for each update.
+    BinaryHeap,
+    /// Calendar queue, where the cells are sorted into buckets covering short time intervals,
+    ///   costing `O(1)` for each update on average.
+    CalendarQueue,
+    /// Complete binary tree (tournament tree), where each node holds the earlier of its children,
+    ///   costing `O(log N_c)` for each update without comparing the keys in the way down.
+    TournamentTree,
+    /// Pairing heap, costing `O(1)` to advance the key and `O(log N_c)` amortised to postpone it.
+    PairingHeap,
 }
 
-impl Scheduler {
-    /// Constructs a minimum heap.
-    pub fn new(cells: &[Rc<RefCell<Cell>>]) -> Scheduler {
-        let nitems: usize = cells.len();
-        let mut heap = Vec::<Rc<RefCell<Cell>>>::new();
-        let mut lookups = Vec::<usize>::new();
-        for (n, cell) in cells.iter().enumerate() {
-            heap.push(cell.clone());
-            lookups.push(n);
-        }
-        Scheduler {
-            nitems,
-            heap,
-            lookups,
-        }
-    }
+/// Global queue over the cells, keyed by the time of the head event of each cell.
+///
+/// N.B.: Ties are broken by the cell indices,
+///   so that all implementations process the events in the same order.
+pub trait Scheduler: Send {
+    /// Returns the index of the cell which contains the coming event.
+    fn get(&mut self) -> usize;
 
-    /// Returns cell which contains the coming event.
-    pub fn get(&self) -> Rc<RefCell<Cell>> {
-        if cfg!(debug_assertions) {
-            self.validate();
-        }
-        self.heap[0].clone()
-    }
+    /// Notifies that the time of the head event of the cell is changed.
+    fn update(&mut self, cell_index: usize, time_bef: f64, time_aft: f64);
+}
 
-    /// Updates heap.
-    pub fn update(&mut self, cell_index: usize, time_bef: f64, time_aft: f64) {
-        if time_bef < time_aft {
-            self.downshift(self.lookups[cell_index]);
-        } else {
-            self.upshift(self.lookups[cell_index]);
-        }
-        if cfg!(debug_assertions) {
-            self.validate();
-        }
-    }
+/// Constructs a scheduler of the given kind over the cells.
+pub fn new(kind: SchedulerKind, cells: &[Cell]) -> Box<dyn Scheduler> {
+    from_times(kind, cells.iter().map(Cell::get_head_event_time).collect())
+}
 
-    fn get_data(&self, heap: &[Rc<RefCell<Cell>>], index: usize) -> f64 {
-        let nitems: usize = self.nitems;
-        if nitems - 1 < index {
-            return f64::MAX;
-        }
-        let cell: &Rc<RefCell<Cell>> = &heap[index];
-        let cell: Ref<Cell> = cell.borrow();
-        let events: &Rc<RefCell<Vec<Event>>> = &cell.events;
-        let events: Ref<Vec<Event>> = events.borrow();
-        let data: f64 = if events.is_empty() {
-            f64::MAX
-        } else {
-            events[0].time
-        };
-        data
+/// Constructs a scheduler of the given kind from the keys of the cells.
+fn from_times(kind: SchedulerKind, times: Vec<f64>) -> Box<dyn Scheduler> {
+    match kind {
+        SchedulerKind::BinaryHeap => Box::new(BinaryHeap::new(times)),
+        SchedulerKind::CalendarQueue => Box::new(CalendarQueue::new(times)),
+        SchedulerKind::TournamentTree => Box::new(TournamentTree::new(times)),
+        SchedulerKind::PairingHeap => Box::new(PairingHeap::new(times)),
     }
+}
 
-    fn upshift(&mut self, mut n_c: usize) {
-        let nitems: usize = self.nitems;
-        while 0 < n_c && n_c < nitems {
-            let heap: &Vec<Rc<RefCell<Cell>>> = &self.heap;
-            let n_p: usize = parent(n_c);
-            let data_c: f64 = self.get_data(heap, n_c);
-            let data_p: f64 = self.get_data(heap, n_p);
-            if data_c < data_p {
-                let cell_index_c: usize = self.heap[n_c].borrow().index;
-                let cell_index_p: usize = self.heap[n_p].borrow().index;
-                self.heap.swap(n_c, n_p);
-                self.lookups.swap(cell_index_c, cell_index_p);
-                n_c = n_p;
+/// Checks if the cell `a` comes before the cell `b`.
+fn precedes(times: &[f64], a: usize, b: usize) -> bool {
+    (times[a], a) < (times[b], b)
+}
+
+/// Finds the cell containing the coming event by visiting all cells.
+fn find_first(times: &[f64]) -> usize {
+    (0..times.len())
+        .reduce(|a: usize, b: usize| if precedes(times, b, a) { b } else { a })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test_scheduler {
+    use super::{find_first, from_times, SchedulerKind};
+    use crate::random::Random;
+
+    const KINDS: [SchedulerKind; 4] = [
+        SchedulerKind::BinaryHeap,
+        SchedulerKind::CalendarQueue,
+        SchedulerKind::TournamentTree,
+        SchedulerKind::PairingHeap,
+    ];
+
+    /// Advances and postpones the keys randomly,
+    ///   checking the scheduler always gives the earliest cell.
+    fn check(kind: SchedulerKind, mut times: Vec<f64>, nsteps: usize) {
+        let ncells: usize = times.len();
+        let mut scheduler = from_times(kind, times.clone());
+        let mut rng = Random::new(0);
+        for _ in 0..nsteps {
+            let first: usize = find_first(&times);
+            assert_eq!(scheduler.get(), first, "{:?}", kind);
+            // the earliest one mostly goes to the future, while any one may come earlier
+            let cell_index: usize = if rng.gen_range(0., 1.) < 0.7 {
+                first
             } else {
-                break;
-            }
+                (rng.gen_range(0., ncells as f64) as usize).min(ncells - 1)
+            };
+            let time_bef: f64 = times[cell_index];
+            let time_aft: f64 = match rng.gen_range(0., 1.) {
+                r if r < 0.1 => f64::MAX,
+                r if r < 0.2 => times[first],
+                r if r < 0.3 => times[first] - rng.gen_range(0., 1.),
+                _ => times[first] + rng.gen_range(0., 10.),
+            };
+            times[cell_index] = time_aft;
+            scheduler.update(cell_index, time_bef, time_aft);
         }
     }
 
-    fn downshift(&mut self, mut n_p: usize) {
-        let nitems: usize = self.nitems;
-        while n_p < nitems {
-            let heap: &Vec<Rc<RefCell<Cell>>> = &self.heap;
-            let n_l: usize = lchild(n_p);
-            let n_r: usize = rchild(n_p);
-            let data_p: f64 = self.get_data(heap, n_p);
-            let data_l: f64 = self.get_data(heap, n_l);
-            let data_r: f64 = self.get_data(heap, n_r);
-            if data_l < data_p && data_l <= data_r {
-                let cell_index_l: usize = self.heap[n_l].borrow().index;
-                let cell_index_p: usize = self.heap[n_p].borrow().index;
-                self.heap.swap(n_l, n_p);
-                self.lookups.swap(cell_index_l, cell_index_p);
-                n_p = n_l;
-            } else if data_r < data_p && data_r < data_l {
-                let cell_index_p: usize = self.heap[n_p].borrow().index;
-                let cell_index_r: usize = self.heap[n_r].borrow().index;
-                self.heap.swap(n_p, n_r);
-                self.lookups.swap(cell_index_p, cell_index_r);
-                n_p = n_r;
-            } else {
-                break;
-            }
+    #[test]
+    fn case1() {
+        // distinct keys
+        for kind in KINDS {
+            let times: Vec<f64> = (0..100).map(|n| (n * 37 % 100) as f64).collect();
+            check(kind, times, 2000);
         }
     }
 
-    #[allow(dead_code)]
-    fn validate(&self) {
-        let nitems: usize = self.nitems;
-        let heap: &Vec<Rc<RefCell<Cell>>> = &self.heap;
-        // for each child element, check its parent satisfies the requirement
-        for n_c in 1..nitems {
-            let n_p: usize = parent(n_c);
-            let data_c: f64 = self.get_data(heap, n_c);
-            let data_p: f64 = self.get_data(heap, n_p);
-            if data_c < data_p {
-                self.show();
-                panic!("invalid heap");
-            }
-        }
-        // for each parent element, check its children satisfy the requirement
-        for n_p in 0..nitems {
-            let n_l: usize = lchild(n_p);
-            let n_r: usize = rchild(n_p);
-            let data_p: f64 = self.get_data(heap, n_p);
-            let data_l: f64 = self.get_data(heap, n_l);
-            let data_r: f64 = self.get_data(heap, n_r);
-            if (data_l < data_p && data_l <= data_r) || (data_r < data_p && data_r < data_l) {
-                self.show();
-                panic!("invalid heap");
-            }
+    #[test]
+    fn case2() {
+        // ties, which are broken by the cell indices
+        for kind in KINDS {
+            check(kind, vec![1.; 50], 2000);
         }
     }
 
-    #[allow(dead_code)]
-    pub fn show(&self) {
-        let nitems: usize = self.nitems;
-        let heap: &Vec<Rc<RefCell<Cell>>> = &self.heap;
-        for n in 0..nitems {
-            let cell_index: usize = heap[n].borrow().index;
-            let data: f64 = self.get_data(heap, n);
-            println!("{:2}, c idx: {:2}, data: {:8.2e}", n, cell_index, data);
+    #[test]
+    fn case3() {
+        // only one cell, or no event at all
+        for kind in KINDS {
+            check(kind, vec![f64::MAX], 10);
+            check(kind, vec![f64::MAX; 8], 10);
         }
     }
 }
-
-fn parent(n: usize) -> usize {
-    (n - 1) / 2
-}
-
-fn lchild(n: usize) -> usize {
-    2 * n + 1
-}
-
-fn rchild(n: usize) -> usize {
-    2 * n + 2
-}
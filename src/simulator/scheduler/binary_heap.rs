@@ -0,0 +1,155 @@
+use super::Scheduler;
+
+pub struct BinaryHeap {
+    /// Number of items
+    nitems: usize,
+    /// Main minimum heap, storing the cell indices
+    heap: Vec<usize>,
+    /// Contravariant look-up table to find heap from cell index
+    lookups: Vec<usize>,
+    /// Time of the head event of each cell, which is the key of the heap
+    times: Vec<f64>,
+}
+
+impl BinaryHeap {
+    /// Constructs a minimum heap.
+    pub fn new(times: Vec<f64>) -> BinaryHeap {
+        let nitems: usize = times.len();
+        let heap: Vec<usize> = (0..nitems).collect();
+        let lookups: Vec<usize> = (0..nitems).collect();
+        let mut scheduler = BinaryHeap {
+            nitems,
+            heap,
+            lookups,
+            times,
+        };
+        for n in (0..nitems / 2).rev() {
+            scheduler.downshift(n);
+        }
+        scheduler
+    }
+
+    /// Returns the key of the given position,
+    ///   which comes after all others when the position is out of range.
+    fn get_data(&self, index: usize) -> (f64, usize) {
+        if self.nitems <= index {
+            return (f64::MAX, usize::MAX);
+        }
+        let cell_index: usize = self.heap[index];
+        (self.times[cell_index], cell_index)
+    }
+
+    fn upshift(&mut self, mut n_c: usize) {
+        let nitems: usize = self.nitems;
+        while 0 < n_c && n_c < nitems {
+            let n_p: usize = parent(n_c);
+            let data_c: (f64, usize) = self.get_data(n_c);
+            let data_p: (f64, usize) = self.get_data(n_p);
+            if data_c < data_p {
+                let cell_index_c: usize = self.heap[n_c];
+                let cell_index_p: usize = self.heap[n_p];
+                self.heap.swap(n_c, n_p);
+                self.lookups.swap(cell_index_c, cell_index_p);
+                n_c = n_p;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn downshift(&mut self, mut n_p: usize) {
+        let nitems: usize = self.nitems;
+        while n_p < nitems {
+            let n_l: usize = lchild(n_p);
+            let n_r: usize = rchild(n_p);
+            let data_p: (f64, usize) = self.get_data(n_p);
+            let data_l: (f64, usize) = self.get_data(n_l);
+            let data_r: (f64, usize) = self.get_data(n_r);
+            if data_l < data_p && data_l <= data_r {
+                let cell_index_l: usize = self.heap[n_l];
+                let cell_index_p: usize = self.heap[n_p];
+                self.heap.swap(n_l, n_p);
+                self.lookups.swap(cell_index_l, cell_index_p);
+                n_p = n_l;
+            } else if data_r < data_p && data_r < data_l {
+                let cell_index_p: usize = self.heap[n_p];
+                let cell_index_r: usize = self.heap[n_r];
+                self.heap.swap(n_p, n_r);
+                self.lookups.swap(cell_index_p, cell_index_r);
+                n_p = n_r;
+            } else {
+                break;
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) {
+        let nitems: usize = self.nitems;
+        // for each child element, check its parent satisfies the requirement
+        for n_c in 1..nitems {
+            let n_p: usize = parent(n_c);
+            let data_c: (f64, usize) = self.get_data(n_c);
+            let data_p: (f64, usize) = self.get_data(n_p);
+            if data_c < data_p {
+                self.show();
+                panic!("invalid heap");
+            }
+        }
+        // for each parent element, check its children satisfy the requirement
+        for n_p in 0..nitems {
+            let n_l: usize = lchild(n_p);
+            let n_r: usize = rchild(n_p);
+            let data_p: (f64, usize) = self.get_data(n_p);
+            let data_l: (f64, usize) = self.get_data(n_l);
+            let data_r: (f64, usize) = self.get_data(n_r);
+            if (data_l < data_p && data_l <= data_r) || (data_r < data_p && data_r < data_l) {
+                self.show();
+                panic!("invalid heap");
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn show(&self) {
+        let nitems: usize = self.nitems;
+        for n in 0..nitems {
+            let cell_index: usize = self.heap[n];
+            let data: f64 = self.get_data(n).0;
+            println!("{:2}, c idx: {:2}, data: {:8.2e}", n, cell_index, data);
+        }
+    }
+}
+
+impl Scheduler for BinaryHeap {
+    fn get(&mut self) -> usize {
+        if cfg!(debug_assertions) {
+            self.validate();
+        }
+        self.heap[0]
+    }
+
+    fn update(&mut self, cell_index: usize, time_bef: f64, time_aft: f64) {
+        self.times[cell_index] = time_aft;
+        if time_bef < time_aft {
+            self.downshift(self.lookups[cell_index]);
+        } else {
+            self.upshift(self.lookups[cell_index]);
+        }
+        if cfg!(debug_assertions) {
+            self.validate();
+        }
+    }
+}
+
+fn parent(n: usize) -> usize {
+    (n - 1) / 2
+}
+
+fn lchild(n: usize) -> usize {
+    2 * n + 1
+}
+
+fn rchild(n: usize) -> usize {
+    2 * n + 2
+}
@@ -0,0 +1,182 @@
+use super::{find_first, precedes, Scheduler};
+
+/// Slot of the cells which have no event and are not in any bucket.
+const NEVER: u64 = u64::MAX;
+/// Number of the earliest keys to estimate the width of the buckets.
+const NSAMPLES: usize = 25;
+
+pub struct CalendarQueue {
+    /// Time interval covered by each bucket
+    width: f64,
+    /// Cells in no particular order, where the bucket of a cell is given by its slot
+    buckets: Vec<Vec<usize>>,
+    /// Number of the widths elapsed until the key of each cell
+    slots: Vec<u64>,
+    /// Position of each cell in its bucket
+    positions: Vec<usize>,
+    /// Slot from which the search starts, which is not later than the slots of all cells
+    current: u64,
+    /// Number of the searches since the width was estimated
+    nsearches: usize,
+    /// Time of the head event of each cell, which is the key of the queue
+    times: Vec<f64>,
+}
+
+impl CalendarQueue {
+    /// Constructs a calendar with one bucket for each cell.
+    pub fn new(times: Vec<f64>) -> CalendarQueue {
+        let nitems: usize = times.len();
+        let mut scheduler = CalendarQueue {
+            width: 1.,
+            buckets: vec![Vec::new(); nitems.max(1)],
+            slots: vec![NEVER; nitems],
+            positions: vec![0; nitems],
+            current: NEVER,
+            nsearches: 0,
+            times,
+        };
+        scheduler.resize();
+        scheduler
+    }
+
+    fn get_slot(&self, time: f64) -> u64 {
+        if f64::MAX == time {
+            return NEVER;
+        }
+        // the conversion saturates, keeping the order
+        ((time / self.width).floor() as u64).min(NEVER - 1)
+    }
+
+    fn get_bucket(&self, slot: u64) -> usize {
+        (slot % self.buckets.len() as u64) as usize
+    }
+
+    fn insert(&mut self, cell_index: usize) {
+        let slot: u64 = self.get_slot(self.times[cell_index]);
+        self.slots[cell_index] = slot;
+        if NEVER == slot {
+            return;
+        }
+        let bucket: usize = self.get_bucket(slot);
+        self.positions[cell_index] = self.buckets[bucket].len();
+        self.buckets[bucket].push(cell_index);
+        // the search starts from the earlier one
+        self.current = self.current.min(slot);
+    }
+
+    fn remove(&mut self, cell_index: usize) {
+        let slot: u64 = self.slots[cell_index];
+        if NEVER == slot {
+            return;
+        }
+        let bucket: usize = self.get_bucket(slot);
+        let bucket: &mut Vec<usize> = &mut self.buckets[bucket];
+        let position: usize = self.positions[cell_index];
+        bucket.swap_remove(position);
+        if let Some(&moved) = bucket.get(position) {
+            self.positions[moved] = position;
+        }
+    }
+
+    /// Estimates the width so that a few cells fall into each bucket around the coming event,
+    ///   from the average interval between the earliest keys following R. Brown (1988),
+    ///   and distributes the cells again.
+    fn resize(&mut self) {
+        let mut samples: Vec<f64> = self
+            .times
+            .iter()
+            .copied()
+            .filter(|&time: &f64| f64::MAX != time)
+            .collect();
+        let nsamples: usize = samples.len().min(NSAMPLES);
+        if 1 < nsamples {
+            if nsamples < samples.len() {
+                samples.select_nth_unstable_by(nsamples, f64::total_cmp);
+                samples.truncate(nsamples);
+            }
+            samples.sort_unstable_by(f64::total_cmp);
+            let intervals: Vec<f64> = samples.windows(2).map(|pair| pair[1] - pair[0]).collect();
+            let average: f64 = intervals.iter().sum::<f64>() / intervals.len() as f64;
+            // intervals much longer than the others are excluded
+            let (sum, count): (f64, usize) = intervals
+                .iter()
+                .filter(|&&interval: &&f64| interval <= 2. * average)
+                .fold((0., 0), |(sum, count), &interval| {
+                    (sum + interval, count + 1)
+                });
+            let width: f64 = 3. * sum / count as f64;
+            if 0. < width && width.is_finite() {
+                self.width = width;
+            }
+        }
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+        self.current = NEVER;
+        for cell_index in 0..self.times.len() {
+            self.insert(cell_index);
+        }
+        self.nsearches = 0;
+    }
+
+    /// Searches the buckets for one year from the current slot.
+    fn search(&mut self) -> Option<usize> {
+        let nbuckets: u64 = self.buckets.len() as u64;
+        for slot in self.current..self.current.saturating_add(nbuckets) {
+            let mut first: Option<usize> = None;
+            for &cell_index in self.buckets[self.get_bucket(slot)].iter() {
+                // the cells in the later years are skipped
+                if slot != self.slots[cell_index] {
+                    continue;
+                }
+                first = match first {
+                    Some(other) if precedes(&self.times, other, cell_index) => Some(other),
+                    _ => Some(cell_index),
+                };
+            }
+            if first.is_some() {
+                self.current = slot;
+                return first;
+            }
+        }
+        None
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self, cell_index: usize) {
+        if find_first(&self.times) != cell_index {
+            panic!("{} is not the first", cell_index);
+        }
+    }
+}
+
+impl Scheduler for CalendarQueue {
+    fn get(&mut self) -> usize {
+        // the distribution of the keys changes as the time goes by
+        self.nsearches += 1;
+        if self.buckets.len() < self.nsearches {
+            self.resize();
+        }
+        let cell_index: usize = match self.search() {
+            Some(cell_index) => cell_index,
+            None => {
+                // no cell in a year, which is found directly
+                let cell_index: usize = find_first(&self.times);
+                if let Some(&slot) = self.slots.get(cell_index) {
+                    self.current = slot;
+                }
+                cell_index
+            }
+        };
+        if cfg!(debug_assertions) {
+            self.validate(cell_index);
+        }
+        cell_index
+    }
+
+    fn update(&mut self, cell_index: usize, _time_bef: f64, time_aft: f64) {
+        self.remove(cell_index);
+        self.times[cell_index] = time_aft;
+        self.insert(cell_index);
+    }
+}
@@ -0,0 +1,166 @@
+use super::{precedes, Scheduler};
+
+/// Marks the absence of a node.
+const NONE: usize = usize::MAX;
+
+pub struct PairingHeap {
+    /// Root node, which is the cell containing the coming event
+    root: usize,
+    /// Leftmost child of each node
+    children: Vec<usize>,
+    /// Next sibling on the right of each node
+    siblings: Vec<usize>,
+    /// Previous sibling on the left of each node, or its parent for the leftmost child
+    prevs: Vec<usize>,
+    /// Time of the head event of each cell, which is the key of the heap
+    times: Vec<f64>,
+    /// Work space to merge the subtrees in two passes
+    buffer: Vec<usize>,
+}
+
+impl PairingHeap {
+    /// Constructs a heap by merging all cells one by one.
+    pub fn new(times: Vec<f64>) -> PairingHeap {
+        let nitems: usize = times.len();
+        let mut scheduler = PairingHeap {
+            root: NONE,
+            children: vec![NONE; nitems],
+            siblings: vec![NONE; nitems],
+            prevs: vec![NONE; nitems],
+            times,
+            buffer: Vec::new(),
+        };
+        for cell_index in 0..nitems {
+            scheduler.root = scheduler.meld(scheduler.root, cell_index);
+        }
+        scheduler
+    }
+
+    /// Merges two trees, where the later root becomes the leftmost child of the earlier one.
+    fn meld(&mut self, a: usize, b: usize) -> usize {
+        if NONE == a {
+            return b;
+        }
+        if NONE == b {
+            return a;
+        }
+        let (a, b): (usize, usize) = if precedes(&self.times, b, a) {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        let child: usize = self.children[a];
+        if NONE != child {
+            self.prevs[child] = b;
+        }
+        self.siblings[b] = child;
+        self.prevs[b] = a;
+        self.children[a] = b;
+        a
+    }
+
+    /// Detaches the subtree rooted at the given node from its parent.
+    fn cut(&mut self, n: usize) {
+        let prev: usize = self.prevs[n];
+        let sibling: usize = self.siblings[n];
+        if n == self.children[prev] {
+            self.children[prev] = sibling;
+        } else {
+            self.siblings[prev] = sibling;
+        }
+        if NONE != sibling {
+            self.prevs[sibling] = prev;
+        }
+        self.prevs[n] = NONE;
+        self.siblings[n] = NONE;
+    }
+
+    /// Detaches all children of the given node,
+    ///   which are merged into a tree in two passes.
+    fn merge_children(&mut self, n: usize) -> usize {
+        let mut buffer: Vec<usize> = std::mem::take(&mut self.buffer);
+        let mut child: usize = self.children[n];
+        self.children[n] = NONE;
+        while NONE != child {
+            let sibling: usize = self.siblings[child];
+            self.prevs[child] = NONE;
+            self.siblings[child] = NONE;
+            buffer.push(child);
+            child = sibling;
+        }
+        // first pass: merge the pairs from the left
+        let npairs: usize = buffer.len() / 2;
+        for k in 0..npairs {
+            buffer[k] = self.meld(buffer[2 * k], buffer[2 * k + 1]);
+        }
+        if 1 == buffer.len() % 2 {
+            buffer[npairs] = buffer[buffer.len() - 1];
+            buffer.truncate(npairs + 1);
+        } else {
+            buffer.truncate(npairs);
+        }
+        // second pass: merge them from the right
+        let mut tree: usize = NONE;
+        while let Some(subtree) = buffer.pop() {
+            tree = self.meld(subtree, tree);
+        }
+        self.buffer = buffer;
+        tree
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) {
+        let mut nnodes: usize = 0;
+        let mut stack: Vec<usize> = vec![self.root];
+        while let Some(parent) = stack.pop() {
+            if NONE == parent {
+                continue;
+            }
+            nnodes += 1;
+            let mut child: usize = self.children[parent];
+            while NONE != child {
+                if precedes(&self.times, child, parent) {
+                    panic!("invalid heap at {}", child);
+                }
+                stack.push(child);
+                child = self.siblings[child];
+            }
+        }
+        if nnodes != self.times.len() {
+            panic!("{} nodes are lost", self.times.len() - nnodes);
+        }
+    }
+}
+
+impl Scheduler for PairingHeap {
+    fn get(&mut self) -> usize {
+        if cfg!(debug_assertions) {
+            self.validate();
+        }
+        self.root
+    }
+
+    fn update(&mut self, cell_index: usize, time_bef: f64, time_aft: f64) {
+        self.times[cell_index] = time_aft;
+        let n: usize = cell_index;
+        if time_aft < time_bef {
+            // the subtree is still ordered, which is merged with the rest
+            if n != self.root {
+                self.cut(n);
+                self.root = self.meld(self.root, n);
+            }
+        } else if time_bef < time_aft {
+            // the children may come before the postponed node,
+            //   which is taken out and merged again
+            let rest: usize = if n == self.root {
+                NONE
+            } else {
+                self.cut(n);
+                self.root
+            };
+            let children: usize = self.merge_children(n);
+            let rest: usize = self.meld(rest, children);
+            self.root = self.meld(rest, n);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::{precedes, Scheduler};
+
+/// Marks a leaf which corresponds to no cell.
+const VACANT: usize = usize::MAX;
+
+pub struct TournamentTree {
+    /// Number of leaves, which is the smallest power of two accommodating all cells
+    nleaves: usize,
+    /// Complete binary tree in the breadth-first order starting from 1,
+    ///   whose last `nleaves` nodes are the leaves,
+    ///   and each of the others stores the earlier cell index of its children
+    nodes: Vec<usize>,
+    /// Time of the head event of each cell, which is the key of the tree
+    times: Vec<f64>,
+}
+
+impl TournamentTree {
+    /// Constructs a tree, where all matches are played from the leaves.
+    pub fn new(times: Vec<f64>) -> TournamentTree {
+        let nitems: usize = times.len();
+        let nleaves: usize = nitems.next_power_of_two();
+        let mut nodes = vec![VACANT; 2 * nleaves];
+        for (cell_index, node) in nodes[nleaves..nleaves + nitems].iter_mut().enumerate() {
+            *node = cell_index;
+        }
+        let mut scheduler = TournamentTree {
+            nleaves,
+            nodes,
+            times,
+        };
+        for n in (1..nleaves).rev() {
+            scheduler.nodes[n] = scheduler.play(n);
+        }
+        scheduler
+    }
+
+    /// Returns the winner of the children of the given node.
+    fn play(&self, n: usize) -> usize {
+        let l: usize = self.nodes[2 * n];
+        let r: usize = self.nodes[2 * n + 1];
+        if VACANT == r || (VACANT != l && precedes(&self.times, l, r)) {
+            l
+        } else {
+            r
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) {
+        for n in 1..self.nleaves {
+            if self.play(n) != self.nodes[n] {
+                panic!("invalid tree at {}", n);
+            }
+        }
+    }
+}
+
+impl Scheduler for TournamentTree {
+    fn get(&mut self) -> usize {
+        if cfg!(debug_assertions) {
+            self.validate();
+        }
+        // the root is the leaf itself when there is only one cell
+        self.nodes[1]
+    }
+
+    fn update(&mut self, cell_index: usize, _time_bef: f64, time_aft: f64) {
+        self.times[cell_index] = time_aft;
+        // replay all matches in the way to the root,
+        //   where the winners may be the same cell as before but the keys are changed
+        let mut n: usize = (self.nleaves + cell_index) / 2;
+        while 0 < n {
+            self.nodes[n] = self.play(n);
+            n /= 2;
+        }
+    }
+}
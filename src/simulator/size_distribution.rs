@@ -0,0 +1,129 @@
+use crate::random::Random;
+use crate::simulator::particle::get_volume;
+use crate::simulator::NDIMS;
+
+/// Distribution from which particle radii are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SizeDistribution {
+    /// All particles share the same radius.
+    Monodisperse { radius: f64 },
+    /// Two species, where `fraction` of particles (in number) have the `large` radius.
+    Bidisperse {
+        small: f64,
+        large: f64,
+        fraction: f64,
+    },
+    /// Log-normal distribution truncated at `max`.
+    ///
+    /// `median` and `sigma` are the median and the standard deviation of the underlying normal
+    ///   distribution of the logarithm, respectively.
+    Lognormal { median: f64, sigma: f64, max: f64 },
+}
+
+impl SizeDistribution {
+    /// Checks if the parameters are meaningful.
+    pub fn is_valid(&self) -> bool {
+        let is_positive = |val: f64| val.is_finite() && 0. < val;
+        match *self {
+            SizeDistribution::Monodisperse { radius } => is_positive(radius),
+            SizeDistribution::Bidisperse {
+                small,
+                large,
+                fraction,
+            } => is_positive(small) && is_positive(large) && (0. ..=1.).contains(&fraction),
+            SizeDistribution::Lognormal { median, sigma, max } => {
+                is_positive(median) && sigma.is_finite() && 0. <= sigma && median <= max
+            }
+        }
+    }
+
+    /// Returns the largest radius which can be drawn.
+    pub fn max_radius(&self) -> f64 {
+        match *self {
+            SizeDistribution::Monodisperse { radius } => radius,
+            SizeDistribution::Bidisperse { small, large, .. } => small.max(large),
+            SizeDistribution::Lognormal { max, .. } => max,
+        }
+    }
+
+    /// Returns the expected volume of a particle.
+    ///
+    /// N.B.: The truncation of the log-normal distribution is not taken into account.
+    pub fn mean_volume(&self) -> f64 {
+        let ndims = NDIMS as i32;
+        let moment: f64 = match *self {
+            SizeDistribution::Monodisperse { radius } => radius.powi(ndims),
+            SizeDistribution::Bidisperse {
+                small,
+                large,
+                fraction,
+            } => (1. - fraction) * small.powi(ndims) + fraction * large.powi(ndims),
+            SizeDistribution::Lognormal { median, sigma, .. } => {
+                median.powi(ndims) * (0.5 * (ndims as f64 * sigma).powi(2)).exp()
+            }
+        };
+        get_volume(1.) * moment
+    }
+
+    /// Draws a radius.
+    ///
+    /// N.B.: The random-number generator is not used for a monodisperse system.
+    pub fn sample(&self, rng: &mut Random) -> f64 {
+        match *self {
+            SizeDistribution::Monodisperse { radius } => radius,
+            SizeDistribution::Bidisperse {
+                small,
+                large,
+                fraction,
+            } => {
+                if rng.gen_range(0., 1.) < fraction {
+                    large
+                } else {
+                    small
+                }
+            }
+            SizeDistribution::Lognormal { median, sigma, max } => loop {
+                let z: f64 = rng.gen_normal(0., 1.);
+                let radius: f64 = median * (sigma * z).exp();
+                // truncate to be consistent with the cell size
+                if radius <= max {
+                    break radius;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_sample {
+    use super::SizeDistribution;
+    use crate::random::Random;
+
+    #[test]
+    fn case1() {
+        let mut rng = Random::new(0);
+        let distribution = SizeDistribution::Bidisperse {
+            small: 0.5,
+            large: 1.,
+            fraction: 0.5,
+        };
+        for _ in 0..128 {
+            let radius: f64 = distribution.sample(&mut rng);
+            assert!(0.5 == radius || 1. == radius);
+        }
+    }
+
+    #[test]
+    fn case2() {
+        let mut rng = Random::new(0);
+        let distribution = SizeDistribution::Lognormal {
+            median: 0.5,
+            sigma: 0.2,
+            max: 0.7,
+        };
+        for _ in 0..128 {
+            let radius: f64 = distribution.sample(&mut rng);
+            assert!(0. < radius && radius <= 0.7);
+        }
+    }
+}
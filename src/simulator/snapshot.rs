@@ -0,0 +1,457 @@
+//! Self-describing binary snapshots of the particles, which are meant for post-processing.
+//!
+//! All numbers are stored in the little-endian byte order, where the integers are 64-bit
+//!   except the format version and the number of dimensions (32-bit).
+//! The layout is:
+//!
+//! 1. magic number `COLLSNAP` (8 bytes) and format version
+//! 2. number of dimensions, time, number of particles, and number of scalars
+//! 3. domain lengths and periodicities (one byte each, 1 if periodic)
+//! 4. particle indices, radii, positions, velocities, and scalars, one array after another,
+//!    where the vectors (and the scalars) of each particle are contiguous
+//!
+//! The same contents can also be written as text (comma-separated values),
+//!   where the header is given in the leading comment lines, followed by one row for each particle.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::myvec::MyVec;
+use crate::simulator::particle::{Particle, Particles};
+use crate::simulator::{Domain, NDIMS};
+
+/// Identifies the format.
+const MAGIC: [u8; 8] = *b"COLLSNAP";
+/// Incremented whenever the layout changes.
+pub const VERSION: u32 = 1;
+
+/// Reasons why a snapshot cannot be written or read.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Underlying stream or file system failed.
+    Io(std::io::Error),
+    /// The stream does not start with the magic number.
+    InvalidMagic,
+    /// The format version is not supported by this build.
+    UnsupportedVersion(u32),
+    /// The snapshot is written by a build with the different number of dimensions.
+    DimensionMismatch(usize),
+    /// The stored data are inconsistent.
+    Corrupted(&'static str),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::InvalidMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "snapshot version {} is not supported (expected {})",
+                version, VERSION
+            ),
+            SnapshotError::DimensionMismatch(ndims) => write!(
+                f,
+                "snapshot is {}-dimensional while this build is {}-dimensional",
+                ndims, NDIMS
+            ),
+            SnapshotError::Corrupted(reason) => write!(f, "corrupted snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+/// Encoding of the snapshots in a series.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SnapshotFormat {
+    /// Binary layout described in the module documentation, which is read by [`Snapshot::read`].
+    #[default]
+    Binary,
+    /// Comma-separated values, which are human-readable but larger and not read back.
+    Csv,
+}
+
+impl SnapshotFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Binary => "bin",
+            SnapshotFormat::Csv => "csv",
+        }
+    }
+}
+
+/// States of all particles at a synchronised time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub time: f64,
+    pub lengths: [f64; NDIMS],
+    pub periodicities: [bool; NDIMS],
+    /// Indices identifying the particles, which are kept throughout the run.
+    pub indices: Vec<usize>,
+    pub radii: Vec<f64>,
+    pub positions: Vec<[f64; NDIMS]>,
+    pub velocities: Vec<[f64; NDIMS]>,
+    /// Scalars of each particle, one for each scalar transport model.
+    pub vals: Vec<Vec<f64>>,
+}
+
+fn put_f64s(writer: &mut dyn Write, vals: &[f64]) -> Result<(), SnapshotError> {
+    for val in vals.iter() {
+        writer.write_all(&val.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn get_bytes<const N: usize>(reader: &mut dyn Read) -> Result<[u8; N], SnapshotError> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn get_u64(reader: &mut dyn Read) -> Result<usize, SnapshotError> {
+    let val: u64 = u64::from_le_bytes(get_bytes(reader)?);
+    usize::try_from(val).map_err(|_| SnapshotError::Corrupted("too large integer"))
+}
+
+fn get_f64(reader: &mut dyn Read) -> Result<f64, SnapshotError> {
+    Ok(f64::from_le_bytes(get_bytes(reader)?))
+}
+
+/// Reads the number of items to follow, which is bounded to reject broken streams.
+fn get_len(reader: &mut dyn Read) -> Result<usize, SnapshotError> {
+    const MAX_LEN: usize = 1 << 40;
+    let len: usize = get_u64(reader)?;
+    if MAX_LEN < len {
+        return Err(SnapshotError::Corrupted("too long sequence"));
+    }
+    Ok(len)
+}
+
+impl Snapshot {
+    /// Collects the particles brought to the given time.
+    pub fn new(domain: &Domain, time: f64, particles: &Particles) -> Snapshot {
+        let mut snapshot = Snapshot {
+            time,
+            lengths: domain.lengths,
+            periodicities: domain.periodicities,
+            ..Default::default()
+        };
+        for p in 0..particles.len() {
+            let acc: MyVec = particles.get_acceleration(domain, p);
+            let dt: f64 = time - particles.times[p];
+            let pos: MyVec = Particle::get_new_pos(
+                domain,
+                particles.positions[p],
+                particles.velocities[p],
+                acc,
+                dt,
+            );
+            let vel: MyVec = Particle::get_new_vel(particles.velocities[p], acc, dt);
+            snapshot.indices.push(particles.indices[p]);
+            snapshot.radii.push(particles.radii[p]);
+            snapshot
+                .positions
+                .push(std::array::from_fn(|dim: usize| pos[dim]));
+            snapshot
+                .velocities
+                .push(std::array::from_fn(|dim: usize| vel[dim]));
+            snapshot.vals.push(particles.vals[p].clone());
+        }
+        snapshot
+    }
+
+    /// Number of the scalars carried by each particle.
+    fn nscalars(&self) -> usize {
+        self.vals.first().map_or(0, |vals: &Vec<f64>| vals.len())
+    }
+
+    pub fn write(&self, writer: &mut dyn Write) -> Result<(), SnapshotError> {
+        let nitems: usize = self.indices.len();
+        let nscalars: usize = self.nscalars();
+        if [
+            self.radii.len(),
+            self.positions.len(),
+            self.velocities.len(),
+            self.vals.len(),
+        ]
+        .iter()
+        .any(|&len: &usize| nitems != len)
+        {
+            return Err(SnapshotError::Corrupted("inconsistent number of particles"));
+        }
+        if self
+            .vals
+            .iter()
+            .any(|vals: &Vec<f64>| nscalars != vals.len())
+        {
+            return Err(SnapshotError::Corrupted("inconsistent number of scalars"));
+        }
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(NDIMS as u32).to_le_bytes())?;
+        put_f64s(writer, &[self.time])?;
+        writer.write_all(&(nitems as u64).to_le_bytes())?;
+        writer.write_all(&(nscalars as u64).to_le_bytes())?;
+        put_f64s(writer, &self.lengths)?;
+        for &periodicity in self.periodicities.iter() {
+            writer.write_all(&[periodicity as u8])?;
+        }
+        for &index in self.indices.iter() {
+            writer.write_all(&(index as u64).to_le_bytes())?;
+        }
+        put_f64s(writer, &self.radii)?;
+        put_f64s(writer, self.positions.as_flattened())?;
+        put_f64s(writer, self.velocities.as_flattened())?;
+        for vals in self.vals.iter() {
+            put_f64s(writer, vals)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot as comma-separated values, e.g.
+    ///
+    /// ```text
+    /// # ndims: 2
+    /// # time: 1.5
+    /// # lengths: 32,32
+    /// # periodicities: 1,0
+    /// index,radius,x0,x1,v0,v1,val0
+    /// 0,0.5,3.25,10.5,0.125,-0.5,1
+    /// ```
+    pub fn write_csv(&self, writer: &mut dyn Write) -> Result<(), SnapshotError> {
+        let join = |vals: &[f64]| -> String {
+            vals.iter()
+                .map(|val: &f64| val.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let nitems: usize = self.indices.len();
+        if [
+            self.radii.len(),
+            self.positions.len(),
+            self.velocities.len(),
+            self.vals.len(),
+        ]
+        .iter()
+        .any(|&len: &usize| nitems != len)
+        {
+            return Err(SnapshotError::Corrupted("inconsistent number of particles"));
+        }
+        writeln!(writer, "# ndims: {}", NDIMS)?;
+        writeln!(writer, "# time: {}", self.time)?;
+        writeln!(writer, "# lengths: {}", join(&self.lengths))?;
+        let periodicities: Vec<f64> = self
+            .periodicities
+            .iter()
+            .map(|&periodicity: &bool| periodicity as u8 as f64)
+            .collect();
+        writeln!(writer, "# periodicities: {}", join(&periodicities))?;
+        let mut columns: Vec<String> = vec![String::from("index"), String::from("radius")];
+        columns.extend((0..NDIMS).map(|dim: usize| format!("x{}", dim)));
+        columns.extend((0..NDIMS).map(|dim: usize| format!("v{}", dim)));
+        columns.extend((0..self.nscalars()).map(|scalar: usize| format!("val{}", scalar)));
+        writeln!(writer, "{}", columns.join(","))?;
+        for n in 0..nitems {
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                self.indices[n],
+                self.radii[n],
+                join(&self.positions[n]),
+                join(&self.velocities[n]),
+                join(&self.vals[n])
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn read(reader: &mut dyn Read) -> Result<Snapshot, SnapshotError> {
+        if MAGIC != get_bytes(reader)? {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let version: u32 = u32::from_le_bytes(get_bytes(reader)?);
+        if VERSION != version {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let ndims: usize = u32::from_le_bytes(get_bytes(reader)?) as usize;
+        if NDIMS != ndims {
+            return Err(SnapshotError::DimensionMismatch(ndims));
+        }
+        let time: f64 = get_f64(reader)?;
+        let nitems: usize = get_len(reader)?;
+        let nscalars: usize = get_len(reader)?;
+        let mut snapshot = Snapshot {
+            time,
+            ..Default::default()
+        };
+        for length in snapshot.lengths.iter_mut() {
+            *length = get_f64(reader)?;
+        }
+        for periodicity in snapshot.periodicities.iter_mut() {
+            *periodicity = match get_bytes::<1>(reader)?[0] {
+                0 => false,
+                1 => true,
+                _ => return Err(SnapshotError::Corrupted("invalid periodicity")),
+            };
+        }
+        let get_vec = |reader: &mut dyn Read| -> Result<[f64; NDIMS], SnapshotError> {
+            let mut vec = [0.; NDIMS];
+            for item in vec.iter_mut() {
+                *item = get_f64(reader)?;
+            }
+            Ok(vec)
+        };
+        snapshot.indices = (0..nitems)
+            .map(|_| get_u64(reader))
+            .collect::<Result<_, _>>()?;
+        snapshot.radii = (0..nitems)
+            .map(|_| get_f64(reader))
+            .collect::<Result<_, _>>()?;
+        snapshot.positions = (0..nitems)
+            .map(|_| get_vec(reader))
+            .collect::<Result<_, _>>()?;
+        snapshot.velocities = (0..nitems)
+            .map(|_| get_vec(reader))
+            .collect::<Result<_, _>>()?;
+        snapshot.vals = (0..nitems)
+            .map(|_| (0..nscalars).map(|_| get_f64(reader)).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(snapshot)
+    }
+}
+
+/// Writes numbered snapshots to a directory, e.g. `snapshot_000000.bin`.
+pub struct SnapshotSeries {
+    directory: PathBuf,
+    format: SnapshotFormat,
+    /// Number of the snapshot written next.
+    index: usize,
+}
+
+impl SnapshotSeries {
+    /// Creates the directory if it does not exist.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Result<SnapshotSeries, SnapshotError> {
+        std::fs::create_dir_all(directory.as_ref())?;
+        Ok(SnapshotSeries {
+            directory: directory.as_ref().to_path_buf(),
+            format: SnapshotFormat::default(),
+            index: 0,
+        })
+    }
+
+    /// Continues the numbering from `index`, e.g. when a run is restarted.
+    pub fn with_index(self, index: usize) -> SnapshotSeries {
+        SnapshotSeries { index, ..self }
+    }
+
+    /// Writes the snapshots in the given format, which is binary by default.
+    pub fn with_format(self, format: SnapshotFormat) -> SnapshotSeries {
+        SnapshotSeries { format, ..self }
+    }
+
+    /// Returns the path of the snapshot with the given number.
+    pub fn get_path(&self, index: usize) -> PathBuf {
+        self.directory
+            .join(format!("snapshot_{:06}.{}", index, self.format.extension()))
+    }
+
+    /// Writes the snapshot to the next file, whose path is returned.
+    pub fn write(&mut self, snapshot: &Snapshot) -> Result<PathBuf, SnapshotError> {
+        let path: PathBuf = self.get_path(self.index);
+        let file: File = File::create(&path)?;
+        let mut stream: BufWriter<File> = BufWriter::new(file);
+        match self.format {
+            SnapshotFormat::Binary => snapshot.write(&mut stream)?,
+            SnapshotFormat::Csv => snapshot.write_csv(&mut stream)?,
+        }
+        stream.flush()?;
+        self.index += 1;
+        Ok(path)
+    }
+
+    /// Reads the snapshot with the given number.
+    ///
+    /// N.B.: Only the binary snapshots can be read.
+    pub fn read(&self, index: usize) -> Result<Snapshot, SnapshotError> {
+        let file: File = File::open(self.get_path(index))?;
+        let mut stream: BufReader<File> = BufReader::new(file);
+        Snapshot::read(&mut stream)
+    }
+}
+
+#[cfg(test)]
+mod test_snapshot {
+    use super::{Snapshot, SnapshotError, SnapshotFormat, SnapshotSeries};
+    use crate::simulator::{SimulationConfig, Simulator, NDIMS};
+
+    #[test]
+    fn case1() {
+        // the stream restores the snapshot, which is consistent with the simulator
+        let config = SimulationConfig::builder()
+            .lengths([16.; NDIMS])
+            .nparticles(32)
+            .build()
+            .unwrap();
+        let mut simulator = Simulator::new(config).unwrap();
+        simulator.advance_to(2.);
+        let snapshot: Snapshot = simulator.get_snapshot();
+        assert_eq!(snapshot.time, 2.);
+        assert_eq!(snapshot.indices.len(), 32);
+        let particles = simulator.get_particles();
+        assert_eq!(snapshot.indices[5], particles.indices[5]);
+        assert_eq!(snapshot.velocities[5][1], particles.velocities[5][1]);
+        assert_eq!(snapshot.vals[5], particles.vals[5]);
+        let mut buffer = Vec::<u8>::new();
+        snapshot.write(&mut buffer).unwrap();
+        assert_eq!(Snapshot::read(&mut buffer.as_slice()).unwrap(), snapshot);
+        // broken streams are rejected
+        let result = Snapshot::read(&mut b"NOTASNAP".as_slice());
+        assert!(matches!(result, Err(SnapshotError::InvalidMagic)));
+        buffer.truncate(buffer.len() - 1);
+        let result = Snapshot::read(&mut buffer.as_slice());
+        assert!(matches!(result, Err(SnapshotError::Io(_))));
+    }
+
+    #[test]
+    fn case2() {
+        // numbered files in a new directory
+        let directory = std::env::temp_dir().join(format!("snapshots_{}", std::process::id()));
+        let mut series = SnapshotSeries::new(&directory).unwrap();
+        let snapshot = Snapshot {
+            time: 1.,
+            indices: vec![3],
+            radii: vec![0.5],
+            positions: vec![[1.; NDIMS]],
+            velocities: vec![[-1.; NDIMS]],
+            vals: vec![vec![0.25, 0.75]],
+            ..Default::default()
+        };
+        let path = series.write(&snapshot).unwrap();
+        assert_eq!(path, directory.join("snapshot_000000.bin"));
+        series.write(&Snapshot::default()).unwrap();
+        assert_eq!(series.read(0).unwrap(), snapshot);
+        assert_eq!(series.read(1).unwrap(), Snapshot::default());
+        assert!(matches!(series.read(2), Err(SnapshotError::Io(_))));
+        // the text format continues the numbering
+        let mut series = series.with_format(SnapshotFormat::Csv);
+        let path = series.write(&snapshot).unwrap();
+        assert_eq!(path, directory.join("snapshot_000002.csv"));
+        let text: String = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "# time: 1");
+        let row: Vec<&str> = lines[5].split(',').collect();
+        assert_eq!(row.len(), 2 + 2 * NDIMS + 2);
+        assert_eq!(row[..2], ["3", "0.5"]);
+        assert_eq!(row[2 + 2 * NDIMS..], ["0.25", "0.75"]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,522 @@
+use crate::myvec::MyVec;
+use crate::simulator::cell;
+use crate::simulator::lees_edwards::{FLOW_DIM, GRADIENT_DIM};
+use crate::simulator::particle::{find_minimum_distance, Particle, Particles};
+use crate::simulator::{Domain, NDIMS};
+
+/// Reasons why a structural analysis is rejected.
+#[derive(Debug, PartialEq)]
+pub enum AnalysisError {
+    /// Cutoff is not positive, or exceeds half of the length accessible to the particle centres,
+    ///   which is reduced by the radius in the bounded directions.
+    InvalidCutoff(f64),
+    /// Number of bins is zero.
+    InvalidNumberOfBins,
+    /// Maximum wavenumber is not positive or not finite.
+    InvalidWavenumber(f64),
+    /// Range of the velocity histograms is not positive or not finite.
+    InvalidVelocityRange(f64),
+    /// No direction is periodic, and thus no wave vector is allowed.
+    NoPeriodicDirection,
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::InvalidCutoff(val) => write!(
+                f,
+                "cutoff should be positive and not exceed half of the domain: {:+.2e}",
+                val
+            ),
+            AnalysisError::InvalidNumberOfBins => write!(f, "number of bins should be positive"),
+            AnalysisError::InvalidWavenumber(val) => {
+                write!(f, "maximum wavenumber should be positive: {:+.2e}", val)
+            }
+            AnalysisError::InvalidVelocityRange(val) => {
+                write!(f, "maximum velocity should be positive: {:+.2e}", val)
+            }
+            AnalysisError::NoPeriodicDirection => {
+                write!(f, "at least one direction should be periodic")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Radial distribution function g(r) sampled on uniform bins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RadialDistribution {
+    /// Centres of the bins.
+    pub distances: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+/// Static structure factor S(k) averaged over the wave vectors with the same magnitude.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructureFactor {
+    /// Magnitudes of the wave vectors in the ascending order.
+    pub wavenumbers: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+/// Positions of all particles brought to the given time.
+fn get_positions(domain: &Domain, time: f64, particles: &Particles) -> Vec<MyVec> {
+    (0..particles.len())
+        .map(|p: usize| {
+            let acc: MyVec = particles.get_acceleration(domain, p);
+            Particle::get_new_pos(
+                domain,
+                particles.positions[p],
+                particles.velocities[p],
+                acc,
+                time - particles.times[p],
+            )
+        })
+        .collect()
+}
+
+/// Lengths of the region accessible to the particle centres,
+///   which are reduced by the particle radius in the bounded directions.
+fn get_accessible_lengths(domain: &Domain, max_rad: f64) -> [f64; NDIMS] {
+    std::array::from_fn(|dim: usize| {
+        if domain.periodicities[dim] {
+            domain.lengths[dim]
+        } else {
+            domain.lengths[dim] - 2. * max_rad
+        }
+    })
+}
+
+/// Finds the cells whose particles can be closer than `layers` cells to the given cell.
+///
+/// Under the Lees-Edwards boundaries, all cells in the flow direction are visited
+///   across the gradient boundary, since the images are displaced.
+fn get_neighbour_cells(domain: &Domain, layers: &[usize; NDIMS], index: usize) -> Vec<usize> {
+    let ncells: &[usize; NDIMS] = &domain.ncells;
+    let mut indices = [0usize; NDIMS];
+    let mut n: usize = index;
+    for (dim, i) in indices.iter_mut().enumerate() {
+        *i = n % ncells[dim];
+        n /= ncells[dim];
+    }
+    let widths: [usize; NDIMS] = layers.map(|layer: usize| 2 * layer + 1);
+    let mut neighbours = Vec::<usize>::new();
+    'outer: for n in 0..widths.iter().product::<usize>() {
+        let mut neighbour = [0usize; NDIMS];
+        let mut is_crossing: bool = false;
+        let mut n: usize = n;
+        for dim in 0..NDIMS {
+            let offset: isize = (n % widths[dim]) as isize - layers[dim] as isize;
+            n /= widths[dim];
+            let size: isize = ncells[dim] as isize;
+            let mut i: isize = indices[dim] as isize + offset;
+            if i < 0 || size <= i {
+                if !domain.periodicities[dim] {
+                    continue 'outer;
+                }
+                is_crossing |= GRADIENT_DIM == dim;
+                i = i.rem_euclid(size);
+            }
+            neighbour[dim] = i as usize;
+        }
+        if domain.shear_rate.is_some() && is_crossing {
+            for i in 0..ncells[FLOW_DIM] {
+                neighbour[FLOW_DIM] = i;
+                neighbours.push(cell::get_index(NDIMS, ncells, &neighbour));
+            }
+        } else {
+            neighbours.push(cell::get_index(NDIMS, ncells, &neighbour));
+        }
+    }
+    // cells may be visited more than once when the layers wrap around the domain
+    neighbours.sort_unstable();
+    neighbours.dedup();
+    neighbours
+}
+
+/// Integrates the pair density of uniformly-distributed points
+///   over the spherical shell between `min` and `max`,
+///   where the bounded directions reduce the number of pairs separated by a long distance.
+///
+/// For a separation `r`, the fraction of the pairs which fit in the box is the product of
+///   `1 - |r_i| / L_i` over the bounded directions, which is averaged over the solid angle.
+fn integrate_shell(inverses: &[f64; NDIMS], min: f64, max: f64) -> f64 {
+    const PI: f64 = std::f64::consts::PI;
+    // elementary symmetric polynomials of the inverse lengths
+    let mut sums = [1., 0., 0., 0.];
+    for &a in inverses.iter() {
+        for k in (1..sums.len()).rev() {
+            sums[k] += sums[k - 1] * a;
+        }
+    }
+    // coefficients of r^{NDIMS - 1} A(r),
+    //   where A(r) is the integral of the fraction over the solid angle
+    let coefs: Vec<f64> = if 2 == NDIMS {
+        vec![0., 2. * PI, -4. * sums[1], 2. * sums[2]]
+    } else {
+        vec![
+            0.,
+            0.,
+            4. * PI,
+            -2. * PI * sums[1],
+            8. / 3. * sums[2],
+            -sums[3],
+        ]
+    };
+    coefs
+        .iter()
+        .enumerate()
+        .map(|(k, &coef)| coef * (max.powi(k as i32 + 1) - min.powi(k as i32 + 1)) / (k + 1) as f64)
+        .sum()
+}
+
+/// Counts the pairs separated by less than `cutoff` on `nbins` uniform bins,
+///   visiting the cells within the cutoff from the one containing each particle.
+fn count_pairs(
+    domain: &Domain,
+    time: f64,
+    positions: &[MyVec],
+    cutoff: f64,
+    nbins: usize,
+) -> Vec<usize> {
+    let ncells: &[usize; NDIMS] = &domain.ncells;
+    let cell_sizes: [f64; NDIMS] =
+        std::array::from_fn(|dim: usize| domain.lengths[dim] / ncells[dim] as f64);
+    let layers: [usize; NDIMS] =
+        std::array::from_fn(|dim: usize| (cutoff / cell_sizes[dim]).ceil() as usize);
+    // particles binned into the cells containing their centres
+    let mut bins = vec![Vec::<usize>::new(); ncells.iter().product()];
+    for (n, pos) in positions.iter().enumerate() {
+        let indices: [usize; NDIMS] = std::array::from_fn(|dim: usize| {
+            ((pos[dim] / cell_sizes[dim]).max(0.) as usize).min(ncells[dim] - 1)
+        });
+        bins[cell::get_index(NDIMS, ncells, &indices)].push(n);
+    }
+    // count each pair once
+    let width: f64 = cutoff / nbins as f64;
+    let mut counts = vec![0usize; nbins];
+    for (index, bin) in bins.iter().enumerate() {
+        if bin.is_empty() {
+            continue;
+        }
+        let neighbours: Vec<usize> = get_neighbour_cells(domain, &layers, index);
+        for &n in bin.iter() {
+            for &neighbour in neighbours.iter() {
+                for &m in bins[neighbour].iter() {
+                    if m <= n {
+                        continue;
+                    }
+                    let dist: f64 = find_minimum_distance(domain, time, positions[n], positions[m]);
+                    if dist < cutoff {
+                        counts[((dist / width) as usize).min(nbins - 1)] += 1;
+                    }
+                }
+            }
+        }
+    }
+    counts
+}
+
+/// Computes g(r) up to `cutoff`, where the pairs are found by the cell grid,
+///   and the minimum images are considered in the periodic directions.
+///
+/// The normalisation takes into account the walls,
+///   assuming the particle centres are distributed in the region accessible to the largest one.
+pub fn get_radial_distribution(
+    domain: &Domain,
+    time: f64,
+    particles: &Particles,
+    cutoff: f64,
+    nbins: usize,
+) -> Result<RadialDistribution, AnalysisError> {
+    if 0 == nbins {
+        return Err(AnalysisError::InvalidNumberOfBins);
+    }
+    let max_rad: f64 = particles
+        .radii
+        .iter()
+        .fold(0., |max: f64, &rad: &f64| max.max(rad));
+    let lengths: [f64; NDIMS] = get_accessible_lengths(domain, max_rad);
+    let max_cutoff: f64 = 0.5
+        * lengths
+            .iter()
+            .fold(f64::MAX, |min: f64, &l: &f64| min.min(l));
+    if !(0. < cutoff && cutoff <= max_cutoff) {
+        return Err(AnalysisError::InvalidCutoff(cutoff));
+    }
+    let positions: Vec<MyVec> = get_positions(domain, time, particles);
+    let counts: Vec<usize> = count_pairs(domain, time, &positions, cutoff, nbins);
+    let width: f64 = cutoff / nbins as f64;
+    // normalised by the pairs of the uniformly-distributed points
+    let nitems: f64 = particles.len() as f64;
+    let volume: f64 = lengths.iter().product();
+    let npairs: f64 = 0.5 * nitems * (nitems - 1.);
+    let inverses: [f64; NDIMS] = std::array::from_fn(|dim: usize| {
+        if domain.periodicities[dim] {
+            0.
+        } else {
+            1. / lengths[dim]
+        }
+    });
+    let mut rdf = RadialDistribution::default();
+    for (k, &count) in counts.iter().enumerate() {
+        let min: f64 = width * k as f64;
+        let max: f64 = width * (k + 1) as f64;
+        let expected: f64 = npairs / volume * integrate_shell(&inverses, min, max);
+        rdf.distances.push(0.5 * (min + max));
+        rdf.values.push(if 0. < expected {
+            count as f64 / expected
+        } else {
+            0.
+        });
+    }
+    Ok(rdf)
+}
+
+/// Computes S(k) = |sum_j exp(i k x_j)|^2 / N for the wave vectors allowed by the periodicity,
+///   whose magnitudes do not exceed `max_wavenumber`.
+///
+/// The components in the bounded directions are zero.
+pub fn get_structure_factor(
+    domain: &Domain,
+    time: f64,
+    particles: &Particles,
+    max_wavenumber: f64,
+) -> Result<StructureFactor, AnalysisError> {
+    const PI: f64 = std::f64::consts::PI;
+    if !(max_wavenumber.is_finite() && 0. < max_wavenumber) {
+        return Err(AnalysisError::InvalidWavenumber(max_wavenumber));
+    }
+    if !domain.periodicities.iter().any(|&periodicity| periodicity) {
+        return Err(AnalysisError::NoPeriodicDirection);
+    }
+    // unit wavenumbers and the largest indices in each direction
+    let units: [f64; NDIMS] = std::array::from_fn(|dim: usize| 2. * PI / domain.lengths[dim]);
+    let nmaxs: [usize; NDIMS] = std::array::from_fn(|dim: usize| {
+        if domain.periodicities[dim] {
+            (max_wavenumber / units[dim]) as usize
+        } else {
+            0
+        }
+    });
+    let positions: Vec<MyVec> = get_positions(domain, time, particles);
+    let nitems: usize = positions.len();
+    // S(k) = S(-k), and thus only the first non-zero component is taken to be positive
+    let widths: [usize; NDIMS] = nmaxs.map(|nmax: usize| 2 * nmax + 1);
+    let mut samples = Vec::<(f64, f64)>::new();
+    for n in 0..widths.iter().product::<usize>() {
+        let mut wavevector = MyVec::new([0.; NDIMS]);
+        let mut indices = [0isize; NDIMS];
+        let mut n: usize = n;
+        for dim in 0..NDIMS {
+            indices[dim] = (n % widths[dim]) as isize - nmaxs[dim] as isize;
+            n /= widths[dim];
+            wavevector[dim] = units[dim] * indices[dim] as f64;
+        }
+        match indices.iter().find(|&&index| 0 != index) {
+            Some(&index) if 0 < index => {}
+            _ => continue,
+        }
+        let wavenumber: f64 = (wavevector * wavevector).sqrt();
+        if max_wavenumber < wavenumber {
+            continue;
+        }
+        let (re, im): (f64, f64) = positions.iter().fold((0., 0.), |(re, im), pos| {
+            let phase: f64 = wavevector * *pos;
+            (re + phase.cos(), im + phase.sin())
+        });
+        samples.push((wavenumber, (re * re + im * im) / nitems as f64));
+    }
+    // average over the wave vectors with the same magnitude
+    samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut factor = StructureFactor::default();
+    let mut nsamples: usize = 0;
+    for (wavenumber, value) in samples.into_iter() {
+        match factor.wavenumbers.last() {
+            Some(&last) if wavenumber - last <= 1e-12 * wavenumber => {
+                *factor.values.last_mut().unwrap() += value;
+                nsamples += 1;
+            }
+            _ => {
+                if let Some(last) = factor.values.last_mut() {
+                    *last /= nsamples as f64;
+                }
+                factor.wavenumbers.push(wavenumber);
+                factor.values.push(value);
+                nsamples = 1;
+            }
+        }
+    }
+    if let Some(last) = factor.values.last_mut() {
+        *last /= nsamples as f64;
+    }
+    Ok(factor)
+}
+
+#[cfg(test)]
+mod test_structure {
+    use super::{count_pairs, AnalysisError};
+    use crate::myvec::{MyVec, PseudoVec};
+    use crate::random::Random;
+    use crate::simulator::boundary::get_periodic_boundaries;
+    use crate::simulator::particle::{
+        find_minimum_distance, FreeFlights, Images, Particle, Particles,
+    };
+    use crate::simulator::{
+        Boundary, Domain, Extrema, ScalarCondition, SimulationConfig, SimulationConfigBuilder,
+        NDIMS,
+    };
+
+    const LENGTH: f64 = 16.;
+    const RAD: f64 = 0.01;
+
+    fn init_builder() -> SimulationConfigBuilder {
+        SimulationConfig::builder()
+            .lengths([LENGTH; NDIMS])
+            .radius(RAD)
+            .buoyancy(None)
+    }
+
+    fn init_particles(positions: &[MyVec]) -> Particles {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(index, &pos)| Particle {
+                index,
+                rad: RAD,
+                mass: 1.,
+                pos,
+                vel: MyVec::new([0.; NDIMS]),
+                angvel: PseudoVec::default(),
+                time: 0.,
+                vals: Vec::new(),
+                flights: FreeFlights::default(),
+                images: Images::default(),
+            })
+            .collect()
+    }
+
+    fn init_positions(nitems: usize) -> Vec<MyVec> {
+        let mut rng = Random::new(42);
+        (0..nitems)
+            .map(|_| {
+                MyVec::new(std::array::from_fn(|_| {
+                    rng.gen_range(2. * RAD, LENGTH - 2. * RAD)
+                }))
+            })
+            .collect()
+    }
+
+    fn count_all_pairs(
+        domain: &Domain,
+        time: f64,
+        positions: &[MyVec],
+        cutoff: f64,
+        nbins: usize,
+    ) -> Vec<usize> {
+        let mut counts = vec![0usize; nbins];
+        for (n, &pos0) in positions.iter().enumerate() {
+            for &pos1 in positions[n + 1..].iter() {
+                let dist: f64 = find_minimum_distance(domain, time, pos0, pos1);
+                if dist < cutoff {
+                    counts[(dist / (cutoff / nbins as f64)) as usize] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn case1() {
+        // the pairs found by the cell grid are identical to the ones found by brute force,
+        //   with walls, periodic images and sliding images
+        let positions: Vec<MyVec> = init_positions(400);
+        let builders = [
+            init_builder(),
+            init_builder().boundaries(get_periodic_boundaries()),
+            init_builder()
+                .boundary(0, Boundary::Periodic, Boundary::Periodic)
+                .boundary(1, Boundary::Periodic, Boundary::Periodic)
+                .lees_edwards(0.3),
+        ];
+        for builder in builders.into_iter() {
+            let domain = Domain::new(&builder.build().unwrap());
+            for cutoff in [1., 4., 7.5] {
+                assert_eq!(
+                    count_pairs(&domain, 5., &positions, cutoff, 20),
+                    count_all_pairs(&domain, 5., &positions, cutoff, 20),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn case2() {
+        // uniformly-distributed points give g = 1 with and without walls
+        let particles = init_particles(&init_positions(4000));
+        let periodic = get_periodic_boundaries();
+        let walls: [Extrema<Boundary>; NDIMS] =
+            std::array::from_fn(|_| Extrema::both(Boundary::Specular(ScalarCondition::Neumann)));
+        for boundaries in [periodic, walls] {
+            let domain = Domain::new(&init_builder().boundaries(boundaries).build().unwrap());
+            let rdf = super::get_radial_distribution(&domain, 0., &particles, 7.5, 5).unwrap();
+            assert_eq!(rdf.distances, vec![0.75, 2.25, 3.75, 5.25, 6.75]);
+            // the shortest bin contains few pairs
+            for &value in rdf.values[1..].iter() {
+                assert!((value - 1.).abs() < 0.05, "{:?}", rdf.values);
+            }
+        }
+    }
+
+    #[test]
+    fn case3() {
+        // a square lattice gives Bragg peaks, and no correlation at the longest wavelength
+        let nitems: usize = 8;
+        let positions: Vec<MyVec> = (0..nitems.pow(NDIMS as u32))
+            .map(|n: usize| {
+                MyVec::new(std::array::from_fn(|dim: usize| {
+                    (n / nitems.pow(dim as u32) % nitems) as f64 * 2. + 1.
+                }))
+            })
+            .collect();
+        let particles = init_particles(&positions);
+        let domain = Domain::new(&init_builder().build().unwrap());
+        let factor = super::get_structure_factor(&domain, 0., &particles, 3.15).unwrap();
+        // only the periodic directions are considered
+        let unit: f64 = 2. * std::f64::consts::PI / LENGTH;
+        assert!((factor.wavenumbers[0] - unit).abs() < 1e-12);
+        assert!(factor.values[0] < 1e-20);
+        let last: usize = factor.wavenumbers.len() - 1;
+        assert!((factor.wavenumbers[last] - std::f64::consts::PI).abs() < 1e-12);
+        let peak: f64 = factor.values[last] / positions.len() as f64;
+        assert!((peak - 1.).abs() < 1e-10);
+    }
+
+    #[test]
+    fn case4() {
+        // invalid arguments are rejected
+        let particles = init_particles(&init_positions(10));
+        let domain = Domain::new(&init_builder().build().unwrap());
+        let func = super::get_radial_distribution;
+        assert_eq!(
+            func(&domain, 0., &particles, 8.5, 10),
+            Err(AnalysisError::InvalidCutoff(8.5))
+        );
+        assert_eq!(
+            func(&domain, 0., &particles, 0., 10),
+            Err(AnalysisError::InvalidCutoff(0.))
+        );
+        assert_eq!(
+            func(&domain, 0., &particles, 1., 0),
+            Err(AnalysisError::InvalidNumberOfBins)
+        );
+        let walls: [Extrema<Boundary>; NDIMS] =
+            std::array::from_fn(|_| Extrema::both(Boundary::Specular(ScalarCondition::Neumann)));
+        let domain = Domain::new(&init_builder().boundaries(walls).build().unwrap());
+        assert_eq!(
+            super::get_structure_factor(&domain, 0., &particles, 1.),
+            Err(AnalysisError::NoPeriodicDirection)
+        );
+    }
+}
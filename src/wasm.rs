@@ -0,0 +1,670 @@
+use wasm_bindgen::prelude::*;
+
+use crate::simulator::{
+    Binning, Boundary, Buoyancy, Conductive, Diffusion, EventCounts, Fields, InitialCondition,
+    InitialConditionError, Observables, Passive, Placement, RoughSphere, ScalarCondition,
+    SimulationConfig, SimulationConfigBuilder, Simulator, SizeDistribution, Stratification,
+    VelocityDistribution, WallMotion, NDIMS,
+};
+
+/// Parameters passed to [`Collision`], built from the JavaScript side.
+#[wasm_bindgen]
+pub struct CollisionConfig {
+    builder: SimulationConfigBuilder,
+}
+
+#[wasm_bindgen]
+impl CollisionConfig {
+    /// `seed` is a `u32`, which is a plain number rather than a `BigInt` in JavaScript.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, height: f64, nitems: usize, rate: f64, seed: u32) -> CollisionConfig {
+        let builder = SimulationConfig::builder()
+            .lengths([width, height])
+            .nparticles(nitems)
+            .sync_rate(rate)
+            .seed(u64::from(seed));
+        CollisionConfig { builder }
+    }
+
+    pub fn set_radius(self, radius: f64) -> CollisionConfig {
+        let builder = self.builder.radius(radius);
+        CollisionConfig { builder }
+    }
+
+    pub fn set_bidisperse(self, small: f64, large: f64, fraction: f64) -> CollisionConfig {
+        let builder = self
+            .builder
+            .size_distribution(SizeDistribution::Bidisperse {
+                small,
+                large,
+                fraction,
+            });
+        CollisionConfig { builder }
+    }
+
+    pub fn set_lognormal(self, median: f64, sigma: f64, max: f64) -> CollisionConfig {
+        let builder =
+            self.builder
+                .size_distribution(SizeDistribution::Lognormal { median, sigma, max });
+        CollisionConfig { builder }
+    }
+
+    pub fn set_cell_size(self, cell_size: f64) -> CollisionConfig {
+        let builder = self.builder.cell_size(cell_size);
+        CollisionConfig { builder }
+    }
+
+    pub fn set_restitution_coefficient(self, restitution_coefficient: f64) -> CollisionConfig {
+        let builder = self
+            .builder
+            .restitution_coefficient(restitution_coefficient);
+        CollisionConfig { builder }
+    }
+
+    /// Appends a scalar which is exchanged on contacts with the given coefficient.
+    pub fn add_conductive_scalar(self, coefficient: f64) -> CollisionConfig {
+        let builder = self.builder.add_scalar(Conductive { coefficient });
+        CollisionConfig { builder }
+    }
+
+    /// Appends a scalar which is never exchanged.
+    pub fn add_passive_scalar(self) -> CollisionConfig {
+        let builder = self.builder.add_scalar(Passive);
+        CollisionConfig { builder }
+    }
+
+    /// Rough particles exchanging the angular momentum as well.
+    pub fn set_rough_sphere(self, normal: f64, tangential: f64) -> CollisionConfig {
+        let builder = self
+            .builder
+            .collision_model(RoughSphere { normal, tangential });
+        CollisionConfig { builder }
+    }
+
+    pub fn set_gravity(self, x: f64, y: f64) -> CollisionConfig {
+        let builder = self.builder.gravity([x, y]);
+        CollisionConfig { builder }
+    }
+
+    /// Imposes the buoyancy `(val - reference) * (x, y)` in addition to the gravity.
+    pub fn set_buoyancy(self, x: f64, y: f64, reference: f64) -> CollisionConfig {
+        let buoyancy = Buoyancy {
+            acceleration: [x, y],
+            reference,
+        };
+        let builder = self.builder.buoyancy(Some(buoyancy));
+        CollisionConfig { builder }
+    }
+
+    /// Decouples the particle motion from the scalar.
+    pub fn unset_buoyancy(self) -> CollisionConfig {
+        let builder = self.builder.buoyancy(None);
+        CollisionConfig { builder }
+    }
+
+    pub fn set_max_volume_fraction(self, max_volume_fraction: f64) -> CollisionConfig {
+        let builder = self.builder.max_volume_fraction(max_volume_fraction);
+        CollisionConfig { builder }
+    }
+
+    /// Places the particles on the square lattice.
+    pub fn set_square_lattice(self) -> CollisionConfig {
+        let builder = self.builder.placement(Placement::SquareLattice);
+        CollisionConfig { builder }
+    }
+
+    /// Places the particles on the hexagonal lattice.
+    pub fn set_hexagonal_lattice(self) -> CollisionConfig {
+        let builder = self.builder.placement(Placement::HexagonalLattice);
+        CollisionConfig { builder }
+    }
+
+    /// Places the particles randomly in an expanded domain, which is then compressed.
+    pub fn set_compression(self) -> CollisionConfig {
+        let builder = self.builder.placement(Placement::Compression);
+        CollisionConfig { builder }
+    }
+
+    /// Draws the initial velocities from the Maxwellian at the given temperature.
+    pub fn set_maxwellian(self, temperature: f64) -> CollisionConfig {
+        let builder = self
+            .builder
+            .velocity_distribution(VelocityDistribution::Maxwellian { temperature });
+        CollisionConfig { builder }
+    }
+
+    /// Initial scalars varying linearly from `min` to `max` in `dim`.
+    pub fn set_linear_stratification(self, dim: usize, min: f64, max: f64) -> CollisionConfig {
+        let builder = self
+            .builder
+            .stratification(Stratification::Linear { dim, min, max });
+        CollisionConfig { builder }
+    }
+
+    /// Initial scalars being `min` (`max`) below (above) `fraction` of the domain in `dim`.
+    pub fn set_step_stratification(
+        self,
+        dim: usize,
+        fraction: f64,
+        min: f64,
+        max: f64,
+    ) -> CollisionConfig {
+        let builder = self.builder.stratification(Stratification::Step {
+            dim,
+            fraction,
+            min,
+            max,
+        });
+        CollisionConfig { builder }
+    }
+
+    /// Imposes boundary conditions on the negative and positive faces in `dim`.
+    pub fn set_boundary(
+        self,
+        dim: usize,
+        min: BoundaryCondition,
+        max: BoundaryCondition,
+    ) -> CollisionConfig {
+        let builder = self.builder.boundary(dim, min.boundary, max.boundary);
+        CollisionConfig { builder }
+    }
+
+    /// Moves the walls on the negative and positive faces in `dim`.
+    pub fn set_wall_motion(
+        self,
+        dim: usize,
+        min: WallMovement,
+        max: WallMovement,
+    ) -> CollisionConfig {
+        let builder = self.builder.wall_motion(dim, min.motion, max.motion);
+        CollisionConfig { builder }
+    }
+
+    /// Imposes a simple shear flow by the Lees-Edwards boundaries,
+    ///   which require periodic boundaries in both directions.
+    pub fn set_lees_edwards(self, shear_rate: f64) -> CollisionConfig {
+        let builder = self.builder.lees_edwards(shear_rate);
+        CollisionConfig { builder }
+    }
+}
+
+/// Condition on a face of the domain, built from the JavaScript side.
+///
+/// The scalar condition of a wall is Neumann if `values` are not given,
+///   and otherwise Dirichlet with one wall value for each scalar.
+#[wasm_bindgen]
+pub struct BoundaryCondition {
+    boundary: Boundary,
+}
+
+#[wasm_bindgen]
+impl BoundaryCondition {
+    pub fn periodic() -> BoundaryCondition {
+        let boundary = Boundary::Periodic;
+        BoundaryCondition { boundary }
+    }
+
+    pub fn specular(values: Option<Vec<f64>>) -> BoundaryCondition {
+        let boundary = Boundary::Specular(to_scalar_condition(values));
+        BoundaryCondition { boundary }
+    }
+
+    pub fn diffuse(temperature: f64, values: Option<Vec<f64>>) -> BoundaryCondition {
+        let boundary = Boundary::Diffuse {
+            temperature,
+            scalar: to_scalar_condition(values),
+        };
+        BoundaryCondition { boundary }
+    }
+
+    pub fn outflow() -> BoundaryCondition {
+        let boundary = Boundary::Outflow;
+        BoundaryCondition { boundary }
+    }
+}
+
+/// Motion of a wall, built from the JavaScript side.
+#[wasm_bindgen]
+pub struct WallMovement {
+    motion: WallMotion,
+}
+
+#[wasm_bindgen]
+impl WallMovement {
+    pub fn stationary() -> WallMovement {
+        let motion = WallMotion::default();
+        WallMovement { motion }
+    }
+
+    /// Tangential velocity is given to `tangential_dim`,
+    ///   while the wall oscillates in the direction given to [`CollisionConfig::set_wall_motion`],
+    ///   which should differ from each other.
+    pub fn new(
+        tangential_dim: usize,
+        velocity: f64,
+        amplitude: f64,
+        angular_frequency: f64,
+    ) -> Result<WallMovement, JsValue> {
+        let mut motion = WallMotion {
+            amplitude,
+            angular_frequency,
+            ..WallMotion::default()
+        };
+        match motion.velocity.get_mut(tangential_dim) {
+            Some(component) => *component = velocity,
+            None => {
+                let error = format!("tangential direction {} does not exist", tangential_dim);
+                return Err(JsValue::from_str(&error));
+            }
+        }
+        Ok(WallMovement { motion })
+    }
+}
+
+fn to_scalar_condition(values: Option<Vec<f64>>) -> ScalarCondition {
+    match values {
+        Some(values) => ScalarCondition::Dirichlet(values),
+        None => ScalarCondition::Neumann,
+    }
+}
+
+#[wasm_bindgen]
+pub struct Collision {
+    simulator: crate::simulator::Simulator,
+    /// Positions in single precision for WebGL, which has no double-precision attributes.
+    positions_f32: Vec<f32>,
+    temperatures: Vec<f32>,
+    radii: Vec<f32>,
+    /// Numbers of the collisions experienced by each particle.
+    collision_counts: Vec<u32>,
+    /// Index of the scalar stored in `temperatures`.
+    scalar: usize,
+}
+
+#[wasm_bindgen]
+impl Collision {
+    /// Builds with the default parameters,
+    ///   where `nitems` is reduced to the particles which can be placed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, height: f64, nitems: usize, rate: f64, seed: u32) -> Collision {
+        let config = CollisionConfig::new(width, height, nitems, rate, seed);
+        let simulator = match config.builder.clone().build().map(Simulator::new) {
+            Ok(Err(InitialConditionError::Shortfall(_, nplaced))) => {
+                let config = CollisionConfig::new(width, height, nplaced, rate, seed);
+                config.builder.build().map(Simulator::new)
+            }
+            result => result,
+        };
+        match simulator {
+            Ok(Ok(simulator)) => Collision::wrap(simulator),
+            Ok(Err(error)) => panic!("{}", error),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn from_config(config: CollisionConfig) -> Result<Collision, JsValue> {
+        let config: SimulationConfig = match config.builder.build() {
+            Ok(config) => config,
+            Err(error) => return Err(JsValue::from_str(&error.to_string())),
+        };
+        match Simulator::new(config) {
+            Ok(simulator) => Ok(Collision::wrap(simulator)),
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Builds from the particles given explicitly,
+    ///   where the positions and the velocities are interleaved (`x0, y0, x1, y1, ...`),
+    ///   and so are the scalars of the particles (one for each scalar transport model).
+    pub fn from_initial_condition(
+        config: CollisionConfig,
+        positions: Vec<f64>,
+        velocities: Vec<f64>,
+        radii: Vec<f64>,
+        vals: Vec<f64>,
+    ) -> Result<Collision, JsValue> {
+        let config: SimulationConfig = match config.builder.build() {
+            Ok(config) => config,
+            Err(error) => return Err(JsValue::from_str(&error.to_string())),
+        };
+        let nscalars: usize = config.scalar_transports().len();
+        let nitems: usize = radii.len();
+        if NDIMS * nitems != positions.len()
+            || NDIMS * nitems != velocities.len()
+            || nscalars * nitems != vals.len()
+        {
+            let error = InitialConditionError::InconsistentLength;
+            return Err(JsValue::from_str(&error.to_string()));
+        }
+        let to_array = |chunk: &[f64]| -> [f64; NDIMS] { [chunk[0], chunk[1]] };
+        let initial_condition = InitialCondition {
+            positions: positions.chunks_exact(NDIMS).map(to_array).collect(),
+            velocities: velocities.chunks_exact(NDIMS).map(to_array).collect(),
+            radii,
+            vals: (0..nitems)
+                .map(|index: usize| vals[nscalars * index..nscalars * (index + 1)].to_vec())
+                .collect(),
+        };
+        match Simulator::from_initial_condition(config, &initial_condition) {
+            Ok(simulator) => Ok(Collision::wrap(simulator)),
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Returns the largest particle radius.
+    pub fn radius(&self) -> f64 {
+        self.simulator.get_config().max_radius()
+    }
+
+    pub fn radii(&self) -> *const f32 {
+        self.radii.as_ptr()
+    }
+
+    /// Returns the number of particles, which decreases when outflow boundaries are imposed.
+    pub fn nitems(&self) -> usize {
+        self.simulator.get_particles().len()
+    }
+
+    /// Returns the positions stored in the simulator (`x0, y0, x1, y1, ...` in `f64`),
+    ///   which are valid until the next update.
+    pub fn positions(&self) -> *const f64 {
+        self.simulator.get_particles().positions.as_ptr() as *const f64
+    }
+
+    /// Returns the positions in `f32` (`x0, y0, x1, y1, ...`), which are filled by each update.
+    pub fn positions_f32(&self) -> *const f32 {
+        self.positions_f32.as_ptr()
+    }
+
+    pub fn temperatures(&self) -> *const f32 {
+        self.temperatures.as_ptr()
+    }
+
+    /// Returns the number of scalars carried by each particle.
+    pub fn nscalars(&self) -> usize {
+        self.simulator.get_config().scalar_transports().len()
+    }
+
+    /// Chooses the scalar exposed by `temperatures` from the next update.
+    pub fn set_scalar(&mut self, scalar: usize) -> Result<(), JsValue> {
+        if self.nscalars() <= scalar {
+            return Err(JsValue::from_str(&format!(
+                "scalar {} does not exist",
+                scalar
+            )));
+        }
+        self.scalar = scalar;
+        Ok(())
+    }
+
+    /// Returns the number of the collisions between two particles since the beginning.
+    pub fn ncollisions(&self) -> usize {
+        self.simulator.get_event_counts().inter_particle_collision
+    }
+
+    /// Returns the number of the particles hitting the walls since the beginning.
+    pub fn nwall_reflections(&self) -> usize {
+        self.simulator.get_event_counts().wall_reflection
+    }
+
+    /// Returns the number of the particles moving between the cells since the beginning.
+    pub fn ncell_transfers(&self) -> usize {
+        let counts: EventCounts = self.simulator.get_event_counts();
+        counts.move_to_neighbour + counts.move_out_of_cell
+    }
+
+    /// Returns the number of the updates since the beginning.
+    pub fn nsynchronisations(&self) -> usize {
+        self.simulator.get_event_counts().synchronisation
+    }
+
+    /// Returns the number of collisions per particle per unit time.
+    pub fn collision_frequency(&self) -> Option<f64> {
+        self.simulator.get_collision_frequency()
+    }
+
+    /// Returns the mean duration of the flights between two successive collisions.
+    pub fn mean_free_time(&self) -> Option<f64> {
+        self.simulator.get_mean_free_time()
+    }
+
+    /// Returns the mean length of the flights between two successive collisions.
+    pub fn mean_free_path(&self) -> Option<f64> {
+        self.simulator.get_mean_free_path()
+    }
+
+    /// Returns the numbers of the collisions experienced by each particle.
+    pub fn collision_counts(&self) -> *const u32 {
+        self.collision_counts.as_ptr()
+    }
+
+    /// Returns the total translational kinetic energy at the latest update.
+    pub fn kinetic_energy(&self) -> Option<f64> {
+        self.simulator
+            .get_observables()
+            .map(|observables: &Observables| observables.kinetic_energy)
+    }
+
+    /// Returns the granular temperature at the latest update.
+    pub fn granular_temperature(&self) -> Option<f64> {
+        self.simulator
+            .get_observables()
+            .map(|observables: &Observables| observables.temperature)
+    }
+
+    /// Returns the net momentum at the latest update.
+    pub fn momentum(&self) -> Option<Vec<f64>> {
+        self.simulator
+            .get_observables()
+            .map(|observables: &Observables| observables.momentum.to_vec())
+    }
+
+    /// Returns the mean and the variance of the scalar chosen by `set_scalar` at the latest update,
+    ///   which are not available when the particles carry no scalar.
+    pub fn scalar_statistics(&self) -> Option<Vec<f64>> {
+        let observables: &Observables = self.simulator.get_observables()?;
+        let mean: f64 = *observables.val_means.get(self.scalar)?;
+        let variance: f64 = *observables.val_variances.get(self.scalar)?;
+        Some(vec![mean, variance])
+    }
+
+    /// Returns the kinetic and the collisional pressure tensors at the latest update,
+    ///   which are concatenated in the row-major order.
+    pub fn pressure_tensors(&self) -> Option<Vec<f64>> {
+        self.simulator
+            .get_observables()
+            .map(|observables: &Observables| {
+                let tensors = [
+                    &observables.kinetic_pressure,
+                    &observables.collisional_pressure,
+                ];
+                tensors
+                    .iter()
+                    .flat_map(|tensor| tensor.iter().flatten())
+                    .copied()
+                    .collect()
+            })
+    }
+
+    /// Returns the pressure, the shear stress and the flux of the scalar chosen by `set_scalar`
+    ///   on the negative and the positive walls normal to `dim`, which are concatenated.
+    pub fn wall_statistics(&self, dim: usize) -> Option<Vec<f64>> {
+        let statistics = self.simulator.get_wall_statistics(dim)?;
+        let mut items = Vec::<f64>::new();
+        for wall in [&statistics.min, &statistics.max] {
+            items.push(wall.pressure);
+            items.extend_from_slice(&wall.shear_stress);
+            items.push(wall.scalar_fluxes.get(self.scalar).copied().unwrap_or(0.));
+        }
+        Some(items)
+    }
+
+    /// Returns the Nusselt number of the walls normal to `dim` for the scalar chosen by `set_scalar`,
+    ///   relative to the flux of the purely conductive state.
+    pub fn nusselt_number(&self, dim: usize, conductive_flux: f64) -> Option<f64> {
+        self.simulator
+            .get_nusselt_number(dim, self.scalar, conductive_flux)
+    }
+
+    /// Restarts the averaging of the wall exchanges.
+    pub fn reset_wall_statistics(&mut self) {
+        self.simulator.reset_wall_statistics();
+    }
+
+    /// Starts sampling the mean-squared displacement and the velocity autocorrelation
+    ///   at each update for `nlags` lags, placing a new time origin every `interval` updates.
+    pub fn set_diffusion(&mut self, nlags: usize, interval: usize) -> Result<(), JsValue> {
+        match Diffusion::new(nlags, interval) {
+            Ok(diffusion) => {
+                self.simulator.set_diffusion(Some(diffusion));
+                Ok(())
+            }
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Returns the lag times, the mean-squared displacements and the velocity autocorrelations,
+    ///   which are interleaved.
+    pub fn diffusion(&self) -> Option<Vec<f64>> {
+        let diffusion = self.simulator.get_diffusion()?;
+        let times: Vec<f64> = diffusion.get_lag_times();
+        let msd: Vec<f64> = diffusion.get_msd();
+        let vacf: Vec<f64> = diffusion.get_vacf();
+        Some(
+            (0..times.len())
+                .flat_map(|lag: usize| [times[lag], msd[lag], vacf[lag]])
+                .collect(),
+        )
+    }
+
+    /// Returns the diffusion coefficient given by the Einstein relation at the longest lag.
+    pub fn diffusion_coefficient(&self) -> Option<f64> {
+        self.simulator.get_diffusion()?.get_diffusion_coefficient()
+    }
+
+    /// Starts sampling the Eulerian averages on `nx` x `ny` bins at each update,
+    ///   with the velocity histograms of `nvelbins` bins between `-max_velocity` and `max_velocity`.
+    pub fn set_binning(
+        &mut self,
+        nx: usize,
+        ny: usize,
+        nvelbins: usize,
+        max_velocity: f64,
+    ) -> Result<(), JsValue> {
+        match Binning::new([nx, ny], nvelbins, max_velocity) {
+            Ok(binning) => {
+                self.simulator.set_binning(Some(binning));
+                Ok(())
+            }
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Discards the samples of the Eulerian averages.
+    pub fn reset_binning(&mut self) {
+        self.simulator.reset_binning();
+    }
+
+    /// Returns the density, the velocity, the temperature and the scalar chosen by `set_scalar`
+    ///   averaged in each bin, which are interleaved with the first direction varying fastest.
+    ///
+    /// N.B.: The scalar is zero when the particles carry no scalar.
+    pub fn field_averages(&self) -> Option<Vec<f64>> {
+        let fields: &Fields = self.simulator.get_binning()?.get_averages();
+        let mut items = Vec::<f64>::new();
+        for n in 0..fields.densities.len() {
+            items.push(fields.densities[n]);
+            items.extend_from_slice(&fields.velocities[n]);
+            items.push(fields.temperatures[n]);
+            items.push(fields.vals[n].get(self.scalar).copied().unwrap_or(0.));
+        }
+        Some(items)
+    }
+
+    /// Returns the probability density functions of the velocity components,
+    ///   which are concatenated in the order of the directions.
+    pub fn velocity_pdfs(&self) -> Option<Vec<f64>> {
+        let binning = self.simulator.get_binning()?;
+        Some(binning.get_velocity_pdfs().concat())
+    }
+
+    /// Returns the radial distribution function up to `cutoff` on `nbins` uniform bins,
+    ///   where the centres of the bins and the values are interleaved.
+    pub fn radial_distribution(&self, cutoff: f64, nbins: usize) -> Result<Vec<f64>, JsValue> {
+        match self.simulator.get_radial_distribution(cutoff, nbins) {
+            Ok(rdf) => Ok(rdf
+                .distances
+                .iter()
+                .zip(rdf.values.iter())
+                .flat_map(|(&distance, &value)| [distance, value])
+                .collect()),
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Returns the static structure factor up to `max_wavenumber`,
+    ///   where the wavenumbers and the values are interleaved.
+    pub fn structure_factor(&self, max_wavenumber: f64) -> Result<Vec<f64>, JsValue> {
+        match self.simulator.get_structure_factor(max_wavenumber) {
+            Ok(factor) => Ok(factor
+                .wavenumbers
+                .iter()
+                .zip(factor.values.iter())
+                .flat_map(|(&wavenumber, &value)| [wavenumber, value])
+                .collect()),
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    /// Changes the time between two updates from the next update.
+    pub fn set_sync_rate(&mut self, rate: f64) -> Result<(), JsValue> {
+        match self.simulator.set_sync_rate(rate) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(JsValue::from_str(&error.to_string())),
+        }
+    }
+
+    pub fn update(&mut self) {
+        use crate::simulator::Particles;
+        self.simulator.integrate();
+        let particles: &Particles = self.simulator.get_particles();
+        let positions_f32: &mut [f32] = &mut self.positions_f32;
+        let temperatures: &mut [f32] = &mut self.temperatures;
+        let radii: &mut [f32] = &mut self.radii;
+        let collision_counts: &mut [u32] = &mut self.collision_counts;
+        for index in 0..particles.len() {
+            for dim in 0..NDIMS {
+                positions_f32[NDIMS * index + dim] = particles.positions[index][dim] as f32;
+            }
+            // particles are not coloured when they carry no scalar
+            if let Some(&val) = particles.vals[index].get(self.scalar) {
+                temperatures[index] = val as f32;
+            }
+            // particles are shifted when some of them are removed
+            radii[index] = particles.radii[index] as f32;
+            collision_counts[index] = particles.flights[index].ncollisions as u32;
+        }
+    }
+}
+
+impl Collision {
+    /// Allocates the buffers shared with the JavaScript side.
+    fn wrap(simulator: Simulator) -> Collision {
+        let nitems: usize = simulator.get_particles().len();
+        let positions_f32 = vec![0f32; NDIMS * nitems];
+        let temperatures = vec![0f32; nitems];
+        let radii = vec![0f32; nitems];
+        let collision_counts = vec![0u32; nitems];
+        Collision {
+            simulator,
+            positions_f32,
+            temperatures,
+            radii,
+            collision_counts,
+            scalar: 0,
+        }
+    }
+}
+
+#[wasm_bindgen(start)]
+pub fn init() {}